@@ -19,76 +19,13 @@
 use std::env;
 use std::fs::File;
 
-use uom::{
-    fmt::DisplayStyle::Abbreviation,
-    si::{linear_number_density::per_inch, time::second},
-};
-
 use png_container::chunks::*;
 use png_container::reader::*;
 use png_container::types::*;
 
 fn print_chunk(first: &str, cd: &PngChunkData) {
-    println!("data={:?}", &cd);
-    match cd {
-        PngChunkData::Chrm { .. } => {
-            println!("{}white_coords={:?}", first, cd.chrm_white_coords());
-            println!("{}  red_coords={:?}", first, cd.chrm_red_coords());
-            println!("{}green_coords={:?}", first, cd.chrm_green_coords());
-            println!("{} blue_coords={:?}", first, cd.chrm_blue_coords());
-        }
-
-        PngChunkData::Gama { .. } => {
-            if let Some(gamma) = cd.gama_gamma() {
-                println!("{}gamma={}", first, gamma);
-            }
-        }
-
-        PngChunkData::Iccp { .. } => {
-            println!("{}profile={:?}", first, cd.iccp_profile());
-        }
-
-        PngChunkData::Ztxt { .. } => {
-            if let Some(string) = cd.ztxt_string() {
-                println!("{}string=\"{}\"", first, string);
-            }
-        }
-
-        PngChunkData::Itxt { .. } => {
-            if let Some(string) = cd.itxt_string() {
-                println!("{}string=\"{}\"", first, string);
-            }
-        }
-
-        PngChunkData::Phys { .. } => {
-            if let Some((xres, yres)) = cd.phys_res() {
-                println!(
-                    "{}pixels per inch={} × {}",
-                    first,
-                    xres.into_format_args(per_inch, Abbreviation),
-                    yres.into_format_args(per_inch, Abbreviation)
-                );
-            }
-        }
-
-        PngChunkData::Time { .. } => {
-            if let Some(time) = cd.time() {
-                println!("{}time={}", first, time);
-            }
-        }
-
-        PngChunkData::Fctl { .. } => {
-            if let Some(delay) = cd.fctl_delay() {
-                println!(
-                    "{}delay={}",
-                    first,
-                    delay.into_format_args(second, Abbreviation)
-                );
-            }
-        }
-
-        _ => (),
-    }
+    println!("{}data={:?}", first, cd);
+    println!("{}{:#}", first, cd);
 }
 
 fn main() -> std::io::Result<()> {
@@ -112,7 +49,7 @@ fn main() -> std::io::Result<()> {
         if let Ok(cd) = reader.read_chunk(c) {
             print_chunk("", &cd);
         }
-        println!("");
+        println!();
     }
 
     if reader.filetype == PngFileType::Apng {
@@ -134,7 +71,7 @@ fn main() -> std::io::Result<()> {
             if let Ok(cd) = reader.read_chunk(&c) {
                 print_chunk("", &cd);
             }
-            println!("");
+            println!();
         }
     }
 
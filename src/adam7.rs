@@ -0,0 +1,357 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Adam7 interlacing
+ *
+ * An interlaced image is stored as seven reduced images (passes), each holding every n'th pixel
+ * of every m'th row. Each pass is filtered separately and passes with no pixels are left out.
+ */
+
+use crate::chunks::*;
+
+/// Starting column, starting row, column step, and row step of each pass
+const PASS_GRID: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Size of the block each decoded pixel should cover for a preview after each pass
+const PREVIEW_BLOCKS: [(u32, u32); 7] = [(8, 8), (4, 8), (4, 4), (2, 4), (2, 2), (1, 2), (1, 1)];
+
+/// Geometry of one pass of an image
+///
+/// A non-interlaced image is a single pass, numbered 0, with steps of 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InterlacePass {
+    /// Pass number, 1 to 7 for Adam7
+    pub number: u8,
+
+    /// Column of the first pixel in the full image
+    pub x_start: u32,
+
+    /// Row of the first pixel in the full image
+    pub y_start: u32,
+
+    /// Distance between pixels in the full image
+    pub x_step: u32,
+
+    /// Distance between rows in the full image
+    pub y_step: u32,
+
+    /// Width of the pass in pixels
+    pub width: u32,
+
+    /// Height of the pass in rows
+    pub height: u32,
+
+    /// Number of bytes in each row, not including the filter type byte
+    pub line_size: usize,
+}
+
+impl InterlacePass {
+    /// The only pass of a non-interlaced image
+    pub(crate) fn whole_image(pixel_bits: u8, width: u32, height: u32) -> Self {
+        Self {
+            number: 0,
+            x_start: 0,
+            y_start: 0,
+            x_step: 1,
+            y_step: 1,
+            width,
+            height,
+            line_size: (width as usize * pixel_bits as usize).div_ceil(8),
+        }
+    }
+
+    /// Does this pass have no pixels, and therefore no data?
+    pub fn is_empty(&self) -> bool {
+        (self.width == 0) || (self.height == 0)
+    }
+}
+
+/// Adam7 geometry of an image
+#[derive(Clone, Copy, Debug)]
+pub struct Adam7 {
+    /// Width of the full image in pixels
+    pub width: u32,
+
+    /// Height of the full image in pixels
+    pub height: u32,
+
+    /// Bits per pixel
+    pub pixel_bits: u8,
+
+    /// The seven passes, some of which may be empty for small images
+    pub passes: [InterlacePass; 7],
+}
+
+impl Adam7 {
+    /// Constructor from the IHDR chunk
+    pub fn new(ihdr: &Ihdr) -> Self {
+        Self::with_size(ihdr, ihdr.width, ihdr.height)
+    }
+
+    /// Constructor for an image with different dimensions to the IHDR chunk, e.g. an APNG frame
+    pub fn with_size(ihdr: &Ihdr, width: u32, height: u32) -> Self {
+        let pixel_bits = ihdr.pixel_bits();
+        let passes = std::array::from_fn(|i| {
+            let (x_start, y_start, x_step, y_step) = PASS_GRID[i];
+            let pass_width = width.saturating_sub(x_start).div_ceil(x_step);
+            InterlacePass {
+                number: i as u8 + 1,
+                x_start,
+                y_start,
+                x_step,
+                y_step,
+                width: pass_width,
+                height: height.saturating_sub(y_start).div_ceil(y_step),
+                line_size: (pass_width as usize * pixel_bits as usize).div_ceil(8),
+            }
+        });
+
+        Self {
+            width,
+            height,
+            pixel_bits,
+            passes,
+        }
+    }
+
+    /// Number of bytes in a row of the full image
+    pub fn line_size(&self) -> usize {
+        (self.width as usize * self.pixel_bits as usize).div_ceil(8)
+    }
+
+    /// Size in bytes of the full image
+    pub fn image_size(&self) -> usize {
+        self.line_size() * self.height as usize
+    }
+
+    /// Total size in bytes of the unfiltered passes, not including filter type bytes
+    pub fn data_size(&self) -> usize {
+        self.passes
+            .iter()
+            .map(|pass| pass.line_size * pass.height as usize)
+            .sum()
+    }
+
+    /// Split the decompressed and unfiltered data of all passes into each pass's data
+    ///
+    /// Empty passes get empty slices.
+    pub fn split_passes<'a>(&self, data: &'a [u8]) -> Result<[&'a [u8]; 7], std::io::Error> {
+        if data.len() < self.data_size() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!(
+                    "PNG: Interlaced image data is too short ({} < {})",
+                    data.len(),
+                    self.data_size()
+                ),
+            ));
+        }
+
+        let mut rest = data;
+        Ok(std::array::from_fn(|i| {
+            let pass = &self.passes[i];
+            let (pass_data, tail) = rest.split_at(pass.line_size * pass.height as usize);
+            rest = tail;
+            pass_data
+        }))
+    }
+
+    /// Check that a pass belongs to this image, and that the full image buffer is big enough
+    fn check_pass(&self, pass: &InterlacePass, image: &[u8]) -> Result<(), std::io::Error> {
+        if !self.passes.contains(pass) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("PNG: Pass {} is not a pass of this image", pass.number),
+            ));
+        }
+
+        if image.len() < self.image_size() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "PNG: Image buffer is too small ({} < {})",
+                    image.len(),
+                    self.image_size()
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Scatter the pixels of one row of a pass into the full image
+    ///
+    /// `pass`: One of [passes](Self::passes).
+    /// `y`: Row number within the pass.
+    /// `row`: At least [line_size](InterlacePass::line_size) bytes of the pass.
+    /// `image`: The full image, [line_size()](Self::line_size) bytes per row.
+    ///
+    /// Returns an [InvalidInput](std::io::ErrorKind::InvalidInput) error if any of these are
+    /// out of range.
+    pub fn deinterlace_row(
+        &self,
+        pass: &InterlacePass,
+        y: u32,
+        row: &[u8],
+        image: &mut [u8],
+    ) -> Result<(), std::io::Error> {
+        self.check_pass(pass, image)?;
+        if (y >= pass.height) || (row.len() < pass.line_size) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "PNG: Row {} ({} bytes) is out of range for pass {}",
+                    y,
+                    row.len(),
+                    pass.number
+                ),
+            ));
+        }
+
+        let bits = self.pixel_bits as usize;
+        let line_size = self.line_size();
+        let image_y = (pass.y_start + y * pass.y_step) as usize;
+        let image_row = &mut image[image_y * line_size..(image_y + 1) * line_size];
+
+        let mut pixel = [0_u8; 8];
+        for x in 0..pass.width as usize {
+            read_pixel(row, x, bits, &mut pixel);
+            let image_x = pass.x_start as usize + x * pass.x_step as usize;
+            write_pixel(image_row, image_x, bits, &pixel);
+        }
+
+        Ok(())
+    }
+
+    /// Scatter the pixels of a whole pass into the full image
+    ///
+    /// Only complete rows of `data` are used.
+    pub fn deinterlace_pass(
+        &self,
+        pass: &InterlacePass,
+        data: &[u8],
+        image: &mut [u8],
+    ) -> Result<(), std::io::Error> {
+        if pass.line_size == 0 {
+            return Ok(());
+        }
+
+        for (y, row) in data
+            .chunks_exact(pass.line_size)
+            .take(pass.height as usize)
+            .enumerate()
+        {
+            self.deinterlace_row(pass, y as u32, row, image)?;
+        }
+
+        Ok(())
+    }
+
+    /// Scatter the pixels of all passes into a new full image
+    pub fn deinterlace(&self, data: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        let mut image = vec![0_u8; self.image_size()];
+        for (pass, pass_data) in self.passes.iter().zip(self.split_passes(data)?) {
+            self.deinterlace_pass(pass, pass_data, &mut image)?;
+        }
+
+        Ok(image)
+    }
+
+    /// Fill in the pixels not yet decoded after a pass, for a low-resolution preview
+    ///
+    /// Each decoded pixel is repeated over the block of pixels to its right and below that will
+    /// be filled in by later passes. The pixels from earlier passes must already be in `image`,
+    /// which must be at least [image_size()](Self::image_size) bytes.
+    pub fn fill_preview(
+        &self,
+        pass: &InterlacePass,
+        image: &mut [u8],
+    ) -> Result<(), std::io::Error> {
+        // The single pass of a non-interlaced image has nothing to fill in
+        if pass.number == 0 {
+            return Ok(());
+        }
+        self.check_pass(pass, image)?;
+
+        let (block_width, block_height) = PREVIEW_BLOCKS[pass.number as usize - 1];
+        let bits = self.pixel_bits as usize;
+        let line_size = self.line_size();
+
+        let mut pixel = [0_u8; 8];
+        for y in 0..self.height as usize {
+            let source_y = y - (y % block_height as usize);
+            for x in 0..self.width as usize {
+                let source_x = x - (x % block_width as usize);
+                if (source_x == x) && (source_y == y) {
+                    continue;
+                }
+
+                read_pixel(
+                    &image[source_y * line_size..(source_y + 1) * line_size],
+                    source_x,
+                    bits,
+                    &mut pixel,
+                );
+                write_pixel(
+                    &mut image[y * line_size..(y + 1) * line_size],
+                    x,
+                    bits,
+                    &pixel,
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Read a pixel from a packed row
+///
+/// Pixels of less than 8 bits are returned in the low bits of the first byte.
+fn read_pixel(row: &[u8], x: usize, bits: usize, pixel: &mut [u8; 8]) {
+    if bits >= 8 {
+        let bytes = bits / 8;
+        pixel[..bytes].copy_from_slice(&row[x * bytes..(x + 1) * bytes]);
+    } else {
+        let bit = x * bits;
+        let shift = 8 - bits - (bit % 8);
+        let mask = (1_u8 << bits) - 1;
+        pixel[0] = (row[bit / 8] >> shift) & mask;
+    }
+}
+
+/// Write a pixel into a packed row
+fn write_pixel(row: &mut [u8], x: usize, bits: usize, pixel: &[u8; 8]) {
+    if bits >= 8 {
+        let bytes = bits / 8;
+        row[x * bytes..(x + 1) * bytes].copy_from_slice(&pixel[..bytes]);
+    } else {
+        let bit = x * bits;
+        let shift = 8 - bits - (bit % 8);
+        let mask = ((1_u8 << bits) - 1) << shift;
+        row[bit / 8] = (row[bit / 8] & !mask) | ((pixel[0] << shift) & mask);
+    }
+}
@@ -0,0 +1,613 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Async PNG/APNG and JNG readers
+ *
+ * These mirror [PngReader](crate::reader::PngReader) and [JngReader](crate::jngreader::JngReader)
+ * over [tokio]'s [AsyncRead] and [AsyncSeek]. Chunk bodies are read into a buffer and then
+ * parsed with the same code as the synchronous readers.
+ *
+ * Requires the `tokio` feature.
+ */
+
+use std::collections::HashMap;
+use std::io::SeekFrom;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::chunks::*;
+use crate::error::Error;
+use crate::limits::*;
+use crate::reader::ApngFrame;
+use crate::registry::ChunkRegistry;
+use crate::types::*;
+
+/// Read the length and type of the chunk at a position
+async fn scan_chunk_at<R>(stream: &mut R, position: u64) -> std::io::Result<PngChunkRef>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    stream.seek(SeekFrom::Start(position)).await?;
+    let mut header = [0_u8; 8];
+    stream.read_exact(&mut header).await?;
+
+    PngChunkRef::from_stream_at(&mut header.as_slice(), position)
+}
+
+/// Read the data and CRC of a chunk into a buffer and parse it
+///
+/// The chunk length is checked against the limits before the buffer is allocated.
+async fn read_chunk_async<R>(
+    chunkref: &PngChunkRef,
+    stream: &mut R,
+    ihdr: Option<&Ihdr>,
+    registry: &ChunkRegistry,
+    limits: &Limits,
+) -> std::io::Result<PngChunkData>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    limits
+        .check_chunk_length(chunkref.length)
+        .map_err(|e| e.in_chunk(chunkref))?;
+    stream
+        .seek(SeekFrom::Start(chunkref.position + 4 + 4))
+        .await?;
+    let mut buf = vec![0_u8; chunkref.length as usize + 4];
+    stream.read_exact(&mut buf).await?;
+
+    chunkref.read_contents(&mut buf.as_slice(), ihdr, registry)
+}
+
+/// An async PNG/APNG file reader
+#[derive(Clone, Debug)]
+pub struct AsyncPngReader<R> {
+    /// Image file type
+    ///
+    /// PNG or APNG
+    pub filetype: PngFileType,
+
+    /// Image width in pixels
+    pub width: u32,
+
+    /// Image height in pixels
+    pub height: u32,
+
+    /// Image bit depth per pixel component
+    pub bit_depth: u8,
+
+    /// Image colour type
+    pub colour_type: PngColourType,
+
+    /// File stream we're reading from
+    pub stream: R,
+
+    /// The IHDR chunk data
+    pub ihdr: Option<Ihdr>,
+
+    /// User-defined chunk types to consult when reading chunks
+    pub registry: ChunkRegistry,
+
+    /// Resource limits for reading untrusted files
+    pub limits: Limits,
+
+    next_chunk_pos: u64,
+    chunk_count: u64,
+    scanned_to: u64,
+    text_size: u64,
+
+    in_header: bool,
+    first_frame_is_static: bool,
+}
+
+impl<R> AsyncPngReader<R>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    /// Constructor from an AsyncRead-able and AsyncSeek-able type
+    ///
+    /// This just checks the file signature. Use any of the scan_*() methods to read chunks.
+    pub async fn from_stream(mut stream: R) -> Result<Self, std::io::Error> {
+        // First check the signature
+        {
+            let mut signature = [0; 8];
+            stream.read_exact(&mut signature).await?;
+            if signature != [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a] {
+                return Err(Error::BadSignature { offset: 0 }.into());
+            }
+        }
+
+        Ok(AsyncPngReader {
+            filetype: PngFileType::Png,
+            width: 0,
+            height: 0,
+            bit_depth: 0,
+            colour_type: PngColourType::Greyscale,
+            stream,
+            ihdr: None,
+            registry: ChunkRegistry::default(),
+            limits: Limits::default(),
+            next_chunk_pos: 8,
+            chunk_count: 0,
+            scanned_to: 0,
+            text_size: 0,
+            in_header: true,
+            first_frame_is_static: false,
+        })
+    }
+
+    /// Scan all of the chunks in a PNG/APNG file
+    ///
+    /// If this is called after scan_header_chunks(), it will only return the following chunks.
+    pub async fn scan_all_chunks(&mut self) -> Result<Vec<PngChunkRef>, std::io::Error> {
+        let mut chunks = Vec::with_capacity(4);
+        loop {
+            let chunkref = self.scan_next_chunk().await?;
+            chunks.push(chunkref);
+            if chunkref.chunktype == *b"IEND" {
+                break;
+            }
+        }
+
+        Ok(chunks)
+    }
+
+    /// Scan chunks in a PNG/APNG file until the first IDAT chunk
+    pub async fn scan_header_chunks(&mut self) -> Result<Vec<PngChunkRef>, std::io::Error> {
+        let mut chunks = Vec::with_capacity(4);
+        loop {
+            let chunkref = self.scan_next_chunk().await?;
+            if chunkref.chunktype == *b"IDAT" {
+                self.next_chunk_pos = chunkref.position;
+                break;
+            }
+            chunks.push(chunkref);
+        }
+
+        Ok(chunks)
+    }
+
+    /// Scan chunks in a PNG/APNG file, returning a Vec of the chunks that match a closure
+    pub async fn scan_chunks_filtered<F>(
+        &mut self,
+        test: F,
+    ) -> Result<Vec<PngChunkRef>, std::io::Error>
+    where
+        F: Fn([u8; 4]) -> bool,
+    {
+        let mut chunks = Vec::new();
+        loop {
+            let chunkref = self.scan_next_chunk().await?;
+            if test(chunkref.chunktype) {
+                chunks.push(chunkref);
+            }
+            if chunkref.chunktype == *b"IEND" {
+                break;
+            }
+        }
+
+        Ok(chunks)
+    }
+
+    /// Scan the next chunk
+    pub async fn scan_next_chunk(&mut self) -> Result<PngChunkRef, std::io::Error> {
+        let chunkref = scan_chunk_at(&mut self.stream, self.next_chunk_pos).await?;
+
+        // Invalid chunk types for PNG/APNG files
+        if matches!(&chunkref.chunktype, b"JHDR" | b"JDAT" | b"JDAA" | b"JSEP") {
+            return Err(Error::DisallowedChunk {
+                chunktype: chunkref.chunktype,
+                offset: chunkref.position,
+                filetype: PngFileType::Png,
+            }
+            .into());
+        }
+
+        // Only count each chunk once, however many times the file is scanned
+        if chunkref.position >= self.scanned_to {
+            self.chunk_count += 1;
+            self.limits
+                .check_chunk_count(self.chunk_count)
+                .map_err(|e| e.in_chunk(&chunkref))?;
+            self.scanned_to = chunkref.position + 4 + 4 + chunkref.length as u64 + 4;
+        }
+
+        self.next_chunk_pos += 4 + 4 + chunkref.length as u64 + 4;
+
+        match &chunkref.chunktype {
+            b"IHDR" => {
+                // Fill in image metadata
+                if let PngChunkData::Ihdr(ihdr) = read_chunk_async(
+                    &chunkref,
+                    &mut self.stream,
+                    None,
+                    &self.registry,
+                    &self.limits,
+                )
+                .await?
+                {
+                    self.limits
+                        .check_image_size(ihdr.width, ihdr.height, ihdr.pixel_bits())
+                        .map_err(|e| e.in_chunk(&chunkref))?;
+                    self.ihdr = Some(ihdr);
+                    self.width = ihdr.width;
+                    self.height = ihdr.height;
+                    self.bit_depth = ihdr.bit_depth;
+                    self.colour_type = ihdr.colour_type;
+                }
+            }
+
+            b"IDAT" => {
+                self.in_header = false;
+            }
+
+            b"aCTL" | b"fdAT" => {
+                self.filetype = PngFileType::Apng;
+            }
+
+            b"fcTL" => {
+                self.filetype = PngFileType::Apng;
+                if self.in_header {
+                    self.first_frame_is_static = true;
+                }
+            }
+
+            _ => (),
+        }
+
+        Ok(chunkref)
+    }
+
+    /// Reset the position of the next chunk to scan back to the start of the file
+    pub fn reset_next_chunk_position(&mut self) {
+        self.next_chunk_pos = 8;
+        self.in_header = true;
+    }
+
+    /// Set the position of the next chunk to scan to a given chunk
+    pub fn set_next_chunk_position(&mut self, chunkref: &PngChunkRef) {
+        self.next_chunk_pos = chunkref.position;
+    }
+
+    /// Set the position of the next chunk to scan to after a given chunk
+    pub fn set_next_chunk_position_after(&mut self, chunkref: &PngChunkRef) {
+        self.next_chunk_pos = chunkref.position + 4 + 4 + chunkref.length as u64 + 4;
+    }
+
+    /// Read the chunk data after seeking to the start of its data
+    ///
+    /// Chunks longer than [max_chunk_length](Limits::max_chunk_length) are rejected before
+    /// anything is allocated.
+    pub async fn read_chunk(
+        &mut self,
+        chunkref: &PngChunkRef,
+    ) -> Result<PngChunkData, std::io::Error> {
+        read_chunk_async(
+            chunkref,
+            &mut self.stream,
+            self.ihdr.as_ref(),
+            &self.registry,
+            &self.limits,
+        )
+        .await
+    }
+
+    /// Get the string of a tEXt, zTXt, or iTXt chunk
+    ///
+    /// Decompressed text counts towards the [max_text_size](Limits::max_text_size) limit, which
+    /// is shared by all of the chunks in the file. Returns None for other chunk types.
+    pub fn text_string(&mut self, chunk: &PngChunkData) -> Result<Option<String>, std::io::Error> {
+        text_string(&self.limits, &mut self.text_size, chunk)
+    }
+
+    /// Get the decompressed profile of an iCCP chunk
+    ///
+    /// Returns None for other chunk types.
+    pub fn iccp_profile(&self, chunk: &PngChunkData) -> Result<Option<Vec<u8>>, std::io::Error> {
+        iccp_profile(&self.limits, chunk)
+    }
+
+    /// Read just the sequence number of an fcTL or fdAT chunk
+    async fn read_sequence_number(&mut self, chunkref: &PngChunkRef) -> std::io::Result<u32> {
+        if chunkref.length < 4 {
+            return Err(too_short(chunkref.chunktype));
+        }
+
+        self.stream
+            .seek(SeekFrom::Start(chunkref.position + 4 + 4))
+            .await?;
+        let mut buf4 = [0_u8; 4];
+        self.stream.read_exact(&mut buf4).await?;
+        Ok(u32::from_be_bytes(buf4))
+    }
+
+    pub async fn apng_scan_frames(&mut self) -> std::io::Result<Vec<ApngFrame>> {
+        let first_frame_is_static = self.first_frame_is_static;
+        let chunkrefs = self
+            .scan_chunks_filtered(|ct| {
+                (first_frame_is_static && ct == *b"IDAT") || ct == *b"fcTL" || ct == *b"fdAT"
+            })
+            .await?;
+
+        // Generate sequence numbers for IDAT chunks and read the fcTL/fdAT ones
+        let num_idats = chunkrefs
+            .iter()
+            .filter(|cr| cr.chunktype == *b"IDAT")
+            .count() as u32;
+        let mut idat_seq_num = 1;
+        let mut seq_nums = HashMap::new();
+        for chunkref in &chunkrefs {
+            let seq_num = if chunkref.chunktype == *b"IDAT" {
+                idat_seq_num += 1;
+                idat_seq_num - 1
+            } else {
+                let seq_num = self.read_sequence_number(chunkref).await?;
+                if self.first_frame_is_static && (seq_num > 0) {
+                    seq_num.saturating_add(num_idats)
+                } else {
+                    seq_num
+                }
+            };
+            seq_nums.insert(chunkref.position, seq_num);
+        }
+
+        // Sort chunks by their sequence number
+        let mut chunkrefs = chunkrefs;
+        chunkrefs.sort_by_key(|cr| seq_nums[&cr.position]);
+
+        // Group fcTL and fdAT chunks into frames
+        let mut frames = Vec::new();
+        for chunkref in chunkrefs {
+            if chunkref.chunktype == *b"fcTL" {
+                let chunk = self.read_chunk(&chunkref).await?;
+                if let PngChunkData::Fctl(fctl) = chunk {
+                    frames.push(ApngFrame {
+                        fctl: *fctl,
+                        dats: Vec::new(),
+                    });
+                }
+            } else {
+                if frames.is_empty() {
+                    return Err(Error::OrderingViolation {
+                        chunktype: chunkref.chunktype,
+                        offset: chunkref.position,
+                        message: "At least one fcTL chunk must go before fdAT chunks".to_string(),
+                    }
+                    .into());
+                }
+                let lasti = frames.len() - 1;
+                frames[lasti].dats.push(chunkref);
+            }
+        }
+
+        Ok(frames)
+    }
+}
+
+/// An async JNG file reader
+#[derive(Debug)]
+pub struct AsyncJngReader<R> {
+    /// Image width in pixels
+    pub width: u32,
+
+    /// Image height in pixels
+    pub height: u32,
+
+    /// Image colour type
+    pub colour_type: JngColourType,
+
+    /// File stream we're reading from
+    pub stream: R,
+
+    /// The JHDR chunk data
+    pub jhdr: Option<Jhdr>,
+
+    /// The IEND chunk
+    pub iend: PngChunkRef,
+
+    /// User-defined chunk types to consult when reading chunks
+    pub registry: ChunkRegistry,
+
+    /// Resource limits for reading untrusted files
+    pub limits: Limits,
+
+    next_chunk_pos: u64,
+    chunk_count: u64,
+    scanned_to: u64,
+    text_size: u64,
+}
+
+impl<R> AsyncJngReader<R>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    /// Constructor from an AsyncRead-able and AsyncSeek-able type
+    pub async fn from_stream(mut stream: R) -> Result<Self, std::io::Error> {
+        // First check the signature
+        {
+            let mut signature = [0; 8];
+            stream.read_exact(&mut signature).await?;
+            if signature != [0x8b, 0x4a, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a] {
+                return Err(Error::BadSignature { offset: 0 }.into());
+            }
+        }
+
+        Ok(AsyncJngReader {
+            width: 0,
+            height: 0,
+            colour_type: JngColourType::Greyscale,
+            stream,
+            jhdr: None,
+            iend: PngChunkRef::default(),
+            registry: ChunkRegistry::default(),
+            limits: Limits::default(),
+            next_chunk_pos: 8,
+            chunk_count: 0,
+            scanned_to: 0,
+            text_size: 0,
+        })
+    }
+
+    /// Scan all of the chunks in a JNG file
+    pub async fn scan_all_chunks(&mut self) -> Result<Vec<PngChunkRef>, std::io::Error> {
+        let mut chunks = Vec::new();
+        loop {
+            let chunkref = self.scan_next_chunk().await?;
+            chunks.push(chunkref);
+            if chunkref.chunktype == *b"IEND" {
+                break;
+            }
+        }
+
+        Ok(chunks)
+    }
+
+    /// Scan chunks in a JNG file until the first IDAT or JDAT chunk
+    pub async fn scan_header_chunks(&mut self) -> Result<Vec<PngChunkRef>, std::io::Error> {
+        let mut chunks = Vec::new();
+        loop {
+            let chunkref = self.scan_next_chunk().await?;
+            if chunkref.chunktype == *b"IDAT" || chunkref.chunktype == *b"JDAT" {
+                self.next_chunk_pos = chunkref.position;
+                break;
+            }
+            chunks.push(chunkref);
+        }
+
+        Ok(chunks)
+    }
+
+    /// Scan the next chunk
+    pub async fn scan_next_chunk(&mut self) -> Result<PngChunkRef, std::io::Error> {
+        let chunkref = scan_chunk_at(&mut self.stream, self.next_chunk_pos).await?;
+
+        // Invalid chunk types for JNG files
+        if matches!(
+            &chunkref.chunktype,
+            b"PLTE"
+                | b"hIST"
+                | b"pCAL"
+                | b"sBIT"
+                | b"sPLT"
+                | b"tRNS"
+                | b"fRAc"
+                | b"gIFg"
+                | b"gIFx"
+                | b"aCTL"
+                | b"fcTL"
+                | b"fdAT"
+        ) {
+            return Err(Error::DisallowedChunk {
+                chunktype: chunkref.chunktype,
+                offset: chunkref.position,
+                filetype: PngFileType::Jng,
+            }
+            .into());
+        }
+
+        // Only count each chunk once, however many times the file is scanned
+        if chunkref.position >= self.scanned_to {
+            self.chunk_count += 1;
+            self.limits
+                .check_chunk_count(self.chunk_count)
+                .map_err(|e| e.in_chunk(&chunkref))?;
+            self.scanned_to = chunkref.position + 4 + 4 + chunkref.length as u64 + 4;
+        }
+
+        self.next_chunk_pos += 4 + 4 + chunkref.length as u64 + 4;
+
+        match &chunkref.chunktype {
+            b"JHDR" => {
+                // Fill in image metadata
+                if let PngChunkData::Jhdr(jhdr) = read_chunk_async(
+                    &chunkref,
+                    &mut self.stream,
+                    None,
+                    &self.registry,
+                    &self.limits,
+                )
+                .await?
+                {
+                    self.limits
+                        .check_image_size(jhdr.width, jhdr.height, jhdr.pixel_bits())
+                        .map_err(|e| e.in_chunk(&chunkref))?;
+                    self.jhdr = Some(*jhdr);
+                    self.width = jhdr.width;
+                    self.height = jhdr.height;
+                    self.colour_type = jhdr.colour_type;
+                }
+            }
+
+            b"IEND" => {
+                self.iend = chunkref;
+            }
+
+            _ => (),
+        }
+
+        Ok(chunkref)
+    }
+
+    /// Reset the position of the next chunk to scan back to the start of the file
+    pub fn reset_next_chunk_position(&mut self) {
+        self.next_chunk_pos = 8;
+    }
+
+    /// Set the position of the next chunk to scan to a given chunk
+    pub fn set_next_chunk_position(&mut self, chunkref: &PngChunkRef) {
+        self.next_chunk_pos = chunkref.position;
+    }
+
+    /// Set the position of the next chunk to scan to after a given chunk
+    pub fn set_next_chunk_position_after(&mut self, chunkref: &PngChunkRef) {
+        self.next_chunk_pos = chunkref.position + 4 + 4 + chunkref.length as u64 + 4;
+    }
+
+    /// Read the chunk data after seeking to the start of its data
+    ///
+    /// Chunks longer than [max_chunk_length](Limits::max_chunk_length) are rejected before
+    /// anything is allocated.
+    pub async fn read_chunk(
+        &mut self,
+        chunkref: &PngChunkRef,
+    ) -> Result<PngChunkData, std::io::Error> {
+        read_chunk_async(
+            chunkref,
+            &mut self.stream,
+            None,
+            &self.registry,
+            &self.limits,
+        )
+        .await
+    }
+
+    /// Get the string of a tEXt, zTXt, or iTXt chunk
+    ///
+    /// Decompressed text counts towards the [max_text_size](Limits::max_text_size) limit, which
+    /// is shared by all of the chunks in the file. Returns None for other chunk types.
+    pub fn text_string(&mut self, chunk: &PngChunkData) -> Result<Option<String>, std::io::Error> {
+        text_string(&self.limits, &mut self.text_size, chunk)
+    }
+
+    /// Get the decompressed profile of an iCCP chunk
+    ///
+    /// Returns None for other chunk types.
+    pub fn iccp_profile(&self, chunk: &PngChunkData) -> Result<Option<Vec<u8>>, std::io::Error> {
+        iccp_profile(&self.limits, chunk)
+    }
+}
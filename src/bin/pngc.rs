@@ -0,0 +1,589 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! pngc: Inspect and edit PNG, APNG, and JNG files
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::process::ExitCode;
+
+use uom::si::time::second;
+
+use png_container::chunks::*;
+use png_container::error::Error;
+use png_container::jngreader::JngReader;
+use png_container::reader::PngReader;
+use png_container::rewrite::{ChunkSelector, RewriteOp};
+use png_container::strip::{StripPolicy, StripReport};
+use png_container::types::*;
+use png_container::validate::{Severity, is_valid_keyword, validate};
+
+const USAGE: &str = "\
+Usage: pngc <command> <file> [args...]
+
+Commands:
+  list <file>                       List chunks with their offsets, lengths, and CRC status
+  dump <file> [type...]             Show the decoded fields of chunks, optionally of some types
+  frames <file>                     Show the frames of an APNG file
+  text get <file> [keyword]         Show text chunks, or the strings of one keyword
+  text set <file> <keyword> <string> [-o <out>] [--ztxt | --itxt]
+                                    Add or replace a text chunk
+  strip <file> [--policy all|colour|metadata] [-o <out>]
+                                    Remove ancillary chunks (default policy: metadata)
+  extract <file> idat|jdat|jdaa|icc|exif <out>
+                                    Write a payload to a file, or '-' for stdout
+  validate <file>                   Check the file for errors
+
+Files are edited in place unless -o is given.
+
+Exit status:
+  0  Success
+  1  A check failed: bad CRCs, validation errors, or nothing found
+  2  Invalid command line
+  3  The file couldn't be read or written";
+
+/// Exit status when a check fails
+const EXIT_CHECK_FAILED: u8 = 1;
+
+/// Exit status for an invalid command line
+const EXIT_USAGE: u8 = 2;
+
+/// Exit status for I/O and format errors
+const EXIT_ERROR: u8 = 3;
+
+enum CliError {
+    Usage(String),
+    Io(std::io::Error),
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+type CliResult = Result<ExitCode, CliError>;
+
+fn usage<T>(message: &str) -> Result<T, CliError> {
+    Err(CliError::Usage(message.to_string()))
+}
+
+/// Remove an option and its value from the arguments
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, CliError> {
+    let Some(i) = args.iter().position(|a| a == name) else {
+        return Ok(None);
+    };
+    if i + 1 >= args.len() {
+        return usage(&format!("{} needs a value", name));
+    }
+
+    let value = args.remove(i + 1);
+    args.remove(i);
+    Ok(Some(value))
+}
+
+/// Remove a flag from the arguments
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// A reader for either type of file
+enum Reader {
+    Png(PngReader<BufReader<File>>),
+    Jng(JngReader<BufReader<File>>),
+}
+
+impl Reader {
+    /// Open a file, choosing the reader from its signature
+    fn open(path: &str) -> Result<Self, std::io::Error> {
+        let mut stream = BufReader::new(File::open(path)?);
+        let mut signature = [0_u8; 8];
+        stream.read_exact(&mut signature)?;
+        stream.seek(SeekFrom::Start(0))?;
+
+        if signature[1..4] == *b"JNG" {
+            Ok(Self::Jng(JngReader::from_stream(stream)?))
+        } else {
+            Ok(Self::Png(PngReader::from_stream(stream)?))
+        }
+    }
+
+    fn scan_all_chunks(&mut self) -> Result<Vec<PngChunkRef>, std::io::Error> {
+        match self {
+            Self::Png(reader) => {
+                reader.reset_next_chunk_position();
+                reader.scan_all_chunks()
+            }
+            Self::Jng(reader) => {
+                reader.reset_next_chunk_position();
+                reader.scan_all_chunks()
+            }
+        }
+    }
+
+    fn read_chunk(&mut self, chunkref: &PngChunkRef) -> Result<PngChunkData, std::io::Error> {
+        match self {
+            Self::Png(reader) => reader.read_chunk(chunkref),
+            Self::Jng(reader) => reader.read_chunk(chunkref),
+        }
+    }
+
+    /// Copy the data of a chunk to a stream, checking its CRC
+    fn copy_chunk_data<W>(&mut self, chunkref: &PngChunkRef, out: &mut W) -> std::io::Result<u64>
+    where
+        W: Write,
+    {
+        match self {
+            Self::Png(reader) => std::io::copy(&mut reader.chunk_body_reader(chunkref)?, out),
+            Self::Jng(reader) => std::io::copy(&mut reader.chunk_body_reader(chunkref)?, out),
+        }
+    }
+
+    fn text_string(&mut self, chunk: &PngChunkData) -> Result<Option<String>, std::io::Error> {
+        match self {
+            Self::Png(reader) => reader.text_string(chunk),
+            Self::Jng(reader) => reader.text_string(chunk),
+        }
+    }
+
+    fn iccp_profile(&self, chunk: &PngChunkData) -> Result<Option<Vec<u8>>, std::io::Error> {
+        match self {
+            Self::Png(reader) => reader.iccp_profile(chunk),
+            Self::Jng(reader) => reader.iccp_profile(chunk),
+        }
+    }
+
+    fn rewrite<W>(&mut self, ops: &[RewriteOp], out: W) -> Result<Vec<PngChunkRef>, std::io::Error>
+    where
+        W: Write,
+    {
+        match self {
+            Self::Png(reader) => reader.rewrite(ops, out),
+            Self::Jng(reader) => reader.rewrite(ops, out),
+        }
+    }
+
+    fn strip<W>(&mut self, policy: StripPolicy, out: W) -> Result<StripReport, std::io::Error>
+    where
+        W: Write,
+    {
+        match self {
+            Self::Png(reader) => reader.strip(policy, out),
+            Self::Jng(reader) => reader.strip(policy, out),
+        }
+    }
+}
+
+/// Check the CRC of a chunk, returning false if it doesn't match
+fn crc_ok(reader: &mut Reader, chunkref: &PngChunkRef) -> Result<bool, std::io::Error> {
+    match reader.copy_chunk_data(chunkref, &mut std::io::sink()) {
+        Ok(_) => Ok(true),
+        Err(e) if matches!(Error::from_io_error(&e), Some(Error::CrcMismatch { .. })) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write an edited copy of a file, to another file or in place
+fn write_output<F, T>(path: &str, out_path: Option<&str>, write: F) -> Result<T, std::io::Error>
+where
+    F: FnOnce(&mut Reader, &mut BufWriter<File>) -> Result<T, std::io::Error>,
+{
+    let mut reader = Reader::open(path)?;
+    let temp_path = format!("{}.pngc-tmp", out_path.unwrap_or(path));
+    let result = (|| {
+        let mut out = BufWriter::new(File::create(&temp_path)?);
+        let result = write(&mut reader, &mut out)?;
+        out.flush()?;
+        Ok(result)
+    })();
+    drop(reader);
+
+    match result {
+        Ok(result) => {
+            std::fs::rename(&temp_path, out_path.unwrap_or(path))?;
+            Ok(result)
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp_path);
+            Err(e)
+        }
+    }
+}
+
+fn list(path: &str) -> CliResult {
+    let mut reader = Reader::open(path)?;
+    let mut bad_crcs = 0;
+
+    println!("{:>10} {:>10}  Type  CRC  Properties", "Offset", "Length");
+    for chunkref in reader.scan_all_chunks()? {
+        let crc = if crc_ok(&mut reader, &chunkref)? {
+            "ok"
+        } else {
+            bad_crcs += 1;
+            "BAD"
+        };
+        println!(
+            "{:>10} {:>10}  {}  {:<3}  {}, {}, {}",
+            chunkref.position,
+            chunkref.length,
+            chunkref.type_str(),
+            crc,
+            if chunkref.is_ancillary() {
+                "ancillary"
+            } else {
+                "critical"
+            },
+            if chunkref.is_private() {
+                "private"
+            } else {
+                "public"
+            },
+            if chunkref.is_safe_to_copy() {
+                "safe to copy"
+            } else {
+                "unsafe to copy"
+            }
+        );
+    }
+
+    if bad_crcs > 0 {
+        eprintln!("{}: {} chunks with bad CRCs", path, bad_crcs);
+        return Ok(ExitCode::from(EXIT_CHECK_FAILED));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn dump(path: &str, chunktypes: &[String]) -> CliResult {
+    let mut reader = Reader::open(path)?;
+    let mut errors = 0;
+
+    for chunkref in reader.scan_all_chunks()? {
+        if !chunktypes.is_empty() && !chunktypes.iter().any(|ct| ct == chunkref.type_str()) {
+            continue;
+        }
+
+        // Only the length of image data is shown
+        if matches!(&chunkref.chunktype, b"IDAT" | b"fdAT" | b"JDAT" | b"JDAA") {
+            println!(
+                "{:>10}  {}: {} bytes",
+                chunkref.position,
+                chunkref.type_str(),
+                chunkref.length
+            );
+            continue;
+        }
+
+        match reader.read_chunk(&chunkref) {
+            Ok(chunk) => println!("{:>10}  {:#}", chunkref.position, chunk),
+
+            Err(e) => {
+                println!(
+                    "{:>10}  {}: error: {}",
+                    chunkref.position,
+                    chunkref.type_str(),
+                    e
+                );
+                errors += 1;
+            }
+        }
+    }
+
+    if errors > 0 {
+        return Ok(ExitCode::from(EXIT_CHECK_FAILED));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn frames(path: &str) -> CliResult {
+    let Reader::Png(mut reader) = Reader::open(path)? else {
+        eprintln!("{}: Not an APNG file", path);
+        return Ok(ExitCode::from(EXIT_CHECK_FAILED));
+    };
+
+    let chunks = reader.scan_all_chunks()?;
+    let Some(actl_ref) = chunks.iter().find(|c| c.chunktype == *b"acTL") else {
+        eprintln!("{}: Not an APNG file", path);
+        return Ok(ExitCode::from(EXIT_CHECK_FAILED));
+    };
+    if let PngChunkData::Actl(actl) = reader.read_chunk(actl_ref)? {
+        println!(
+            "{} frames, {} plays",
+            actl.num_frames,
+            match actl.num_plays {
+                0 => "infinite".to_string(),
+                n => n.to_string(),
+            }
+        );
+    }
+
+    reader.reset_next_chunk_position();
+    println!(
+        "{:>5} {:>5} {:>11} {:>11} {:>10} {:>10} {:>5} {:>10}",
+        "Frame", "Seq", "Size", "Offset", "Delay", "Dispose", "Blend", "Data"
+    );
+    for (i, frame) in reader.apng_scan_frames()?.iter().enumerate() {
+        let fctl = &frame.fctl;
+        let data: u64 = frame
+            .dats
+            .iter()
+            .map(|c| match &c.chunktype {
+                b"fdAT" => c.length.saturating_sub(4) as u64,
+                _ => c.length as u64,
+            })
+            .sum();
+        println!(
+            "{:>5} {:>5} {:>11} {:>11} {:>9}s {:>10} {:>5} {:>10}",
+            i,
+            fctl.sequence_number,
+            format!("{}x{}", fctl.width, fctl.height),
+            format!("{},{}", fctl.x_offset, fctl.y_offset),
+            fctl.delay().get::<second>(),
+            format!("{:?}", fctl.dispose_op),
+            format!("{:?}", fctl.blend_op),
+            data
+        );
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn text_get(path: &str, keyword: Option<&str>) -> CliResult {
+    let mut reader = Reader::open(path)?;
+    let mut found = false;
+
+    for chunkref in reader.scan_all_chunks()? {
+        if !matches!(&chunkref.chunktype, b"tEXt" | b"zTXt" | b"iTXt") {
+            continue;
+        }
+
+        let chunk = reader.read_chunk(&chunkref)?;
+        let (Some(chunk_keyword), Some(string)) =
+            (chunk.text_keyword(), reader.text_string(&chunk)?)
+        else {
+            continue;
+        };
+        match keyword {
+            Some(keyword) if keyword == chunk_keyword => println!("{}", string),
+            Some(_) => continue,
+            None => println!("{}: {}", chunk_keyword, string),
+        }
+        found = true;
+    }
+
+    if !found {
+        return Ok(ExitCode::from(EXIT_CHECK_FAILED));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn text_set(mut args: Vec<String>) -> CliResult {
+    let out_path = take_option(&mut args, "-o")?;
+    let ztxt = take_flag(&mut args, "--ztxt");
+    let itxt = take_flag(&mut args, "--itxt");
+    let [path, keyword, string] = args.as_slice() else {
+        return usage("text set needs a file, a keyword, and a string");
+    };
+    if !is_valid_keyword(keyword) {
+        return usage(&format!("Invalid keyword {:?}", keyword));
+    }
+
+    // tEXt and zTXt can only hold Latin-1
+    let latin1 = string.chars().all(|c| (c as u32) < 256);
+    let chunk: PngChunkData = if itxt || !latin1 {
+        Itxt::new(keyword, None, "", "", string).into()
+    } else if ztxt {
+        Ztxt::new(keyword, PngCompressionMethod::Zlib, string).into()
+    } else {
+        Text::new(keyword, string).into()
+    };
+
+    let ops = [RewriteOp::Replace(
+        ChunkSelector::Keyword(keyword.to_string()),
+        chunk,
+    )];
+    write_output(path, out_path.as_deref(), |reader, out| {
+        reader.rewrite(&ops, out)
+    })?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn strip(mut args: Vec<String>) -> CliResult {
+    let out_path = take_option(&mut args, "-o")?;
+    let policy = match take_option(&mut args, "--policy")?.as_deref() {
+        Some("all") => StripPolicy::All,
+        Some("colour") | Some("color") => StripPolicy::AllExceptColour,
+        Some("metadata") | None => StripPolicy::Metadata,
+        Some(policy) => return usage(&format!("Unknown policy {:?}", policy)),
+    };
+    let [path] = args.as_slice() else {
+        return usage("strip needs a file");
+    };
+
+    let report = write_output(path, out_path.as_deref(), |reader, out| {
+        reader.strip(policy, out)
+    })?;
+    for chunkref in &report.removed {
+        println!(
+            "Removed {} at {}, {} bytes",
+            chunkref.type_str(),
+            chunkref.position,
+            chunkref.length
+        );
+    }
+    println!("{} bytes saved", report.bytes_saved);
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn extract(path: &str, what: &str, out_path: &str) -> CliResult {
+    let chunktype = match what {
+        "idat" => *b"IDAT",
+        "jdat" => *b"JDAT",
+        "jdaa" => *b"JDAA",
+        "icc" => *b"iCCP",
+        "exif" => *b"eXIf",
+        _ => return usage(&format!("Unknown payload {:?}", what)),
+    };
+
+    let mut reader = Reader::open(path)?;
+    let chunks = reader
+        .scan_all_chunks()?
+        .into_iter()
+        .filter(|c| c.chunktype == chunktype)
+        .collect::<Vec<PngChunkRef>>();
+    if chunks.is_empty() {
+        eprintln!(
+            "{}: No {} chunks",
+            path,
+            String::from_utf8_lossy(&chunktype)
+        );
+        return Ok(ExitCode::from(EXIT_CHECK_FAILED));
+    }
+
+    let mut out: Box<dyn Write> = if out_path == "-" {
+        Box::new(std::io::stdout().lock())
+    } else {
+        Box::new(BufWriter::new(File::create(out_path)?))
+    };
+    match what {
+        "icc" => {
+            let chunk = reader.read_chunk(&chunks[0])?;
+            if let Some(profile) = reader.iccp_profile(&chunk)? {
+                out.write_all(&profile)?;
+            }
+        }
+
+        _ => {
+            for chunkref in &chunks {
+                reader.copy_chunk_data(chunkref, &mut out)?;
+            }
+        }
+    }
+    out.flush()?;
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn validate_file(path: &str) -> CliResult {
+    let (errors, warnings) = match Reader::open(path)? {
+        Reader::Png(mut reader) => {
+            let findings = validate(&mut reader)?;
+            for finding in &findings {
+                println!("{}", finding);
+            }
+            let errors = findings
+                .iter()
+                .filter(|f| f.severity == Severity::Error)
+                .count();
+            (errors, findings.len() - errors)
+        }
+
+        // Only the chunks themselves can be checked in JNG files
+        mut reader @ Reader::Jng(_) => {
+            let mut errors = 0;
+            for chunkref in reader.scan_all_chunks()? {
+                let result = match crc_ok(&mut reader, &chunkref)? {
+                    true => reader.read_chunk(&chunkref).map(|_| ()),
+                    false => Err(std::io::Error::other(format!(
+                        "CRC of {} chunk at {} doesn't match",
+                        chunkref.type_str(),
+                        chunkref.position
+                    ))),
+                };
+                if let Err(e) = result {
+                    println!("error: {}", e);
+                    errors += 1;
+                }
+            }
+            (errors, 0)
+        }
+    };
+
+    println!("{}: {} errors, {} warnings", path, errors, warnings);
+    if errors > 0 {
+        return Ok(ExitCode::from(EXIT_CHECK_FAILED));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run(mut args: Vec<String>) -> CliResult {
+    if args.is_empty() {
+        return usage("No command");
+    }
+    let command = args.remove(0);
+
+    match (command.as_str(), args.as_slice()) {
+        ("list", [path]) => list(path),
+        ("dump", [path, chunktypes @ ..]) => dump(path, chunktypes),
+        ("frames", [path]) => frames(path),
+        ("text", [sub, path]) if sub == "get" => text_get(path, None),
+        ("text", [sub, path, keyword]) if sub == "get" => text_get(path, Some(keyword)),
+        ("text", [sub, ..]) if sub == "set" => text_set(args[1..].to_vec()),
+        ("strip", _) => strip(args),
+        ("extract", [path, what, out_path]) => extract(path, what, out_path),
+        ("validate", [path]) => validate_file(path),
+        ("help" | "--help" | "-h", _) => {
+            println!("{}", USAGE);
+            Ok(ExitCode::SUCCESS)
+        }
+        _ => usage(&format!("Invalid arguments for {:?}", command)),
+    }
+}
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(code) => code,
+
+        Err(CliError::Usage(message)) => {
+            eprintln!("pngc: {}\n\n{}", message, USAGE);
+            ExitCode::from(EXIT_USAGE)
+        }
+
+        Err(CliError::Io(e)) => {
+            eprintln!("pngc: {}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
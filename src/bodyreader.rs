@@ -0,0 +1,225 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Streaming readers for chunk data
+ */
+
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::chunks::*;
+use crate::crc::*;
+use crate::error::Error;
+
+/// Reads the raw data of a chunk without holding all of it in memory
+///
+/// The CRC is updated as data passes through and checked against the stored value once the end
+/// of the chunk data is reached. A mismatch is returned as an [InvalidData](std::io::ErrorKind::InvalidData)
+/// error in place of EOF.
+#[derive(Debug)]
+pub struct ChunkBodyReader<R> {
+    stream: R,
+    chunkref: PngChunkRef,
+    remaining: u32,
+    data_crc: CRC,
+    crc_checked: bool,
+}
+
+impl<R> ChunkBodyReader<R>
+where
+    R: Read,
+{
+    /// Constructor from a stream that is already at the start of the chunk data
+    pub fn new(stream: R, chunkref: PngChunkRef) -> Self {
+        let mut reader = Self {
+            stream,
+            chunkref,
+            remaining: 0,
+            data_crc: CRC::new(),
+            crc_checked: true,
+        };
+        reader.start(chunkref);
+
+        reader
+    }
+
+    /// Start reading the data of another chunk
+    ///
+    /// The stream must already be at the start of its data.
+    fn start(&mut self, chunkref: PngChunkRef) {
+        self.chunkref = chunkref;
+        self.remaining = chunkref.length;
+        self.data_crc = CRC::new();
+        self.data_crc.consume(&chunkref.chunktype);
+        self.crc_checked = false;
+    }
+
+    /// The chunk being read
+    pub fn chunkref(&self) -> &PngChunkRef {
+        &self.chunkref
+    }
+
+    /// Number of data bytes left to read
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// Unwrap the underlying stream
+    ///
+    /// If the CRC has been checked, the stream is at the start of the next chunk.
+    pub fn into_inner(self) -> R {
+        self.stream
+    }
+
+    /// Read the stored CRC and compare it to the computed one
+    fn check_crc(&mut self) -> std::io::Result<()> {
+        self.crc_checked = true;
+
+        let mut buf4 = [0_u8; 4];
+        self.stream
+            .read_exact(&mut buf4)
+            .map_err(|e| Error::in_chunk(e, &self.chunkref))?;
+        let crc = u32::from_be_bytes(buf4);
+        let computed = self.data_crc.value();
+        if crc != computed {
+            return Err(Error::CrcMismatch {
+                chunktype: self.chunkref.chunktype,
+                offset: self.chunkref.position,
+                expected: crc,
+                actual: computed,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> Read for ChunkBodyReader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 {
+            if !self.crc_checked {
+                self.check_crc()?;
+            }
+            return Ok(0);
+        }
+
+        let max = buf.len().min(self.remaining as usize);
+        let n = self.stream.read(&mut buf[..max])?;
+        if n == 0 && max > 0 {
+            return Err(Error::TruncatedData {
+                chunktype: Some(self.chunkref.chunktype),
+                offset: self.chunkref.position,
+                message: format!(
+                    "PNG: Stream ended with {} bytes of {} chunk data left",
+                    self.remaining,
+                    self.chunkref.type_str()
+                ),
+            }
+            .into());
+        }
+
+        self.data_crc.consume(&buf[..n]);
+        self.remaining -= n as u32;
+
+        Ok(n)
+    }
+}
+
+/// Reads the concatenated image data of a sequence of IDAT and/or fdAT chunks
+///
+/// The sequence numbers of fdAT chunks are skipped, so the result is a single zlib stream. The
+/// CRC of each chunk is checked as its end is reached.
+#[derive(Debug)]
+pub struct ImageDataReader<R> {
+    body: ChunkBodyReader<R>,
+    chunks: std::vec::IntoIter<PngChunkRef>,
+}
+
+impl<R> ImageDataReader<R>
+where
+    R: Read + Seek,
+{
+    /// Constructor from a stream and the chunks to read, in order
+    pub fn new(stream: R, chunks: Vec<PngChunkRef>) -> Self {
+        Self {
+            body: ChunkBodyReader {
+                stream,
+                chunkref: PngChunkRef::default(),
+                remaining: 0,
+                data_crc: CRC::new(),
+                crc_checked: true,
+            },
+            chunks: chunks.into_iter(),
+        }
+    }
+
+    /// Unwrap the underlying stream
+    pub fn into_inner(self) -> R {
+        self.body.stream
+    }
+
+    /// Seek to the next chunk and skip any sequence number
+    fn start_chunk(&mut self, chunkref: PngChunkRef) -> std::io::Result<()> {
+        self.body
+            .stream
+            .seek(SeekFrom::Start(chunkref.position + 4 + 4))?;
+        self.body.start(chunkref);
+
+        match chunkref.chunktype {
+            Idat::TYPE => Ok(()),
+
+            Fdat::TYPE => {
+                let mut buf4 = [0_u8; 4];
+                self.body.read_exact(&mut buf4)
+            }
+
+            _ => Err(Error::InvalidFieldValue {
+                chunktype: chunkref.chunktype,
+                offset: chunkref.position,
+                message: format!("PNG: {} chunk is not an IDAT or fdAT", chunkref.type_str()),
+            }
+            .into()),
+        }
+    }
+}
+
+impl<R> Read for ImageDataReader<R>
+where
+    R: Read + Seek,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let n = self.body.read(buf)?;
+            if n > 0 {
+                return Ok(n);
+            }
+
+            match self.chunks.next() {
+                Some(chunkref) => self.start_chunk(chunkref)?,
+                None => return Ok(0),
+            }
+        }
+    }
+}
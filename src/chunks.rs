@@ -17,12 +17,100 @@
 */
 
 /*! PNG chunks
+ *
+ * Every chunk's data implements [Display](std::fmt::Display), as a one-line summary with decoded
+ * values, e.g. gamma as a float, and pHYs in DPI. Long strings are truncated. The alternate form,
+ * `{:#}`, adds raw values and shows whole strings, palettes, and tables. Compressed text and
+ * profiles are only decompressed up to the default [Limits](crate::limits::Limits).
  */
 
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::slice::Iter;
 use std::str;
 
+use crate::crc::*;
+use crate::error::Error;
+use crate::registry::{ChunkRegistry, CustomChunk};
+
+/// Implement [ChunkCodec](crate::registry::ChunkCodec) for a chunk struct using its inherent
+/// `TYPE`, `from_contents_stream()`, `length()`/`LENGTH`, and `write_contents()`
+macro_rules! impl_chunk_codec {
+    ($chunk:ty, fixed) => {
+        impl crate::registry::ChunkCodec for $chunk {
+            const TYPE: [u8; 4] = <$chunk>::TYPE;
+
+            fn from_contents_stream<R>(
+                stream: &mut R,
+                length: u32,
+                data_crc: Option<&mut crate::crc::CRC>,
+            ) -> std::io::Result<Self>
+            where
+                R: std::io::Read,
+            {
+                if length != <$chunk>::LENGTH {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "PNG: Invalid length of {} chunk ({})",
+                            String::from_utf8_lossy(&<$chunk>::TYPE),
+                            length
+                        ),
+                    ));
+                }
+
+                <$chunk>::from_contents_stream(stream, data_crc)
+            }
+
+            fn length(&self) -> u32 {
+                <$chunk>::LENGTH
+            }
+
+            fn write_contents<W>(
+                &self,
+                stream: &mut W,
+                data_crc: Option<&mut crate::crc::CRC>,
+            ) -> std::io::Result<()>
+            where
+                W: std::io::Write,
+            {
+                <$chunk>::write_contents(self, stream, data_crc)
+            }
+        }
+    };
+
+    ($chunk:ty) => {
+        impl crate::registry::ChunkCodec for $chunk {
+            const TYPE: [u8; 4] = <$chunk>::TYPE;
+
+            fn from_contents_stream<R>(
+                stream: &mut R,
+                length: u32,
+                data_crc: Option<&mut crate::crc::CRC>,
+            ) -> std::io::Result<Self>
+            where
+                R: std::io::Read,
+            {
+                <$chunk>::from_contents_stream(stream, length, data_crc)
+            }
+
+            fn length(&self) -> u32 {
+                <$chunk>::length(self)
+            }
+
+            fn write_contents<W>(
+                &self,
+                stream: &mut W,
+                data_crc: Option<&mut crate::crc::CRC>,
+            ) -> std::io::Result<()>
+            where
+                W: std::io::Write,
+            {
+                <$chunk>::write_contents(self, stream, data_crc)
+            }
+        }
+    };
+}
+
 pub mod animation;
 pub mod apple;
 pub mod colour_space;
@@ -40,10 +128,9 @@ pub use crate::chunks::{
     public::*, text::*, time::*, transparency::*,
 };
 
-use crate::crc::*;
-
 /// Enum of PNG chunk types and the data they hold
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PngChunkData {
     // Critical chunks
     /// Image header
@@ -170,6 +257,30 @@ pub enum PngChunkData {
 
     /// Apple's iDOT chunk to allow parallel decoding?
     Idot(Box<Idot>),
+
+    /// Any chunk type that isn't handled above, kept as raw bytes
+    ///
+    /// Whether one of these can be copied into a modified file can be decided from the
+    /// [PngChunkRef::is_safe_to_copy()] property of its chunk reference.
+    Unknown {
+        /// Chunk type
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::chunktype"))]
+        chunktype: [u8; 4],
+
+        /// Raw chunk data
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
+        data: Vec<u8>,
+    },
+
+    /// A user-defined chunk type from a [ChunkRegistry]
+    ///
+    /// With the `serde` feature, these are serialized with their chunk type and raw data, and
+    /// can't be deserialized.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serialize::custom", skip_deserializing)
+    )]
+    Custom(Box<dyn CustomChunk>),
 }
 
 impl PngChunkData {
@@ -188,15 +299,18 @@ impl PngChunkData {
         }
     }
 
-    /// Write a chunk to a write-able stream, returning a chunk reference
-    pub fn to_stream<W>(&self, stream: &mut W) -> std::io::Result<PngChunkRef>
-    where
-        W: Write + Seek,
-    {
-        let position = stream.stream_position()?;
+    /// Chunk type
+    pub fn chunktype(&self) -> [u8; 4] {
+        self.length_and_type().1
+    }
 
-        // First get the chunk length and type
-        let (length, chunktype) = match self {
+    /// Length of the chunk contents
+    pub fn length(&self) -> u32 {
+        self.length_and_type().0
+    }
+
+    fn length_and_type(&self) -> (u32, [u8; 4]) {
+        match self {
             PngChunkData::Ihdr(_) => (Ihdr::LENGTH, Ihdr::TYPE),
             PngChunkData::Plte(plte) => (plte.length(), Plte::TYPE),
             PngChunkData::Idat(idat) => (idat.length(), Idat::TYPE),
@@ -237,7 +351,32 @@ impl PngChunkData {
             PngChunkData::Ornt(_) => (Ornt::LENGTH, Ornt::TYPE),
 
             PngChunkData::Idot(idot) => (idot.length(), Idot::TYPE),
-        };
+
+            PngChunkData::Unknown { chunktype, data } => (data.len() as u32, *chunktype),
+            PngChunkData::Custom(custom) => (custom.contents_length(), custom.chunk_type()),
+        }
+    }
+
+    /// Write a chunk to a write-able stream, returning a chunk reference
+    pub fn to_stream<W>(&self, stream: &mut W) -> std::io::Result<PngChunkRef>
+    where
+        W: Write + Seek,
+    {
+        let position = stream.stream_position()?;
+        self.to_stream_at(stream, position)
+    }
+
+    /// Write a chunk to a write-able stream that can't report its own position, returning a
+    /// chunk reference
+    ///
+    /// `position`: The position of the chunk in the stream, as tracked by the caller, e.g. with
+    /// a [CountingWriter](crate::countingwriter::CountingWriter).
+    pub fn to_stream_at<W>(&self, stream: &mut W, position: u64) -> std::io::Result<PngChunkRef>
+    where
+        W: Write,
+    {
+        // First get the chunk length and type
+        let (length, chunktype) = self.length_and_type();
 
         // Write the chunk length and type
         stream.write_all(&length.to_be_bytes())?;
@@ -290,6 +429,12 @@ impl PngChunkData {
             PngChunkData::Ornt(ornt) => ornt.write_contents(stream, Some(&mut data_crc))?,
 
             PngChunkData::Idot(idot) => idot.write_contents(stream, Some(&mut data_crc))?,
+
+            PngChunkData::Unknown { data, .. } => {
+                stream.write_all(data)?;
+                data_crc.consume(data);
+            }
+            PngChunkData::Custom(custom) => custom.write_dyn(stream, Some(&mut data_crc))?,
         }
 
         // Now write the CRC
@@ -305,6 +450,7 @@ impl PngChunkData {
 
 /// Reference to a chunk in a PNG file
 #[derive(Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PngChunkRef {
     /// The position in the stream/file for this chunk
     pub position: u64,
@@ -313,9 +459,109 @@ pub struct PngChunkRef {
     pub length: u32,
 
     /// Chunk type
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::chunktype"))]
     pub chunktype: [u8; 4],
 }
 
+impl std::fmt::Display for PngChunkData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.chunktype()))?;
+
+        let data: &dyn std::fmt::Display = match self {
+            PngChunkData::Ihdr(ihdr) => ihdr,
+            PngChunkData::Plte(plte) => plte,
+            PngChunkData::Idat(idat) => idat,
+            PngChunkData::Iend | PngChunkData::Jsep => return Ok(()),
+            PngChunkData::Trns(trns) => trns,
+            PngChunkData::Chrm(chrm) => chrm,
+            PngChunkData::Gama(gama) => gama,
+            PngChunkData::Iccp(iccp) => iccp,
+            PngChunkData::Sbit(sbit) => sbit,
+            PngChunkData::Srgb(srgb) => srgb,
+            PngChunkData::Cicp(cicp) => cicp,
+            PngChunkData::Mdcv(mdcv) => mdcv,
+            PngChunkData::Clli(clli) => clli,
+            PngChunkData::Text(text) => text,
+            PngChunkData::Ztxt(ztxt) => ztxt,
+            PngChunkData::Itxt(itxt) => itxt,
+            PngChunkData::Bkgd(bkgd) => bkgd,
+            PngChunkData::Hist(hist) => hist,
+            PngChunkData::Phys(phys) => phys,
+            PngChunkData::Splt(splt) => splt,
+            PngChunkData::Exif(exif) => exif,
+            PngChunkData::Time(time) => time,
+            PngChunkData::Actl(actl) => actl,
+            PngChunkData::Fctl(fctl) => fctl,
+            PngChunkData::Fdat(fdat) => fdat,
+            PngChunkData::Offs(offs) => offs,
+            PngChunkData::Pcal(pcal) => pcal,
+            PngChunkData::Scal(scal) => scal,
+            PngChunkData::Gifg(gifg) => gifg,
+            PngChunkData::Gifx(gifx) => gifx,
+            PngChunkData::Ster(ster) => ster,
+            PngChunkData::Jhdr(jhdr) => jhdr,
+            PngChunkData::Jdat(jdat) => jdat,
+            PngChunkData::Jdaa(jdaa) => jdaa,
+            PngChunkData::Canv(canv) => canv,
+            PngChunkData::Vpag(vpag) => vpag,
+            PngChunkData::Ornt(ornt) => ornt,
+            PngChunkData::Idot(idot) => idot,
+
+            PngChunkData::Unknown { data, .. } => {
+                write!(f, ": ")?;
+                return fmt_bytes(f, data);
+            }
+
+            // Custom chunks only have to implement Debug
+            PngChunkData::Custom(custom) => {
+                return if f.alternate() {
+                    write!(f, ": {:#?}", custom)
+                } else {
+                    write!(f, ": {:?}", custom)
+                };
+            }
+        };
+
+        write!(f, ": ")?;
+        data.fmt(f)
+    }
+}
+
+/// Number of characters of strings shown by the normal [Display](std::fmt::Display) form
+const DISPLAY_STRING_LENGTH: usize = 60;
+
+/// Number of bytes of binary data shown by the alternate [Display](std::fmt::Display) form
+const DISPLAY_BYTES_LENGTH: usize = 16;
+
+/// Display a quoted string, truncated unless the alternate form is used
+fn fmt_string(f: &mut std::fmt::Formatter<'_>, string: &str) -> std::fmt::Result {
+    if f.alternate() || (string.chars().count() <= DISPLAY_STRING_LENGTH) {
+        return write!(f, "{:?}", string);
+    }
+
+    let truncated = string
+        .chars()
+        .take(DISPLAY_STRING_LENGTH)
+        .collect::<String>();
+    write!(f, "{:?}…", truncated)
+}
+
+/// Display the length of binary data, and the first bytes in hex if the alternate form is used
+fn fmt_bytes(f: &mut std::fmt::Formatter<'_>, data: &[u8]) -> std::fmt::Result {
+    write!(f, "{} bytes", data.len())?;
+    if f.alternate() && !data.is_empty() {
+        write!(f, ":")?;
+        for byte in data.iter().take(DISPLAY_BYTES_LENGTH) {
+            write!(f, " {:02x}", byte)?;
+        }
+        if data.len() > DISPLAY_BYTES_LENGTH {
+            write!(f, " …")?;
+        }
+    }
+
+    Ok(())
+}
+
 fn find_null(bytes: &[u8]) -> usize {
     bytes
         .iter()
@@ -323,6 +569,56 @@ fn find_null(bytes: &[u8]) -> usize {
         .unwrap_or(bytes.len())
 }
 
+/// Find a null separator that must be present
+fn require_null(bytes: &[u8], chunktype: [u8; 4]) -> std::io::Result<usize> {
+    bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or_else(|| invalid_contents(chunktype, "missing null separator"))
+}
+
+/// Error for chunk data that can't be parsed
+fn invalid_contents(chunktype: [u8; 4], reason: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!(
+            "PNG: Invalid contents of {} chunk ({})",
+            str::from_utf8(&chunktype).unwrap_or(""),
+            reason
+        ),
+    )
+}
+
+/// Error for chunk data that is too short for the fields it must contain
+pub(crate) fn too_short(chunktype: [u8; 4]) -> std::io::Error {
+    invalid_contents(chunktype, "too short")
+}
+
+/// Required length of chunk types that have a fixed length
+fn fixed_length(chunktype: [u8; 4]) -> Option<u32> {
+    match chunktype {
+        Ihdr::TYPE => Some(Ihdr::LENGTH),
+        Gama::TYPE => Some(Gama::LENGTH),
+        Chrm::TYPE => Some(Chrm::LENGTH),
+        Srgb::TYPE => Some(Srgb::LENGTH),
+        Cicp::TYPE => Some(Cicp::LENGTH),
+        Mdcv::TYPE => Some(Mdcv::LENGTH),
+        Clli::TYPE => Some(Clli::LENGTH),
+        Phys::TYPE => Some(Phys::LENGTH),
+        Time::TYPE => Some(Time::LENGTH),
+        Actl::TYPE => Some(Actl::LENGTH),
+        Fctl::TYPE => Some(Fctl::LENGTH),
+        Offs::TYPE => Some(Offs::LENGTH),
+        Gifg::TYPE => Some(Gifg::LENGTH),
+        Ster::TYPE => Some(Ster::LENGTH),
+        Jhdr::TYPE => Some(Jhdr::LENGTH),
+        Canv::TYPE => Some(Canv::LENGTH),
+        Vpag::TYPE => Some(Vpag::LENGTH),
+        Ornt::TYPE => Some(Ornt::LENGTH),
+        _ => None,
+    }
+}
+
 impl PngChunkRef {
     /// Read the length and type of a chunk from a [Read]'able stream to make a chunk reference
     ///
@@ -332,7 +628,17 @@ impl PngChunkRef {
         R: Read + Seek,
     {
         let position = stream.stream_position()?;
+        Self::from_stream_at(stream, position)
+    }
 
+    /// Read the length and type of a chunk from a [Read]'able stream that can't report its own
+    /// position
+    ///
+    /// `position`: The position of the chunk in the stream, as tracked by the caller.
+    pub fn from_stream_at<R>(stream: &mut R, position: u64) -> Result<Self, std::io::Error>
+    where
+        R: Read,
+    {
         let mut buf4 = [0_u8; 4];
         stream.read_exact(&mut buf4)?;
         let length = u32::from_be_bytes(buf4);
@@ -388,6 +694,8 @@ impl PngChunkRef {
         R: Read + Seek,
     {
         match &self.chunktype {
+            b"fcTL" | b"fdAT" if self.length < 4 => Err(too_short(self.chunktype)),
+
             b"fcTL" | b"fdAT" => {
                 stream.seek(SeekFrom::Start(self.position + 4 + 4))?;
                 let mut buf4 = [0_u8; 4];
@@ -395,10 +703,12 @@ impl PngChunkRef {
                 Ok(u32::from_be_bytes(buf4))
             }
 
-            _ => Err(std::io::Error::other(format!(
-                "PNG: Chunk type ({:?}) is not an fcTL or fdAT",
-                self.chunktype
-            ))),
+            _ => Err(Error::InvalidFieldValue {
+                chunktype: self.chunktype,
+                offset: self.position,
+                message: format!("PNG: {} chunk is not an fcTL or fdAT", self.type_str()),
+            }
+            .into()),
         }
     }
 
@@ -406,36 +716,137 @@ impl PngChunkRef {
     ///
     /// `ihdr`: The IHDR chunk, only used for tRNS, sBIT, and bKGD chunks for the colour_type value.
     /// This also checks the chunk CRC value.
+    ///
+    /// Chunk types that aren't recognised are returned as [PngChunkData::Unknown] with their raw
+    /// data, so that they can be written back out unchanged.
     pub fn read_chunk<R>(
         &self,
         stream: &mut R,
         ihdr: Option<&Ihdr>,
     ) -> Result<PngChunkData, std::io::Error>
+    where
+        R: Read + Seek,
+    {
+        self.read_chunk_with_registry(stream, ihdr, &ChunkRegistry::default())
+    }
+
+    /// Read the chunk data and parse it into a PngChunkData enum, consulting a registry of
+    /// user-defined chunk types first
+    pub fn read_chunk_with_registry<R>(
+        &self,
+        stream: &mut R,
+        ihdr: Option<&Ihdr>,
+        registry: &ChunkRegistry,
+    ) -> Result<PngChunkData, std::io::Error>
     where
         R: Read + Seek,
     {
         stream.seek(SeekFrom::Start(self.position + 4 + 4))?;
+        self.read_contents(stream, ihdr, registry)
+    }
+
+    /// Read the chunk data and CRC from a stream that is already at the start of the chunk data
+    ///
+    /// This doesn't need to seek, so it can be used on pipes and other forward-only streams.
+    /// The stream is left at the start of the next chunk.
+    pub fn read_contents<R>(
+        &self,
+        stream: &mut R,
+        ihdr: Option<&Ihdr>,
+        registry: &ChunkRegistry,
+    ) -> Result<PngChunkData, std::io::Error>
+    where
+        R: Read,
+    {
+        if let Some(length) = fixed_length(self.chunktype)
+            && (self.length != length)
+            && !registry.is_registered(self.chunktype)
+        {
+            return Err(Error::InvalidFieldValue {
+                chunktype: self.chunktype,
+                offset: self.position,
+                message: format!(
+                    "PNG: Invalid length of {} chunk ({})",
+                    self.type_str(),
+                    self.length
+                ),
+            }
+            .into());
+        }
+
         let mut chunkstream = stream.take(self.length as u64);
 
         let mut data_crc = CRC::new();
         data_crc.consume(&self.chunktype);
 
-        let chunk = match self.chunktype {
+        let chunk = self
+            .parse_contents(&mut chunkstream, ihdr, registry, &mut data_crc)
+            .map_err(|e| Error::in_chunk(e, self))?;
+
+        // Skip over anything the parser didn't use, so that the CRC can still be checked
+        let mut rest = Vec::new();
+        chunkstream.read_to_end(&mut rest)?;
+        data_crc.consume(&rest);
+
+        let mut buf4 = [0_u8; 4];
+        stream
+            .read_exact(&mut buf4)
+            .map_err(|e| Error::in_chunk(e, self))?;
+        let crc = u32::from_be_bytes(buf4);
+        if crc != data_crc.value() {
+            return Err(Error::CrcMismatch {
+                chunktype: self.chunktype,
+                offset: self.position,
+                expected: crc,
+                actual: data_crc.value(),
+            }
+            .into());
+        }
+
+        Ok(chunk)
+    }
+
+    /// Error for a chunk that needs the IHDR chunk to be parsed, but came before it
+    fn before_ihdr(&self) -> std::io::Error {
+        Error::OrderingViolation {
+            chunktype: self.chunktype,
+            offset: self.position,
+            message: "IHDR must go first".to_string(),
+        }
+        .into()
+    }
+
+    /// Parse chunk data into a PngChunkData enum
+    fn parse_contents<R>(
+        &self,
+        chunkstream: &mut R,
+        ihdr: Option<&Ihdr>,
+        registry: &ChunkRegistry,
+        data_crc: &mut CRC,
+    ) -> Result<PngChunkData, std::io::Error>
+    where
+        R: Read,
+    {
+        match self.chunktype {
+            chunktype if registry.is_registered(chunktype) => {
+                registry.parse(self, chunkstream, data_crc)
+            }
+
             Ihdr::TYPE => Ok(PngChunkData::Ihdr(Ihdr::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             Plte::TYPE => Ok(PngChunkData::Plte(Box::new(Plte::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Idat::TYPE => Ok(PngChunkData::Idat(Box::new(Idat::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             IEND_TYPE => Ok(PngChunkData::Iend),
@@ -443,238 +854,228 @@ impl PngChunkRef {
             Trns::TYPE => {
                 if let Some(Ihdr { colour_type, .. }) = ihdr {
                     Ok(PngChunkData::Trns(Box::new(Trns::from_contents_stream(
-                        &mut chunkstream,
+                        chunkstream,
                         self.length,
                         *colour_type,
-                        Some(&mut data_crc),
+                        Some(data_crc),
                     )?)))
                 } else {
-                    Err(std::io::Error::other("PNG: Unset ihdr".to_string()))
+                    Err(self.before_ihdr())
                 }
             }
 
             Gama::TYPE => Ok(PngChunkData::Gama(Gama::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             Chrm::TYPE => Ok(PngChunkData::Chrm(Box::new(Chrm::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?))),
 
             Iccp::TYPE => Ok(PngChunkData::Iccp(Box::new(Iccp::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Sbit::TYPE => {
                 if let Some(Ihdr { colour_type, .. }) = ihdr {
                     Ok(PngChunkData::Sbit(Sbit::from_contents_stream(
-                        &mut chunkstream,
+                        chunkstream,
                         self.length,
                         *colour_type,
-                        Some(&mut data_crc),
+                        Some(data_crc),
                     )?))
                 } else {
-                    Err(std::io::Error::other("PNG: Unset ihdr".to_string()))
+                    Err(self.before_ihdr())
                 }
             }
 
             Srgb::TYPE => Ok(PngChunkData::Srgb(Srgb::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             Cicp::TYPE => Ok(PngChunkData::Cicp(Cicp::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             Mdcv::TYPE => Ok(PngChunkData::Mdcv(Box::new(Mdcv::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?))),
 
             Clli::TYPE => Ok(PngChunkData::Clli(Clli::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             Text::TYPE => Ok(PngChunkData::Text(Box::new(Text::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Ztxt::TYPE => Ok(PngChunkData::Ztxt(Box::new(Ztxt::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Itxt::TYPE => Ok(PngChunkData::Itxt(Box::new(Itxt::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Bkgd::TYPE => {
                 if let Some(Ihdr { colour_type, .. }) = ihdr {
                     Ok(PngChunkData::Bkgd(Bkgd::from_contents_stream(
-                        &mut chunkstream,
+                        chunkstream,
                         self.length,
                         *colour_type,
-                        Some(&mut data_crc),
+                        Some(data_crc),
                     )?))
                 } else {
-                    Err(std::io::Error::other("PNG: Unset ihdr".to_string()))
+                    Err(self.before_ihdr())
                 }
             }
 
             Hist::TYPE => Ok(PngChunkData::Hist(Box::new(Hist::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Phys::TYPE => Ok(PngChunkData::Phys(Phys::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             Exif::TYPE => Ok(PngChunkData::Exif(Box::new(Exif::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Splt::TYPE => Ok(PngChunkData::Splt(Box::new(Splt::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Time::TYPE => Ok(PngChunkData::Time(Time::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             // Animation information
             Actl::TYPE => Ok(PngChunkData::Actl(Actl::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             Fctl::TYPE => Ok(PngChunkData::Fctl(Box::new(Fctl::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?))),
 
             Fdat::TYPE => Ok(PngChunkData::Fdat(Box::new(Fdat::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             // Public extensions
             Offs::TYPE => Ok(PngChunkData::Offs(Offs::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             Pcal::TYPE => Ok(PngChunkData::Pcal(Box::new(Pcal::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Scal::TYPE => Ok(PngChunkData::Scal(Box::new(Scal::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Gifg::TYPE => Ok(PngChunkData::Gifg(Gifg::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             Gifx::TYPE => Ok(PngChunkData::Gifx(Box::new(Gifx::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Ster::TYPE => Ok(PngChunkData::Ster(Ster::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             // JNG chunks
             Jhdr::TYPE => Ok(PngChunkData::Jhdr(Box::new(Jhdr::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?))),
 
             Jdat::TYPE => Ok(PngChunkData::Jdat(Box::new(Jdat::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             Jdaa::TYPE => Ok(PngChunkData::Jdaa(Box::new(Jdaa::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
             JSEP_TYPE => Ok(PngChunkData::Jsep),
 
             // Imagemagick chunk types
             Canv::TYPE => Ok(PngChunkData::Canv(Box::new(Canv::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?))),
 
             Vpag::TYPE => Ok(PngChunkData::Vpag(Box::new(Vpag::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?))),
 
             Ornt::TYPE => Ok(PngChunkData::Ornt(Ornt::from_contents_stream(
-                &mut chunkstream,
-                Some(&mut data_crc),
+                chunkstream,
+                Some(data_crc),
             )?)),
 
             Idot::TYPE => Ok(PngChunkData::Idot(Box::new(Idot::from_contents_stream(
-                &mut chunkstream,
+                chunkstream,
                 self.length,
-                Some(&mut data_crc),
+                Some(data_crc),
             )?))),
 
-            _ => Err(std::io::Error::other(format!(
-                "PNG: Unhandled chunk type ({:?})",
-                self.chunktype
-            ))),
-        }?;
+            _ => {
+                let mut data = vec![0_u8; self.length as usize];
+                chunkstream.read_exact(&mut data)?;
+                data_crc.consume(&data);
 
-        let mut buf4 = [0_u8; 4];
-        stream.read_exact(&mut buf4)?;
-        let crc = u32::from_be_bytes(buf4);
-        if crc != data_crc.value() {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!(
-                    "PNG: Read CRC ({:#x}) doesn't match the computed one ({:#x})",
-                    crc,
-                    data_crc.value()
-                ),
-            ));
+                Ok(PngChunkData::Unknown {
+                    chunktype: self.chunktype,
+                    data,
+                })
+            }
         }
-
-        Ok(chunk)
     }
 }
 
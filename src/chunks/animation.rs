@@ -20,15 +20,16 @@
 
 use std::io::{Read, Write};
 
-use uom::si::f64::Time;
+use uom::si::{f64::Time, time::second};
 
-use crate::chunks::PngChunkData;
+use crate::chunks::{PngChunkData, fmt_bytes, too_short};
 use crate::crc::*;
 use crate::to_io_error;
 use crate::types::*;
 
 /// Animation control
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Actl {
     pub num_frames: u32,
     pub num_plays: u32,
@@ -81,6 +82,19 @@ impl Actl {
     }
 }
 
+impl_chunk_codec!(Actl, fixed);
+
+impl std::fmt::Display for Actl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} frames, ", self.num_frames)?;
+        match self.num_plays {
+            0 => write!(f, "looping forever"),
+            1 => write!(f, "played once"),
+            plays => write!(f, "played {} times", plays),
+        }
+    }
+}
+
 impl From<Actl> for PngChunkData {
     fn from(actl: Actl) -> Self {
         Self::Actl(actl)
@@ -89,6 +103,7 @@ impl From<Actl> for PngChunkData {
 
 /// Frame control
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct Fctl {
     pub sequence_number: u32,
     pub width: u32,
@@ -128,7 +143,7 @@ impl Fctl {
             delay_num: u16::from_be_bytes(data[20..22].try_into().map_err(to_io_error)?),
             delay_den: u16::from_be_bytes(data[22..24].try_into().map_err(to_io_error)?),
             dispose_op: data[24].try_into().map_err(to_io_error)?,
-            blend_op: data[24].try_into().map_err(to_io_error)?,
+            blend_op: data[25].try_into().map_err(to_io_error)?,
         })
     }
 
@@ -179,8 +194,15 @@ impl Fctl {
     }
 
     /// Calculate delay from fcTL chunk in seconds
+    ///
+    /// A denominator of 0 means 100, i.e. the numerator is in hundredths of a second.
     pub fn delay(&self) -> Time {
-        Time::new::<uom::si::time::second>(self.delay_num as f64 / self.delay_den as f64)
+        let delay_den = if self.delay_den == 0 {
+            100
+        } else {
+            self.delay_den
+        };
+        Time::new::<uom::si::time::second>(self.delay_num as f64 / delay_den as f64)
     }
 }
 
@@ -195,6 +217,31 @@ impl PngChunkData {
     }
 }
 
+impl_chunk_codec!(Fctl, fixed);
+
+impl std::fmt::Display for Fctl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "sequence {}, {}×{} at ({}, {}), delay {} s",
+            self.sequence_number,
+            self.width,
+            self.height,
+            self.x_offset,
+            self.y_offset,
+            self.delay().get::<second>()
+        )?;
+        if f.alternate() {
+            write!(f, " ({}/{})", self.delay_num, self.delay_den)?;
+        }
+        write!(
+            f,
+            ", dispose {:?}, blend {:?}",
+            self.dispose_op, self.blend_op
+        )
+    }
+}
+
 impl From<Fctl> for PngChunkData {
     fn from(fctl: Fctl) -> Self {
         Self::Fctl(Box::new(fctl))
@@ -203,8 +250,10 @@ impl From<Fctl> for PngChunkData {
 
 /// Frame data
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Fdat {
     pub sequence_number: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
     pub frame_data: Vec<u8>,
 }
 
@@ -226,6 +275,10 @@ impl Fdat {
             data_crc.consume(&data);
         }
 
+        if data.len() < 4 {
+            return Err(too_short(Self::TYPE));
+        }
+
         Ok(Self {
             sequence_number: u32::from_be_bytes(data[0..4].try_into().map_err(to_io_error)?),
             frame_data: data[4..].to_vec(),
@@ -258,6 +311,15 @@ impl Fdat {
     }
 }
 
+impl_chunk_codec!(Fdat);
+
+impl std::fmt::Display for Fdat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sequence {}, ", self.sequence_number)?;
+        fmt_bytes(f, &self.frame_data)
+    }
+}
+
 impl From<Fdat> for PngChunkData {
     fn from(fdat: Fdat) -> Self {
         Self::Fdat(Box::new(fdat))
@@ -28,6 +28,7 @@ use crate::to_io_error;
 ///
 /// <https://www.hackerfactor.com/blog/index.php?/archives/895-Connecting-the-iDOTs.html>
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Idot(pub Vec<IdotSegment>);
 
 impl Idot {
@@ -42,11 +43,11 @@ impl Idot {
     where
         R: Read,
     {
-        if length < 4 {
-            return Err(std::io::Error::other(format!(
-                "Incorrect length for iDOT chunk: {}",
-                length
-            )));
+        if (length < 4) || !(length - 4).is_multiple_of(12) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("PNG: Invalid length of iDOT chunk ({})", length),
+            ));
         }
 
         let mut data = vec![0_u8; length as usize];
@@ -113,6 +114,21 @@ impl Idot {
     }
 }
 
+impl_chunk_codec!(Idot);
+
+impl std::fmt::Display for Idot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} segments", self.0.len())?;
+        if f.alternate() {
+            for segment in &self.0 {
+                write!(f, "; {}", segment)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl From<Idot> for PngChunkData {
     fn from(idot: Idot) -> Self {
         Self::Idot(Box::new(idot))
@@ -121,6 +137,7 @@ impl From<Idot> for PngChunkData {
 
 /// A segment in the iDOT chunk
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IdotSegment {
     /// The starting row of this segment
     pub start_row: u32,
@@ -131,3 +148,13 @@ pub struct IdotSegment {
     /// The position of the IDAT where these rows start
     pub idat_position: u32,
 }
+
+impl std::fmt::Display for IdotSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} rows from row {}, IDAT at {}",
+            self.num_rows, self.start_row, self.idat_position
+        )
+    }
+}
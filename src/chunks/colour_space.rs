@@ -20,14 +20,12 @@
 
 use std::io::{Read, Write};
 
-use flate2::{
-    Compression,
-    bufread::{ZlibDecoder, ZlibEncoder},
-};
+use flate2::{Compression, bufread::ZlibEncoder};
 use uom::si::{f64::Luminance, luminance::candela_per_square_meter};
 
-use crate::chunks::{PngChunkData, find_null};
+use crate::chunks::{PngChunkData, require_null, too_short};
 use crate::crc::*;
+use crate::limits::*;
 use crate::to_io_error;
 use crate::types::*;
 
@@ -35,6 +33,7 @@ use crate::types::*;
 ///
 /// Values are scaled by 100000
 #[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct Chrm {
     pub white_x: u32,
     pub white_y: u32,
@@ -253,6 +252,24 @@ impl PngChunkData {
     }
 }
 
+impl_chunk_codec!(Chrm, fixed);
+
+impl std::fmt::Display for Chrm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (white, red, green, blue) = (
+            self.white_coords(),
+            self.red_coords(),
+            self.green_coords(),
+            self.blue_coords(),
+        );
+        write!(
+            f,
+            "white ({}, {}), red ({}, {}), green ({}, {}), blue ({}, {})",
+            white.0, white.1, red.0, red.1, green.0, green.1, blue.0, blue.1
+        )
+    }
+}
+
 impl From<Chrm> for PngChunkData {
     fn from(chrm: Chrm) -> Self {
         Self::Chrm(Box::new(chrm))
@@ -261,6 +278,7 @@ impl From<Chrm> for PngChunkData {
 
 /// Image gamma
 #[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct Gama {
     /// Gamma value, scaled by 100000
     pub gamma: u32,
@@ -343,6 +361,19 @@ impl PngChunkData {
     }
 }
 
+impl_chunk_codec!(Gama, fixed);
+
+impl std::fmt::Display for Gama {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "gamma {}", self.gamma())?;
+        if f.alternate() {
+            write!(f, " ({})", self.gamma)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl From<Gama> for PngChunkData {
     fn from(gama: Gama) -> Self {
         Self::Gama(gama)
@@ -351,9 +382,11 @@ impl From<Gama> for PngChunkData {
 
 /// Embedded ICC profile
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Iccp {
     pub name: String,
     pub compression_method: PngCompressionMethod,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
     pub compressed_profile: Vec<u8>,
 }
 
@@ -390,10 +423,13 @@ impl Iccp {
             data_crc.consume(&data);
         }
 
-        let name_end = find_null(&data);
+        let name_end = require_null(&data, Self::TYPE)?;
+        let compression_method = *data
+            .get(name_end + 1)
+            .ok_or_else(|| too_short(Self::TYPE))?;
         Ok(Self {
             name: data[0..name_end].iter().map(|b| *b as char).collect(),
-            compression_method: data[name_end].try_into().map_err(to_io_error)?,
+            compression_method: compression_method.try_into().map_err(to_io_error)?,
             compressed_profile: data[name_end + 2..].to_vec(),
         })
     }
@@ -440,16 +476,22 @@ impl Iccp {
     }
 
     /// Uncompressed profile
+    ///
+    /// Returns None if it can't be decompressed. The size isn't limited, see
+    /// [profile_with_limit()](Self::profile_with_limit) or
+    /// [PngReader::iccp_profile()](crate::reader::PngReader::iccp_profile) for untrusted files.
     pub fn profile(&self) -> Option<Vec<u8>> {
-        if self.compression_method == PngCompressionMethod::Zlib {
-            let mut decoder = ZlibDecoder::new(self.compressed_profile.as_slice());
-            let mut out = Vec::new();
-            if decoder.read_to_end(&mut out).is_ok() {
-                return Some(out);
-            }
-        }
+        self.profile_with_limit(u64::MAX).ok()
+    }
 
-        None
+    /// Uncompressed profile, up to a maximum size in bytes
+    pub fn profile_with_limit(&self, max_size: u64) -> std::io::Result<Vec<u8>> {
+        inflate_limited(
+            &self.compressed_profile,
+            max_size,
+            LimitKind::IccProfileSize,
+            Self::TYPE,
+        )
     }
 }
 
@@ -471,6 +513,30 @@ impl PngChunkData {
     }
 }
 
+impl_chunk_codec!(Iccp);
+
+impl std::fmt::Display for Iccp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?}, {} bytes compressed",
+            self.name,
+            self.compressed_profile.len()
+        )?;
+        if f.alternate() {
+            match self
+                .profile_with_limit(Limits::default().max_icc_profile_size)
+                .ok()
+            {
+                Some(profile) => write!(f, ", {} bytes decompressed", profile.len())?,
+                None => write!(f, ", can't be decompressed")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl From<Iccp> for PngChunkData {
     fn from(iccp: Iccp) -> Self {
         Self::Iccp(Box::new(iccp))
@@ -479,6 +545,7 @@ impl From<Iccp> for PngChunkData {
 
 /// Significant bits
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Sbit {
     Greyscale {
         grey_bits: u8,
@@ -522,6 +589,16 @@ impl Sbit {
             data_crc.consume(&data);
         }
 
+        let num_values = match colour_type {
+            PngColourType::Greyscale => 1,
+            PngColourType::TrueColour | PngColourType::IndexedColour => 3,
+            PngColourType::GreyscaleAlpha => 2,
+            PngColourType::TrueColourAlpha => 4,
+        };
+        if data.len() < num_values {
+            return Err(too_short(Self::TYPE));
+        }
+
         match colour_type {
             PngColourType::Greyscale => Ok(Self::Greyscale { grey_bits: data[0] }),
 
@@ -590,6 +667,40 @@ impl Sbit {
     }
 }
 
+impl std::fmt::Display for Sbit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Greyscale { grey_bits } => write!(f, "grey {}", grey_bits),
+
+            Self::Colour {
+                red_bits,
+                green_bits,
+                blue_bits,
+            } => write!(
+                f,
+                "red {}, green {}, blue {}",
+                red_bits, green_bits, blue_bits
+            ),
+
+            Self::GreyscaleAlpha {
+                grey_bits,
+                alpha_bits,
+            } => write!(f, "grey {}, alpha {}", grey_bits, alpha_bits),
+
+            Self::TrueColourAlpha {
+                red_bits,
+                green_bits,
+                blue_bits,
+                alpha_bits,
+            } => write!(
+                f,
+                "red {}, green {}, blue {}, alpha {}",
+                red_bits, green_bits, blue_bits, alpha_bits
+            ),
+        }
+    }
+}
+
 impl From<Sbit> for PngChunkData {
     fn from(sbit: Sbit) -> Self {
         Self::Sbit(sbit)
@@ -598,6 +709,7 @@ impl From<Sbit> for PngChunkData {
 
 /// Standard RGB colour space
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Srgb {
     pub rendering_intent: PngRenderingIntent,
 }
@@ -644,6 +756,14 @@ impl Srgb {
     }
 }
 
+impl_chunk_codec!(Srgb, fixed);
+
+impl std::fmt::Display for Srgb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} rendering intent", self.rendering_intent)
+    }
+}
+
 impl From<Srgb> for PngChunkData {
     fn from(srgb: Srgb) -> Self {
         Self::Srgb(srgb)
@@ -652,6 +772,7 @@ impl From<Srgb> for PngChunkData {
 
 /// Coding-independent code points for video signal type identification
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cicp {
     pub colour_primaries: ColourPrimaries,
     pub transfer_function: TransferFunction,
@@ -709,6 +830,25 @@ impl Cicp {
     }
 }
 
+impl_chunk_codec!(Cicp, fixed);
+
+impl std::fmt::Display for Cicp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} primaries, {:?} transfer function, {:?} matrix coefficients, {} range",
+            self.colour_primaries,
+            self.transfer_function,
+            self.matrix_coeffs,
+            if self.video_full_range {
+                "full"
+            } else {
+                "narrow"
+            }
+        )
+    }
+}
+
 impl From<Cicp> for PngChunkData {
     fn from(cicp: Cicp) -> Self {
         Self::Cicp(cicp)
@@ -717,6 +857,7 @@ impl From<Cicp> for PngChunkData {
 
 /// Mastering Display Color Volume
 #[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct Mdcv {
     pub red_x: u16,
     pub red_y: u16,
@@ -1002,6 +1143,33 @@ impl PngChunkData {
     }
 }
 
+impl_chunk_codec!(Mdcv, fixed);
+
+impl std::fmt::Display for Mdcv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (red, green, blue, white) = (
+            self.red_coords(),
+            self.green_coords(),
+            self.blue_coords(),
+            self.white_coords(),
+        );
+        write!(
+            f,
+            "red ({}, {}), green ({}, {}), blue ({}, {}), white ({}, {}), luminance {}-{} cd/m²",
+            red.0,
+            red.1,
+            green.0,
+            green.1,
+            blue.0,
+            blue.1,
+            white.0,
+            white.1,
+            self.min_lum().get::<candela_per_square_meter>(),
+            self.max_lum().get::<candela_per_square_meter>()
+        )
+    }
+}
+
 impl From<Mdcv> for PngChunkData {
     fn from(mdcv: Mdcv) -> Self {
         Self::Mdcv(Box::new(mdcv))
@@ -1010,6 +1178,7 @@ impl From<Mdcv> for PngChunkData {
 
 /// Content Light Level Information
 #[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct Clli {
     /// Maximum Content Light Level
     pub max_cll: u32,
@@ -1127,6 +1296,19 @@ impl PngChunkData {
     }
 }
 
+impl_chunk_codec!(Clli, fixed);
+
+impl std::fmt::Display for Clli {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "MaxCLL {} cd/m², MaxFALL {} cd/m²",
+            self.max_cll().get::<candela_per_square_meter>(),
+            self.max_fall().get::<candela_per_square_meter>()
+        )
+    }
+}
+
 impl From<Clli> for PngChunkData {
     fn from(clli: Clli) -> Self {
         Self::Clli(clli)
@@ -20,13 +20,14 @@
 
 use std::io::{Read, Write};
 
-use crate::chunks::PngChunkData;
+use crate::chunks::{PngChunkData, fmt_bytes, invalid_contents};
 use crate::crc::*;
 use crate::to_io_error;
 use crate::types::*;
 
 /// Image header
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ihdr {
     /// Width of image in pixels
     pub width: u32,
@@ -109,7 +110,7 @@ impl Ihdr {
         let width_bytes = self.width.to_be_bytes();
         stream.write_all(&width_bytes)?;
 
-        let height_bytes = self.width.to_be_bytes();
+        let height_bytes = self.height.to_be_bytes();
         stream.write_all(&height_bytes)?;
 
         let rest_bytes = [
@@ -132,7 +133,9 @@ impl Ihdr {
 
     /// Number of bits in a pixel
     pub fn pixel_bits(&self) -> u8 {
-        self.colour_type.num_components() * self.bit_depth
+        self.colour_type
+            .num_components()
+            .saturating_mul(self.bit_depth)
     }
 
     /// Number of bytes in a line of image data, rounded up
@@ -148,6 +151,30 @@ impl Ihdr {
     }
 }
 
+impl_chunk_codec!(Ihdr, fixed);
+
+impl std::fmt::Display for Ihdr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}×{}, {}-bit {:?}",
+            self.width, self.height, self.bit_depth, self.colour_type
+        )?;
+        if self.interlace_method == PngInterlaceMethod::Adam7 {
+            write!(f, ", interlaced")?;
+        }
+        if f.alternate() {
+            write!(
+                f,
+                " (compression method {:?}, filter method {:?}, interlace method {:?})",
+                self.compression_method, self.filter_method, self.interlace_method
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 impl From<Ihdr> for PngChunkData {
     fn from(ihdr: Ihdr) -> Self {
         Self::Ihdr(ihdr)
@@ -156,6 +183,7 @@ impl From<Ihdr> for PngChunkData {
 
 /// Palette
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Plte(pub Vec<PngPaletteEntry>);
 
 impl Plte {
@@ -181,6 +209,10 @@ impl Plte {
             data_crc.consume(&data);
         }
 
+        if !length.is_multiple_of(3) {
+            return Err(invalid_contents(Self::TYPE, "partial palette entry"));
+        }
+
         Ok(Self(
             data.chunks(3)
                 .map(|col| {
@@ -220,6 +252,26 @@ impl Plte {
     }
 }
 
+impl_chunk_codec!(Plte);
+
+impl std::fmt::Display for Plte {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} entries", self.0.len())?;
+        if f.alternate() {
+            write!(f, ":")?;
+            for entry in &self.0 {
+                write!(
+                    f,
+                    " #{:02x}{:02x}{:02x}",
+                    entry.red, entry.green, entry.blue
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl From<Plte> for PngChunkData {
     fn from(plte: Plte) -> Self {
         Self::Plte(Box::new(plte))
@@ -228,7 +280,10 @@ impl From<Plte> for PngChunkData {
 
 /// Image data
 #[derive(Clone, Debug)]
-pub struct Idat(pub Vec<u8>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Idat(
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))] pub Vec<u8>,
+);
 
 impl Idat {
     pub(crate) const TYPE: [u8; 4] = *b"IDAT";
@@ -272,6 +327,14 @@ impl Idat {
     }
 }
 
+impl_chunk_codec!(Idat);
+
+impl std::fmt::Display for Idat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_bytes(f, &self.0)
+    }
+}
+
 impl From<Idat> for PngChunkData {
     fn from(idat: Idat) -> Self {
         Self::Idat(Box::new(idat))
@@ -28,6 +28,7 @@ use crate::to_io_error;
 
 /// Canvas
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Canv {
     pub width: u32,
     pub height: u32,
@@ -102,6 +103,18 @@ impl Canv {
     }
 }
 
+impl_chunk_codec!(Canv, fixed);
+
+impl std::fmt::Display for Canv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}×{} at ({}, {})",
+            self.width, self.height, self.x_offset, self.y_offset
+        )
+    }
+}
+
 impl From<Canv> for PngChunkData {
     fn from(canv: Canv) -> Self {
         Self::Canv(Box::new(canv))
@@ -110,6 +123,7 @@ impl From<Canv> for PngChunkData {
 
 /// VirtualPage
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vpag {
     pub virtual_page_width: u32,
     pub virtual_page_height: u32,
@@ -178,6 +192,18 @@ impl Vpag {
     }
 }
 
+impl_chunk_codec!(Vpag, fixed);
+
+impl std::fmt::Display for Vpag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}×{}, unit {}",
+            self.virtual_page_width, self.virtual_page_height, self.virtual_page_units
+        )
+    }
+}
+
 impl From<Vpag> for PngChunkData {
     fn from(vpag: Vpag) -> Self {
         Self::Vpag(Box::new(vpag))
@@ -186,6 +212,7 @@ impl From<Vpag> for PngChunkData {
 
 /// Orientation
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ornt(pub PngOrientation);
 
 impl Ornt {
@@ -228,6 +255,14 @@ impl Ornt {
     }
 }
 
+impl_chunk_codec!(Ornt, fixed);
+
+impl std::fmt::Display for Ornt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
 impl From<Ornt> for PngChunkData {
     fn from(ornt: Ornt) -> Self {
         Self::Ornt(ornt)
@@ -236,6 +271,7 @@ impl From<Ornt> for PngChunkData {
 
 /// Orientation (from EXIF/TIFF tag 0x0112)
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum PngOrientation {
     Undefined,
@@ -20,13 +20,14 @@
 
 use std::io::{Read, Write};
 
-use crate::chunks::PngChunkData;
+use crate::chunks::{PngChunkData, fmt_bytes};
 use crate::crc::*;
 use crate::to_io_error;
 use crate::types::*;
 
 /// JNG header
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Jhdr {
     /// Width of image in pixels
     pub width: u32,
@@ -60,10 +61,11 @@ pub struct Jhdr {
 }
 
 impl Jhdr {
-    pub(crate) const TYPE: [u8; 4] = *b"Jhdr";
+    pub(crate) const TYPE: [u8; 4] = *b"JHDR";
     pub(crate) const LENGTH: u32 = 16;
 
     /// Constructor
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         width: u32,
         height: u32,
@@ -90,6 +92,21 @@ impl Jhdr {
         }
     }
 
+    /// Number of bits in a decoded pixel, with 12-bit samples stored in 16 bits
+    pub fn pixel_bits(&self) -> u8 {
+        let sample_bits = match self.image_sample_depth {
+            JngImageSampleDepth::Depth8 => 8,
+            _ => 16,
+        };
+
+        match self.colour_type {
+            JngColourType::Greyscale => sample_bits,
+            JngColourType::Colour => 3 * sample_bits,
+            JngColourType::GreyscaleAlpha => sample_bits + self.alpha_sample_depth as u8,
+            JngColourType::ColourAlpha => 3 * sample_bits + self.alpha_sample_depth as u8,
+        }
+    }
+
     /// Read contents from a stream
     pub fn from_contents_stream<R>(
         stream: &mut R,
@@ -129,7 +146,7 @@ impl Jhdr {
         let width_bytes = self.width.to_be_bytes();
         stream.write_all(&width_bytes)?;
 
-        let height_bytes = self.width.to_be_bytes();
+        let height_bytes = self.height.to_be_bytes();
         stream.write_all(&height_bytes)?;
 
         let rest_bytes = [
@@ -154,6 +171,35 @@ impl Jhdr {
     }
 }
 
+impl_chunk_codec!(Jhdr, fixed);
+
+impl std::fmt::Display for Jhdr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}×{}, {:?}, image {:?} {:?}, alpha {:?}",
+            self.width,
+            self.height,
+            self.colour_type,
+            self.image_sample_depth,
+            self.image_compression_method,
+            self.alpha_sample_depth
+        )?;
+        if f.alternate() {
+            write!(
+                f,
+                " (image interlace method {:?}, alpha compression method {:?}, alpha filter method {:?}, alpha interlace method {:?})",
+                self.image_interlace_method,
+                self.alpha_compression_method,
+                self.alpha_filter_method,
+                self.alpha_interlace_method
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 impl From<Jhdr> for PngChunkData {
     fn from(jhdr: Jhdr) -> Self {
         Self::Jhdr(Box::new(jhdr))
@@ -162,7 +208,10 @@ impl From<Jhdr> for PngChunkData {
 
 /// JNG image data
 #[derive(Clone, Debug)]
-pub struct Jdat(pub Vec<u8>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Jdat(
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))] pub Vec<u8>,
+);
 
 impl Jdat {
     pub(crate) const TYPE: [u8; 4] = *b"JDAT";
@@ -206,6 +255,14 @@ impl Jdat {
     }
 }
 
+impl_chunk_codec!(Jdat);
+
+impl std::fmt::Display for Jdat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_bytes(f, &self.0)
+    }
+}
+
 impl From<Jdat> for PngChunkData {
     fn from(jdat: Jdat) -> Self {
         Self::Jdat(Box::new(jdat))
@@ -214,7 +271,10 @@ impl From<Jdat> for PngChunkData {
 
 /// JNG JPEG-encoded alpha data
 #[derive(Clone, Debug)]
-pub struct Jdaa(pub Vec<u8>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Jdaa(
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))] pub Vec<u8>,
+);
 
 impl Jdaa {
     pub(crate) const TYPE: [u8; 4] = *b"JDAA";
@@ -258,6 +318,14 @@ impl Jdaa {
     }
 }
 
+impl_chunk_codec!(Jdaa);
+
+impl std::fmt::Display for Jdaa {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_bytes(f, &self.0)
+    }
+}
+
 impl From<Jdaa> for PngChunkData {
     fn from(jdaa: Jdaa) -> Self {
         Self::Jdaa(Box::new(jdaa))
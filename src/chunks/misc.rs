@@ -20,15 +20,19 @@
 
 use std::io::{Read, Write};
 
-use uom::si::{f64::LinearNumberDensity, linear_number_density::per_meter};
+use uom::si::{
+    f64::LinearNumberDensity,
+    linear_number_density::{per_inch, per_meter},
+};
 
-use crate::chunks::{PngChunkData, find_null};
+use crate::chunks::{PngChunkData, fmt_bytes, invalid_contents, require_null, too_short};
 use crate::crc::*;
 use crate::to_io_error;
 use crate::types::*;
 
 /// Background colour
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Bkgd {
     Greyscale { value: u16 },
 
@@ -59,10 +63,10 @@ impl Bkgd {
         match colour_type {
             PngColourType::Greyscale | PngColourType::GreyscaleAlpha => {
                 if length != 2 {
-                    return Err(std::io::Error::other(format!(
-                        "PNG: Invalid length of bKGD chunk ({})",
-                        length
-                    )));
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("PNG: Invalid length of bKGD chunk ({})", length),
+                    ));
                 }
 
                 Ok(Self::Greyscale {
@@ -72,10 +76,10 @@ impl Bkgd {
 
             PngColourType::TrueColour | PngColourType::TrueColourAlpha => {
                 if length != 6 {
-                    return Err(std::io::Error::other(format!(
-                        "Png: Invalid length of bKGD chunk ({})",
-                        length
-                    )));
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("PNG: Invalid length of bKGD chunk ({})", length),
+                    ));
                 }
 
                 Ok(Self::TrueColour {
@@ -87,10 +91,10 @@ impl Bkgd {
 
             PngColourType::IndexedColour => {
                 if length != 1 {
-                    return Err(std::io::Error::other(format!(
-                        "Png: Invalid length of bKGD chunk ({})",
-                        length
-                    )));
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("PNG: Invalid length of bKGD chunk ({})", length),
+                    ));
                 }
 
                 Ok(Self::IndexedColour { index: data[0] })
@@ -134,6 +138,20 @@ impl Bkgd {
     }
 }
 
+impl std::fmt::Display for Bkgd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Greyscale { value } => write!(f, "grey {}", value),
+
+            Self::TrueColour { red, green, blue } => {
+                write!(f, "RGB ({}, {}, {})", red, green, blue)
+            }
+
+            Self::IndexedColour { index } => write!(f, "palette index {}", index),
+        }
+    }
+}
+
 impl From<Bkgd> for PngChunkData {
     fn from(bkgd: Bkgd) -> Self {
         Self::Bkgd(bkgd)
@@ -142,6 +160,7 @@ impl From<Bkgd> for PngChunkData {
 
 /// Image histogram
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Hist(pub Vec<u16>);
 
 impl Hist {
@@ -196,6 +215,22 @@ impl Hist {
     }
 }
 
+impl_chunk_codec!(Hist);
+
+impl std::fmt::Display for Hist {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} entries", self.0.len())?;
+        if f.alternate() {
+            write!(f, ":")?;
+            for frequency in &self.0 {
+                write!(f, " {}", frequency)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl From<Hist> for PngChunkData {
     fn from(hist: Hist) -> Self {
         Self::Hist(Box::new(hist))
@@ -204,6 +239,7 @@ impl From<Hist> for PngChunkData {
 
 /// Physical pixel dimensions
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Phys {
     pub x_pixels_per_unit: u32,
     pub y_pixels_per_unit: u32,
@@ -285,6 +321,35 @@ impl PngChunkData {
     }
 }
 
+impl_chunk_codec!(Phys, fixed);
+
+impl std::fmt::Display for Phys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.resolution() {
+            Some((x, y)) => write!(
+                f,
+                "{:.0}×{:.0} DPI",
+                x.get::<per_inch>(),
+                y.get::<per_inch>()
+            )?,
+            None => write!(
+                f,
+                "aspect ratio {}:{}",
+                self.x_pixels_per_unit, self.y_pixels_per_unit
+            )?,
+        }
+        if f.alternate() && (self.unit == PngUnitType::Metre) {
+            write!(
+                f,
+                " ({}×{} pixels per metre)",
+                self.x_pixels_per_unit, self.y_pixels_per_unit
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 impl From<Phys> for PngChunkData {
     fn from(phys: Phys) -> Self {
         Self::Phys(phys)
@@ -293,6 +358,7 @@ impl From<Phys> for PngChunkData {
 
 /// Suggested palette
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Splt {
     pub name: String,
     pub depth: u8,
@@ -326,10 +392,19 @@ impl Splt {
             data_crc.consume(&data);
         }
 
-        let name_end = find_null(&data);
-        let depth = data[name_end + 1];
+        let name_end = require_null(&data, Self::TYPE)?;
+        let depth = *data
+            .get(name_end + 1)
+            .ok_or_else(|| too_short(Self::TYPE))?;
+        if (depth != 8) && (depth != 16) {
+            return Err(invalid_contents(Self::TYPE, "invalid sample depth"));
+        }
         let entry_size = ((depth / 8) * 4) + 2;
-        let num_entries = (length as usize - name_end - 1) / (entry_size as usize);
+        let entries_length = data.len() - name_end - 2;
+        if !entries_length.is_multiple_of(entry_size as usize) {
+            return Err(invalid_contents(Self::TYPE, "partial palette entry"));
+        }
+        let num_entries = entries_length / (entry_size as usize);
 
         Ok(Self {
             name: data[0..name_end].iter().map(|b| *b as char).collect(),
@@ -439,6 +514,32 @@ impl Splt {
     }
 }
 
+impl_chunk_codec!(Splt);
+
+impl std::fmt::Display for Splt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?}, {}-bit, {} entries",
+            self.name,
+            self.depth,
+            self.palette.len()
+        )?;
+        if f.alternate() {
+            write!(f, ":")?;
+            for entry in &self.palette {
+                write!(
+                    f,
+                    " ({}, {}, {}, {}) ×{}",
+                    entry.red, entry.green, entry.blue, entry.alpha, entry.frequency
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl From<Splt> for PngChunkData {
     fn from(splt: Splt) -> Self {
         Self::Splt(Box::new(splt))
@@ -447,7 +548,10 @@ impl From<Splt> for PngChunkData {
 
 /// Exchangeable Image File (Exif) Profile
 #[derive(Clone, Debug)]
-pub struct Exif(pub Vec<u8>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Exif(
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))] pub Vec<u8>,
+);
 
 impl Exif {
     pub(crate) const TYPE: [u8; 4] = *b"eXIf";
@@ -489,7 +593,14 @@ impl Exif {
 
         Ok(())
     }
+}
 
+impl_chunk_codec!(Exif);
+
+impl std::fmt::Display for Exif {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_bytes(f, &self.0)
+    }
 }
 
 impl From<Exif> for PngChunkData {
@@ -22,13 +22,14 @@ use std::io::{Read, Write};
 
 use uom::si::{f64::Length, length::meter};
 
-use crate::chunks::{PngChunkData, find_null};
+use crate::chunks::{PngChunkData, find_null, fmt_bytes, require_null, too_short};
 use crate::crc::*;
 use crate::to_io_error;
 use crate::types::*;
 
 /// Image offset
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Offs {
     pub x: u32,
     pub y: u32,
@@ -110,6 +111,18 @@ impl PngChunkData {
     }
 }
 
+impl_chunk_codec!(Offs, fixed);
+
+impl std::fmt::Display for Offs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)?;
+        match self.unit {
+            PngUnitType::Unknown => write!(f, " pixels"),
+            PngUnitType::Metre => write!(f, " µm"),
+        }
+    }
+}
+
 impl From<Offs> for PngChunkData {
     fn from(offs: Offs) -> Self {
         Self::Offs(offs)
@@ -118,6 +131,7 @@ impl From<Offs> for PngChunkData {
 
 /// Calibration of pixel values
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pcal {
     pub name: String,
     pub original_zero: u32,
@@ -145,36 +159,47 @@ impl Pcal {
             data_crc.consume(&data);
         }
 
-        let name_end = find_null(&data);
-        let num_parameters = data[name_end + 9];
-        let unit_end = find_null(&data[name_end + 10..]) + name_end + 10;
-
-        let parameters = data[unit_end..]
-            .split(|b| *b == 0)
-            .map(|slice| slice.iter().map(|b| *b as char).collect::<String>())
-            .collect::<Vec<_>>();
+        let name_end = require_null(&data, Self::TYPE)?;
+        if data.len() < name_end + 11 {
+            return Err(too_short(Self::TYPE));
+        }
+        let num_parameters = data[name_end + 10];
+        let unit_end = find_null(&data[name_end + 11..]) + name_end + 11;
+
+        // Each parameter is preceded by a null separator
+        let parameters = if unit_end < data.len() {
+            data[unit_end + 1..]
+                .split(|b| *b == 0)
+                .map(|slice| slice.iter().map(|b| *b as char).collect::<String>())
+                .collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
         if parameters.len() != num_parameters as usize {
-            return Err(std::io::Error::other(format!(
-                "Read {} parameters but there are supposed to be {}",
-                parameters.len(),
-                num_parameters
-            )));
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "PNG: Read {} parameters but there are supposed to be {}",
+                    parameters.len(),
+                    num_parameters
+                ),
+            ));
         }
 
         Ok(Self {
             name: data[0..name_end].iter().map(|b| *b as char).collect(),
             original_zero: u32::from_be_bytes(
-                data[name_end..name_end + 4]
+                data[name_end + 1..name_end + 5]
                     .try_into()
                     .map_err(to_io_error)?,
             ),
             original_max: u32::from_be_bytes(
-                data[name_end + 4..name_end + 8]
+                data[name_end + 5..name_end + 9]
                     .try_into()
                     .map_err(to_io_error)?,
             ),
-            equation_type: data[name_end + 8].try_into().map_err(to_io_error)?,
-            unit_name: data[name_end + 10..unit_end]
+            equation_type: data[name_end + 9].try_into().map_err(to_io_error)?,
+            unit_name: data[name_end + 11..unit_end]
                 .iter()
                 .map(|b| *b as char)
                 .collect(),
@@ -241,6 +266,23 @@ impl Pcal {
     }
 }
 
+impl_chunk_codec!(Pcal);
+
+impl std::fmt::Display for Pcal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?}, {}-{} mapped by {:?}, unit {:?}",
+            self.name, self.original_zero, self.original_max, self.equation_type, self.unit_name
+        )?;
+        if f.alternate() {
+            write!(f, ", parameters {:?}", self.parameters)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl From<Pcal> for PngChunkData {
     fn from(pcal: Pcal) -> Self {
         Self::Pcal(Box::new(pcal))
@@ -249,6 +291,7 @@ impl From<Pcal> for PngChunkData {
 
 /// Physical scale of image subject
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Scal {
     pub unit: PngUnitType,
     pub pixel_width: String,
@@ -273,13 +316,14 @@ impl Scal {
             data_crc.consume(&data);
         }
 
-        let width_end = find_null(&data[1..]) + 1;
-        let height_end = find_null(&data[width_end..]) + width_end;
+        let unit = *data.first().ok_or_else(|| too_short(Self::TYPE))?;
+        let width_end = require_null(&data[1..], Self::TYPE)? + 1;
+        let height_end = find_null(&data[width_end + 1..]) + width_end + 1;
 
         Ok(Self {
-            unit: data[0].try_into().map_err(to_io_error)?,
+            unit: unit.try_into().map_err(to_io_error)?,
             pixel_width: data[1..width_end].iter().map(|b| *b as char).collect(),
-            pixel_height: data[width_end..height_end]
+            pixel_height: data[width_end + 1..height_end]
                 .iter()
                 .map(|b| *b as char)
                 .collect(),
@@ -329,6 +373,18 @@ impl Scal {
     }
 }
 
+impl_chunk_codec!(Scal);
+
+impl std::fmt::Display for Scal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}×{}", self.pixel_width, self.pixel_height)?;
+        match self.unit {
+            PngUnitType::Unknown => write!(f, " (unknown unit) per pixel"),
+            PngUnitType::Metre => write!(f, " m per pixel"),
+        }
+    }
+}
+
 impl From<Scal> for PngChunkData {
     fn from(scal: Scal) -> Self {
         Self::Scal(Box::new(scal))
@@ -337,6 +393,7 @@ impl From<Scal> for PngChunkData {
 
 /// GIF Graphic Control Extension
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gifg {
     pub disposal_method: GifDisposalMethod,
     pub user_input: bool,
@@ -391,6 +448,20 @@ impl Gifg {
     }
 }
 
+impl_chunk_codec!(Gifg, fixed);
+
+impl std::fmt::Display for Gifg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} disposal, {}user input, delay {} s",
+            self.disposal_method,
+            if self.user_input { "" } else { "no " },
+            self.delay_time as f64 / 100.0
+        )
+    }
+}
+
 impl From<Gifg> for PngChunkData {
     fn from(gifg: Gifg) -> Self {
         Self::Gifg(gifg)
@@ -399,9 +470,11 @@ impl From<Gifg> for PngChunkData {
 
 /// GIF Application Extension
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gifx {
     pub app_id: [char; 8],
     pub app_auth: [u8; 3],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
     pub app_data: Vec<u8>,
 }
 
@@ -423,13 +496,22 @@ impl Gifx {
             data_crc.consume(&data);
         }
 
+        if data.len() < 11 {
+            return Err(too_short(Self::TYPE));
+        }
+
         Ok(Self {
             app_id: data[0..8]
                 .iter()
                 .map(|b| *b as char)
                 .collect::<Vec<_>>()
                 .try_into()
-                .map_err(|e| std::io::Error::other(format!("Couldn't convert {:?}", e)))?,
+                .map_err(|e| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Couldn't convert {:?}", e),
+                    )
+                })?,
             app_auth: [data[8], data[9], data[10]],
             app_data: data[11..].to_vec(),
         })
@@ -463,6 +545,22 @@ impl Gifx {
     }
 }
 
+impl_chunk_codec!(Gifx);
+
+impl std::fmt::Display for Gifx {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "application {:?} ({:02x}{:02x}{:02x}), ",
+            self.app_id.iter().collect::<String>(),
+            self.app_auth[0],
+            self.app_auth[1],
+            self.app_auth[2]
+        )?;
+        fmt_bytes(f, &self.app_data)
+    }
+}
+
 impl From<Gifx> for PngChunkData {
     fn from(gifx: Gifx) -> Self {
         Self::Gifx(Box::new(gifx))
@@ -471,6 +569,7 @@ impl From<Gifx> for PngChunkData {
 
 /// Indicator of Stereo Image
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ster {
     pub mode: StereoMode,
 }
@@ -517,6 +616,14 @@ impl Ster {
     }
 }
 
+impl_chunk_codec!(Ster, fixed);
+
+impl std::fmt::Display for Ster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.mode)
+    }
+}
+
 impl From<Ster> for PngChunkData {
     fn from(ster: Ster) -> Self {
         Self::Ster(ster)
@@ -20,18 +20,17 @@
 
 use std::io::{Read, Write};
 
-use flate2::{
-    Compression,
-    bufread::{ZlibDecoder, ZlibEncoder},
-};
+use flate2::{Compression, bufread::ZlibEncoder};
 
-use crate::chunks::{PngChunkData, find_null};
+use crate::chunks::{PngChunkData, fmt_string, require_null, too_short};
 use crate::crc::*;
+use crate::limits::*;
 use crate::to_io_error;
 use crate::types::*;
 
 /// Textual data
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Text {
     pub keyword: String,
     pub string: String,
@@ -63,7 +62,7 @@ impl Text {
             data_crc.consume(&data);
         }
 
-        let keyword_end = find_null(&data);
+        let keyword_end = require_null(&data, Self::TYPE)?;
         Ok(Self {
             keyword: data[0..keyword_end].iter().map(|b| *b as char).collect(),
             string: data[keyword_end + 1..].iter().map(|b| *b as char).collect(),
@@ -101,6 +100,15 @@ impl Text {
     }
 }
 
+impl_chunk_codec!(Text);
+
+impl std::fmt::Display for Text {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: ", self.keyword)?;
+        fmt_string(f, &self.string)
+    }
+}
+
 impl From<Text> for PngChunkData {
     fn from(text: Text) -> Self {
         Self::Text(Box::new(text))
@@ -109,9 +117,11 @@ impl From<Text> for PngChunkData {
 
 /// Compressed textual data
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct Ztxt {
     pub keyword: String,
     pub compression_method: PngCompressionMethod,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
     pub compressed_string: Vec<u8>,
 }
 
@@ -148,10 +158,13 @@ impl Ztxt {
             data_crc.consume(&data);
         }
 
-        let keyword_end = find_null(&data);
+        let keyword_end = require_null(&data, Self::TYPE)?;
+        let compression_method = *data
+            .get(keyword_end + 1)
+            .ok_or_else(|| too_short(Self::TYPE))?;
         Ok(Self {
             keyword: data[0..keyword_end].iter().map(|b| *b as char).collect(),
-            compression_method: data[keyword_end + 1].try_into().map_err(to_io_error)?,
+            compression_method: compression_method.try_into().map_err(to_io_error)?,
             compressed_string: data[keyword_end + 2..].to_vec(),
         })
     }
@@ -198,16 +211,24 @@ impl Ztxt {
     }
 
     /// Decompress the compressed string in a zTXt chunk
+    ///
+    /// Returns None if it can't be decompressed. The size isn't limited, see
+    /// [string_with_limit()](Self::string_with_limit) or
+    /// [PngReader::text_string()](crate::reader::PngReader::text_string) for untrusted files.
     pub fn string(&self) -> Option<String> {
-        if self.compression_method == PngCompressionMethod::Zlib {
-            let mut decoder = ZlibDecoder::new(self.compressed_string.as_slice());
-            let mut out = Vec::new();
-            if decoder.read_to_end(&mut out).is_ok() {
-                return Some(out.iter().map(|b| *b as char).collect());
-            }
-        }
+        self.string_with_limit(u64::MAX).ok()
+    }
 
-        None
+    /// Decompress the compressed string in a zTXt chunk, up to a maximum size in bytes
+    pub fn string_with_limit(&self, max_size: u64) -> std::io::Result<String> {
+        let out = inflate_limited(
+            &self.compressed_string,
+            max_size,
+            LimitKind::TextSize,
+            Self::TYPE,
+        )?;
+
+        Ok(out.iter().map(|b| *b as char).collect())
     }
 }
 
@@ -229,6 +250,22 @@ impl PngChunkData {
     }
 }
 
+impl_chunk_codec!(Ztxt);
+
+impl std::fmt::Display for Ztxt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: ", self.keyword)?;
+        match self.string_with_limit(Limits::default().max_text_size).ok() {
+            Some(string) => fmt_string(f, &string),
+            None => write!(
+                f,
+                "{} bytes that can't be decompressed",
+                self.compressed_string.len()
+            ),
+        }
+    }
+}
+
 impl From<Ztxt> for PngChunkData {
     fn from(ztxt: Ztxt) -> Self {
         Self::Ztxt(Box::new(ztxt))
@@ -237,11 +274,13 @@ impl From<Ztxt> for PngChunkData {
 
 /// International textual data
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct Itxt {
     pub keyword: String,
     pub compression_method: Option<PngCompressionMethod>,
     pub language: String,
     pub translated_keyword: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::bytes"))]
     pub compressed_string: Vec<u8>,
 }
 
@@ -288,14 +327,17 @@ impl Itxt {
             data_crc.consume(&data);
         }
 
-        let keyword_end = find_null(&data);
-        let language_end = find_null(&data[keyword_end + 3..]) + keyword_end + 3;
-        let tkeyword_end = find_null(&data[language_end + 1..]) + language_end + 1;
+        let keyword_end = require_null(&data, Self::TYPE)?;
+        let compression = data
+            .get(keyword_end + 1..keyword_end + 3)
+            .ok_or_else(|| too_short(Self::TYPE))?;
+        let language_end = require_null(&data[keyword_end + 3..], Self::TYPE)? + keyword_end + 3;
+        let tkeyword_end = require_null(&data[language_end + 1..], Self::TYPE)? + language_end + 1;
 
         Ok(Self {
             keyword: data[0..keyword_end].iter().map(|b| *b as char).collect(),
-            compression_method: if data[keyword_end + 1] > 0 {
-                Some(data[keyword_end + 2].try_into().map_err(to_io_error)?)
+            compression_method: if compression[0] > 0 {
+                Some(compression[1].try_into().map_err(to_io_error)?)
             } else {
                 None
             },
@@ -391,16 +433,27 @@ impl Itxt {
     }
 
     /// Decompress the compressed string in an iTXt chunk
+    ///
+    /// Returns None if it can't be decompressed. The size isn't limited, see
+    /// [string_with_limit()](Self::string_with_limit) or
+    /// [PngReader::text_string()](crate::reader::PngReader::text_string) for untrusted files.
     pub fn string(&self) -> Option<String> {
+        self.string_with_limit(u64::MAX).ok()
+    }
+
+    /// Decompress the compressed string in an iTXt chunk, up to a maximum size in bytes
+    pub fn string_with_limit(&self, max_size: u64) -> std::io::Result<String> {
         if self.compression_method == Some(PngCompressionMethod::Zlib) {
-            let mut decoder = ZlibDecoder::new(self.compressed_string.as_slice());
-            let mut out = String::new();
-            if decoder.read_to_string(&mut out).is_ok() {
-                return Some(out);
-            }
+            let out = inflate_limited(
+                &self.compressed_string,
+                max_size,
+                LimitKind::TextSize,
+                Self::TYPE,
+            )?;
+            return String::from_utf8(out).map_err(to_io_error);
         }
 
-        String::from_utf8(self.compressed_string.to_vec()).ok()
+        String::from_utf8(self.compressed_string.to_vec()).map_err(to_io_error)
     }
 }
 
@@ -426,8 +479,40 @@ impl PngChunkData {
     }
 }
 
+impl_chunk_codec!(Itxt);
+
+impl std::fmt::Display for Itxt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.keyword)?;
+        if !self.language.is_empty() || !self.translated_keyword.is_empty() {
+            write!(f, " ({:?}, {:?})", self.language, self.translated_keyword)?;
+        }
+        write!(f, ": ")?;
+        match self.string_with_limit(Limits::default().max_text_size).ok() {
+            Some(string) => fmt_string(f, &string),
+            None => write!(
+                f,
+                "{} bytes that can't be decoded",
+                self.compressed_string.len()
+            ),
+        }
+    }
+}
+
 impl From<Itxt> for PngChunkData {
     fn from(itxt: Itxt) -> Self {
         Self::Itxt(Box::new(itxt))
     }
 }
+
+impl PngChunkData {
+    /// Keyword of a tEXt, zTXt, or iTXt chunk
+    pub fn text_keyword(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(&text.keyword),
+            Self::Ztxt(ztxt) => Some(&ztxt.keyword),
+            Self::Itxt(itxt) => Some(&itxt.keyword),
+            _ => None,
+        }
+    }
+}
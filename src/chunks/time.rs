@@ -28,13 +28,14 @@ use crate::to_io_error;
 
 /// Image last-modification time
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct Time {
-    year: u16,
-    month: u8,
-    day: u8,
-    hour: u8,
-    minute: u8,
-    second: u8,
+    pub(crate) year: u16,
+    pub(crate) month: u8,
+    pub(crate) day: u8,
+    pub(crate) hour: u8,
+    pub(crate) minute: u8,
+    pub(crate) second: u8,
 }
 
 impl Time {
@@ -126,6 +127,21 @@ impl PngChunkData {
     }
 }
 
+impl_chunk_codec!(Time, fixed);
+
+impl std::fmt::Display for Time {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.time() {
+            Some(time) => write!(f, "{}", time.format("%Y-%m-%dT%H:%M:%SZ")),
+            None => write!(
+                f,
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z (invalid)",
+                self.year, self.month, self.day, self.hour, self.minute, self.second
+            ),
+        }
+    }
+}
+
 impl From<Time> for PngChunkData {
     fn from(time: Time) -> Self {
         Self::Time(time)
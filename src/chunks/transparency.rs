@@ -20,13 +20,14 @@
 
 use std::io::{Read, Write};
 
-use crate::chunks::PngChunkData;
+use crate::chunks::{PngChunkData, too_short};
 use crate::crc::*;
 use crate::to_io_error;
 use crate::types::*;
 
 /// tRNS chunk
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Trns {
     Greyscale { value: u16 },
 
@@ -53,6 +54,15 @@ impl Trns {
             data_crc.consume(&data);
         }
 
+        let min_length = match colour_type {
+            PngColourType::Greyscale => 2,
+            PngColourType::TrueColour => 6,
+            _ => 0,
+        };
+        if data.len() < min_length {
+            return Err(too_short(Self::TYPE));
+        }
+
         match colour_type {
             PngColourType::Greyscale => Ok(Self::Greyscale {
                 value: u16::from_be_bytes(data[0..2].try_into().map_err(to_io_error)?),
@@ -66,10 +76,10 @@ impl Trns {
 
             PngColourType::IndexedColour => Ok(Self::IndexedColour { values: data }),
 
-            _ => Err(std::io::Error::other(format!(
-                "PNG: Invalid colour type ({}) in ihdr",
-                colour_type as u8
-            ))),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("PNG: Invalid colour type ({}) in ihdr", colour_type as u8),
+            )),
         }
     }
 
@@ -115,6 +125,30 @@ impl Trns {
     }
 }
 
+impl std::fmt::Display for Trns {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Greyscale { value } => write!(f, "grey {}", value),
+
+            Self::TrueColour { red, green, blue } => {
+                write!(f, "RGB ({}, {}, {})", red, green, blue)
+            }
+
+            Self::IndexedColour { values } => {
+                write!(f, "{} alpha values", values.len())?;
+                if f.alternate() {
+                    write!(f, ":")?;
+                    for value in values {
+                        write!(f, " {}", value)?;
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
 impl From<Trns> for PngChunkData {
     fn from(trns: Trns) -> Self {
         Self::Trns(Box::new(trns))
@@ -0,0 +1,93 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Tracking the position in streams that can't seek
+ */
+
+use std::io::Write;
+
+use crate::chunks::{PngChunkData, PngChunkRef};
+
+/// Counts the bytes written to a stream, e.g. a socket, stdout, or a compression encoder
+///
+/// The count is the position used for the [PngChunkRef] of each chunk written.
+#[derive(Debug)]
+pub struct CountingWriter<W> {
+    stream: W,
+    position: u64,
+}
+
+impl<W> CountingWriter<W>
+where
+    W: Write,
+{
+    /// Constructor, counting from zero
+    pub fn new(stream: W) -> Self {
+        Self::with_position(stream, 0)
+    }
+
+    /// Constructor, counting from a position
+    ///
+    /// `position`: The number of bytes already written to the stream.
+    pub fn with_position(stream: W, position: u64) -> Self {
+        Self { stream, position }
+    }
+
+    /// Current position, i.e. the starting position plus the number of bytes written
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Write a chunk, returning a chunk reference
+    pub fn write_chunk(&mut self, chunk: &PngChunkData) -> std::io::Result<PngChunkRef> {
+        let position = self.position;
+        chunk.to_stream_at(self, position)
+    }
+
+    /// Get a reference to the stream
+    pub fn get_ref(&self) -> &W {
+        &self.stream
+    }
+
+    /// Get a mutable reference to the stream
+    ///
+    /// Anything written directly to the stream isn't counted.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.stream
+    }
+
+    /// Unwrap the stream
+    pub fn into_inner(self) -> W {
+        self.stream
+    }
+}
+
+impl<W> Write for CountingWriter<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = self.stream.write(buf)?;
+        self.position += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
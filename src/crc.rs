@@ -54,6 +54,7 @@ const CRC_TABLE: [u32; 256] = [
     0xb3667a2e, 0xc4614ab8, 0x5d681b02, 0x2a6f2b94, 0xb40bbe37, 0xc30c8ea1, 0x5a05df1b, 0x2d02ef8d,
 ];
 
+#[derive(Clone, Copy, Debug)]
 pub struct CRC {
     state: u32,
 }
@@ -79,3 +80,9 @@ impl CRC {
         self.state ^ 0xffffffff
     }
 }
+
+impl Default for CRC {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,340 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Decompression and unfiltering of image data into scanlines
+ *
+ * Rows are returned in the packed format described by the IHDR chunk, i.e. samples are not
+ * expanded or converted.
+ */
+
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+use crate::adam7::*;
+use crate::chunks::*;
+use crate::types::*;
+
+/// Check that the bit depth is allowed for the colour type, before decoding anything
+pub(crate) fn check_bit_depth(ihdr: &Ihdr) -> Result<(), std::io::Error> {
+    if !ihdr
+        .colour_type
+        .allowed_bit_depths()
+        .contains(&ihdr.bit_depth)
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "PNG: Invalid bit depth ({}) for colour type ({:?})",
+                ihdr.bit_depth, ihdr.colour_type
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Number of bytes per complete pixel, rounded up to 1, as used by the filters
+pub fn filter_bytes_per_pixel(ihdr: &Ihdr) -> usize {
+    (ihdr.pixel_bits() as usize).div_ceil(8)
+}
+
+/// Paeth predictor
+#[inline]
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if (pa <= pb) && (pa <= pc) {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Reconstruct a filtered line in place
+///
+/// `bpp`: Number of bytes per complete pixel, see [filter_bytes_per_pixel()].
+/// `prev`: The previous reconstructed line, or all zeroes for the first line. Returns an
+/// [InvalidInput](std::io::ErrorKind::InvalidInput) error if it's shorter than `line`.
+pub fn unfilter_line(
+    filter_type: PngFilterType,
+    bpp: usize,
+    prev: &[u8],
+    line: &mut [u8],
+) -> Result<(), std::io::Error> {
+    if prev.len() < line.len() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "PNG: Previous line is shorter than the line ({} < {})",
+                prev.len(),
+                line.len()
+            ),
+        ));
+    }
+
+    match filter_type {
+        PngFilterType::None => (),
+
+        PngFilterType::Sub => {
+            for i in bpp..line.len() {
+                line[i] = line[i].wrapping_add(line[i - bpp]);
+            }
+        }
+
+        PngFilterType::Up => {
+            for (x, b) in line.iter_mut().zip(prev) {
+                *x = x.wrapping_add(*b);
+            }
+        }
+
+        PngFilterType::Average => {
+            for i in 0..line.len() {
+                let a = if i >= bpp { line[i - bpp] as u16 } else { 0 };
+                line[i] = line[i].wrapping_add(((a + prev[i] as u16) >> 1) as u8);
+            }
+        }
+
+        PngFilterType::Paeth => {
+            for i in 0..line.len() {
+                let (a, c) = if i >= bpp {
+                    (line[i - bpp], prev[i - bpp])
+                } else {
+                    (0, 0)
+                };
+                line[i] = line[i].wrapping_add(paeth(a, prev[i], c));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A decoded row of a pass
+#[derive(Clone, Copy, Debug)]
+pub struct PassRow<'a> {
+    /// The pass this row belongs to
+    pub pass: InterlacePass,
+
+    /// Row number within the pass
+    pub y: u32,
+
+    /// Unfiltered row data
+    pub data: &'a [u8],
+}
+
+/// Decompresses and unfilters image data, one row at a time
+///
+/// Rows of interlaced images are returned pass by pass.
+pub struct ScanlineDecoder<R> {
+    stream: ZlibDecoder<R>,
+    bpp: usize,
+    adam7: Option<Adam7>,
+    passes: Vec<InterlacePass>,
+    pass_index: usize,
+    row: u32,
+    prev: Vec<u8>,
+    line: Vec<u8>,
+}
+
+impl<R> ScanlineDecoder<R>
+where
+    R: Read,
+{
+    /// Constructor from a stream of compressed image data and the IHDR chunk
+    pub fn new(stream: R, ihdr: &Ihdr) -> Result<Self, std::io::Error> {
+        Self::with_size(stream, ihdr, ihdr.width, ihdr.height)
+    }
+
+    /// Constructor for image data with different dimensions to the IHDR chunk, e.g. an APNG frame
+    pub fn with_size(
+        stream: R,
+        ihdr: &Ihdr,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, std::io::Error> {
+        check_bit_depth(ihdr)?;
+
+        let (adam7, passes) = match ihdr.interlace_method {
+            PngInterlaceMethod::None => (
+                None,
+                vec![InterlacePass::whole_image(ihdr.pixel_bits(), width, height)],
+            ),
+
+            PngInterlaceMethod::Adam7 => {
+                let adam7 = Adam7::with_size(ihdr, width, height);
+                (Some(adam7), adam7.passes.to_vec())
+            }
+        };
+
+        Ok(Self {
+            stream: ZlibDecoder::new(stream),
+            bpp: filter_bytes_per_pixel(ihdr),
+            adam7,
+            passes,
+            pass_index: 0,
+            row: 0,
+            prev: Vec::new(),
+            line: Vec::new(),
+        })
+    }
+
+    /// Adam7 geometry, if the image is interlaced
+    pub fn adam7(&self) -> Option<&Adam7> {
+        self.adam7.as_ref()
+    }
+
+    /// The pass currently being decoded
+    ///
+    /// Returns None once all rows have been decoded.
+    pub fn current_pass(&self) -> Option<&InterlacePass> {
+        self.passes[self.pass_index..]
+            .iter()
+            .find(|pass| !pass.is_empty())
+    }
+
+    /// Number of bytes in each row of the current pass
+    pub fn line_size(&self) -> usize {
+        self.current_pass().map_or(0, |pass| pass.line_size)
+    }
+
+    /// Number of rows left to decode, over all passes
+    pub fn rows_left(&self) -> u32 {
+        self.passes[self.pass_index..]
+            .iter()
+            .filter(|pass| !pass.is_empty())
+            .map(|pass| pass.height)
+            .sum::<u32>()
+            - self.row
+    }
+
+    /// Decode the next row
+    ///
+    /// Returns None once all rows have been decoded.
+    pub fn next_row(&mut self) -> Result<Option<&[u8]>, std::io::Error> {
+        Ok(self.next_pass_row()?.map(|row| row.data))
+    }
+
+    /// Decode the next row, along with the pass it belongs to and its row number in that pass
+    ///
+    /// Returns None once all rows have been decoded.
+    pub fn next_pass_row(&mut self) -> Result<Option<PassRow<'_>>, std::io::Error> {
+        let pass = loop {
+            let Some(pass) = self.passes.get(self.pass_index) else {
+                return Ok(None);
+            };
+
+            if pass.is_empty() || (self.row >= pass.height) {
+                self.pass_index += 1;
+                self.row = 0;
+                continue;
+            }
+
+            break *pass;
+        };
+
+        if self.row == 0 {
+            // Each pass is filtered separately, so the "previous" line of its first row is all
+            // zeroes. It is swapped into place below.
+            self.line.clear();
+            self.line.resize(pass.line_size, 0);
+            self.prev.resize(pass.line_size, 0);
+        }
+
+        let mut filter_byte = [0_u8];
+        self.stream.read_exact(&mut filter_byte)?;
+        let filter_type = PngFilterType::try_from(filter_byte[0]).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("PNG: Invalid filter type ({})", filter_byte[0]),
+            )
+        })?;
+
+        std::mem::swap(&mut self.prev, &mut self.line);
+        self.stream.read_exact(&mut self.line)?;
+        unfilter_line(filter_type, self.bpp, &self.prev, &mut self.line)?;
+        let y = self.row;
+        self.row += 1;
+
+        Ok(Some(PassRow {
+            pass,
+            y,
+            data: &self.line,
+        }))
+    }
+
+    /// Decode the rest of the current pass into a full image buffer
+    ///
+    /// `image`: The full image, in packed rows. The pixels of the pass are scattered into it.
+    /// Returns an [InvalidInput](std::io::ErrorKind::InvalidInput) error if it's too small.
+    ///
+    /// Returns the pass that was completed, or None once all passes have been decoded. For
+    /// progressive display, call [Adam7::fill_preview()] after each pass. A non-interlaced image
+    /// is decoded as a single pass.
+    pub fn next_pass(&mut self, image: &mut [u8]) -> Result<Option<InterlacePass>, std::io::Error> {
+        let adam7 = self.adam7;
+        while let Some(PassRow { pass, y, data: row }) = self.next_pass_row()? {
+            match &adam7 {
+                Some(adam7) => adam7.deinterlace_row(&pass, y, row, image)?,
+
+                None => {
+                    let start = y as usize * row.len();
+                    let image_len = image.len();
+                    let image_row = image.get_mut(start..start + row.len()).ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!("PNG: Image buffer is too small ({})", image_len),
+                        )
+                    })?;
+                    image_row.copy_from_slice(row);
+                }
+            }
+
+            if y + 1 == pass.height {
+                return Ok(Some(pass));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Decode all of the remaining rows into one full image buffer
+    ///
+    /// Interlaced images are deinterlaced.
+    pub fn read_all(&mut self) -> Result<Vec<u8>, std::io::Error> {
+        let size = match &self.adam7 {
+            Some(adam7) => adam7.image_size(),
+            None => self.passes[0].line_size * self.passes[0].height as usize,
+        };
+
+        let mut image = vec![0_u8; size];
+        while self.next_pass(&mut image)?.is_some() {}
+
+        Ok(image)
+    }
+
+    /// Unwrap the stream of compressed image data
+    pub fn into_inner(self) -> R {
+        self.stream.into_inner()
+    }
+}
@@ -0,0 +1,312 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! In-memory PNG/APNG documents for editing
+ *
+ * A [PngDocument] holds every chunk of a file. Chunks that there can only be one of have typed
+ * getters and setters, and new chunks are inserted where the [ordering rules](crate::ordering)
+ * allow. Documents are written with a [PngWriter].
+ */
+
+use std::io::{Read, Seek, Write};
+
+use crate::chunks::*;
+use crate::ordering::{Placement, find_rule};
+use crate::reader::PngReader;
+use crate::writer::{DEFAULT_IDAT_SIZE, PngWriter};
+
+/// Getter and setter for a chunk type that there can only be one of
+macro_rules! singleton {
+    ($get:ident, $set:ident, $variant:ident, $chunk:ty, $name:literal) => {
+        #[doc = concat!("The ", $name, " chunk, if there is one")]
+        pub fn $get(&self) -> Option<&$chunk> {
+            self.chunks.iter().find_map(|chunk| match chunk {
+                PngChunkData::$variant(inner) => {
+                    let inner: &$chunk = inner;
+                    Some(inner)
+                }
+                _ => None,
+            })
+        }
+
+        #[doc = concat!("Replace the ", $name, " chunk, or insert one")]
+        pub fn $set(&mut self, chunk: $chunk) {
+            self.set(chunk.into());
+        }
+    };
+}
+
+/// All of the chunks of a PNG/APNG file, in order
+#[derive(Clone, Debug)]
+pub struct PngDocument {
+    /// Chunks in file order, from IHDR to IEND
+    pub chunks: Vec<PngChunkData>,
+
+    /// Maximum length of each IDAT chunk when the document is written
+    pub idat_size: u32,
+}
+
+impl PngDocument {
+    /// Constructor for a new document with no image data
+    pub fn new(ihdr: Ihdr) -> Self {
+        Self {
+            chunks: vec![PngChunkData::Ihdr(ihdr), PngChunkData::Iend],
+            idat_size: DEFAULT_IDAT_SIZE,
+        }
+    }
+
+    /// Read every chunk from a stream
+    pub fn from_stream<R>(stream: R) -> Result<Self, std::io::Error>
+    where
+        R: Read + Seek,
+    {
+        Self::from_reader(&mut PngReader::from_stream(stream)?)
+    }
+
+    /// Read every chunk with a reader, using its [limits](PngReader::limits) and registry
+    pub fn from_reader<R>(reader: &mut PngReader<R>) -> Result<Self, std::io::Error>
+    where
+        R: Read + Seek,
+    {
+        reader.reset_next_chunk_position();
+        let chunks = reader
+            .scan_all_chunks()?
+            .iter()
+            .map(|chunkref| reader.read_chunk(chunkref))
+            .collect::<Result<Vec<PngChunkData>, std::io::Error>>()?;
+
+        Ok(Self {
+            chunks,
+            idat_size: DEFAULT_IDAT_SIZE,
+        })
+    }
+
+    /// Write the document to a stream
+    ///
+    /// Chunks are placed by a [PngWriter], so the file is written in a legal order even if the
+    /// chunks have been edited into an illegal one: chunks that must be before the image data are
+    /// moved before it, and the rest stay on the same side of it, in the same order. Returns the
+    /// stream.
+    pub fn write_to<W>(&self, stream: W) -> Result<W, std::io::Error>
+    where
+        W: Write,
+    {
+        let ihdr = self.ihdr().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "PNG: No IHDR chunk")
+        })?;
+        let mut writer = PngWriter::new(stream, *ihdr)?;
+        writer.idat_size = self.idat_size;
+
+        let first_idat = self
+            .chunks
+            .iter()
+            .position(|c| c.chunktype() == *b"IDAT")
+            .unwrap_or(self.chunks.len());
+        let (before_idat, rest): (Vec<_>, Vec<_>) = self
+            .chunks
+            .iter()
+            .enumerate()
+            .partition(|(i, c)| (*i < first_idat) || before_image_data(c.chunktype()));
+
+        for (_, chunk) in before_idat.into_iter().chain(rest) {
+            match chunk {
+                PngChunkData::Ihdr(_) | PngChunkData::Iend => (),
+                PngChunkData::Idat(idat) => writer.write_image_data(&idat.0)?,
+                _ => writer.add_chunk(chunk.clone())?,
+            }
+        }
+
+        writer.finish()
+    }
+
+    /// The first chunk of a type
+    pub fn get(&self, chunktype: [u8; 4]) -> Option<&PngChunkData> {
+        self.chunks.iter().find(|c| c.chunktype() == chunktype)
+    }
+
+    /// All of the chunks of a type
+    pub fn get_all(&self, chunktype: [u8; 4]) -> impl Iterator<Item = &PngChunkData> {
+        self.chunks
+            .iter()
+            .filter(move |c| c.chunktype() == chunktype)
+    }
+
+    /// Replace the first chunk of the same type, removing any others, or insert it
+    pub fn set(&mut self, chunk: PngChunkData) {
+        let chunktype = chunk.chunktype();
+        match self.chunks.iter().position(|c| c.chunktype() == chunktype) {
+            Some(index) => {
+                self.chunks[index] = chunk;
+                let rest = self.chunks.split_off(index + 1);
+                self.chunks
+                    .extend(rest.into_iter().filter(|c| c.chunktype() != chunktype));
+            }
+
+            None => {
+                self.insert(chunk);
+            }
+        }
+    }
+
+    /// Insert a chunk at the last position its [ordering rule](crate::ordering::RULES) allows
+    ///
+    /// Chunk types without a rule go before IEND. IHDR replaces the existing one, and IDAT is
+    /// added to the end of the image data. Returns the index of the chunk.
+    pub fn insert(&mut self, chunk: PngChunkData) -> usize {
+        let chunktype = chunk.chunktype();
+        let position = |test: &dyn Fn(&PngChunkData) -> bool| self.chunks.iter().position(test);
+        let end = position(&|c| matches!(c, PngChunkData::Iend)).unwrap_or(self.chunks.len());
+        let first_idat = position(&|c| matches!(c, PngChunkData::Idat(_))).unwrap_or(end);
+
+        let index = match chunk {
+            PngChunkData::Ihdr(_) if self.ihdr().is_some() => {
+                self.set(chunk);
+                return 0;
+            }
+
+            PngChunkData::Idat(_) => self
+                .chunks
+                .iter()
+                .rposition(|c| matches!(c, PngChunkData::Idat(_)))
+                .map_or(end, |i| i + 1),
+
+            // Before the chunks that depend on it
+            PngChunkData::Plte(_) => position(&|c| {
+                matches!(c, PngChunkData::Idat(_))
+                    || find_rule(c.chunktype()).is_some_and(|r| r.placement == Placement::AfterPlte)
+            })
+            .unwrap_or(end),
+
+            _ => match find_rule(chunktype).map_or(Placement::Anywhere, |r| r.placement) {
+                Placement::First => 0,
+
+                Placement::BeforePlte => {
+                    position(&|c| matches!(c, PngChunkData::Plte(_) | PngChunkData::Idat(_)))
+                        .unwrap_or(end)
+                }
+
+                Placement::AfterPlte | Placement::BeforeIdat => first_idat,
+
+                Placement::AfterIdat
+                | Placement::Consecutive
+                | Placement::Anywhere
+                | Placement::Last => end,
+            },
+        };
+
+        self.chunks.insert(index, chunk);
+        index
+    }
+
+    /// Remove all chunks of a type, returning them
+    pub fn remove(&mut self, chunktype: [u8; 4]) -> Vec<PngChunkData> {
+        self.remove_if(|c| c.chunktype() == chunktype)
+    }
+
+    /// Remove all chunks that pass a test, returning them
+    pub fn remove_if<F>(&mut self, mut test: F) -> Vec<PngChunkData>
+    where
+        F: FnMut(&PngChunkData) -> bool,
+    {
+        let (removed, kept) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(|c| test(c));
+        self.chunks = kept;
+
+        removed
+    }
+
+    /// All tEXt, zTXt, and iTXt chunks
+    pub fn text_chunks(&self) -> impl Iterator<Item = &PngChunkData> {
+        self.chunks.iter().filter(|c| c.text_keyword().is_some())
+    }
+
+    /// Add a tEXt, zTXt, or iTXt chunk
+    pub fn add_text(&mut self, chunk: PngChunkData) {
+        self.insert(chunk);
+    }
+
+    /// Remove all text chunks with a keyword, returning them
+    pub fn remove_text(&mut self, keyword: &str) -> Vec<PngChunkData> {
+        self.remove_if(|c| c.text_keyword() == Some(keyword))
+    }
+
+    /// Image data of all IDAT chunks as one zlib stream
+    pub fn image_data(&self) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .filter_map(|c| match c {
+                PngChunkData::Idat(idat) => Some(idat.0.as_slice()),
+                _ => None,
+            })
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    /// Replace all of the IDAT chunks with new image data
+    ///
+    /// The data is split into IDAT chunks when the document is written.
+    pub fn set_image_data(&mut self, data: Vec<u8>) {
+        let first_idat = self
+            .chunks
+            .iter()
+            .position(|c| matches!(c, PngChunkData::Idat(_)));
+        self.remove(*b"IDAT");
+
+        let idat = PngChunkData::Idat(Box::new(Idat(data)));
+        match first_idat {
+            Some(index) => self.chunks.insert(index, idat),
+            None => {
+                self.insert(idat);
+            }
+        }
+    }
+
+    singleton!(ihdr, set_ihdr, Ihdr, Ihdr, "IHDR");
+    singleton!(plte, set_plte, Plte, Plte, "PLTE");
+    singleton!(trns, set_trns, Trns, Trns, "tRNS");
+    singleton!(chrm, set_chrm, Chrm, Chrm, "cHRM");
+    singleton!(gama, set_gama, Gama, Gama, "gAMA");
+    singleton!(iccp, set_iccp, Iccp, Iccp, "iCCP");
+    singleton!(sbit, set_sbit, Sbit, Sbit, "sBIT");
+    singleton!(srgb, set_srgb, Srgb, Srgb, "sRGB");
+    singleton!(cicp, set_cicp, Cicp, Cicp, "cICP");
+    singleton!(mdcv, set_mdcv, Mdcv, Mdcv, "mDCV");
+    singleton!(clli, set_clli, Clli, Clli, "cLLI");
+    singleton!(bkgd, set_bkgd, Bkgd, Bkgd, "bKGD");
+    singleton!(hist, set_hist, Hist, Hist, "hIST");
+    singleton!(phys, set_phys, Phys, Phys, "pHYs");
+    singleton!(exif, set_exif, Exif, Exif, "eXIf");
+    singleton!(time, set_time, Time, Time, "tIME");
+    singleton!(offs, set_offs, Offs, Offs, "oFFs");
+    singleton!(pcal, set_pcal, Pcal, Pcal, "pCAL");
+    singleton!(scal, set_scal, Scal, Scal, "sCAL");
+    singleton!(ster, set_ster, Ster, Ster, "sTER");
+    singleton!(actl, set_actl, Actl, Actl, "acTL");
+}
+
+/// Whether a chunk type must be before the first IDAT
+fn before_image_data(chunktype: [u8; 4]) -> bool {
+    find_rule(chunktype).is_some_and(|rule| {
+        matches!(
+            rule.placement,
+            Placement::BeforePlte | Placement::AfterPlte | Placement::BeforeIdat
+        )
+    })
+}
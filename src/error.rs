@@ -0,0 +1,293 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Structured errors
+ *
+ * Functions in this crate return [std::io::Error], so that I/O errors and format errors can be
+ * handled together. Format errors carry an [Error] as their payload, which can be matched on:
+ *
+ * ```
+ * # use std::io::Cursor;
+ * use png_container::Error;
+ * use png_container::reader::PngReader;
+ *
+ * let e = PngReader::from_stream(Cursor::new(b"GIF89a\0\0")).unwrap_err();
+ * assert!(matches!(Error::from_io_error(&e), Some(Error::BadSignature { .. })));
+ * ```
+ */
+
+use crate::chunks::PngChunkRef;
+use crate::limits::LimitExceeded;
+use crate::types::PngFileType;
+
+/// A format error, with the chunk type and file offset where it was found
+///
+/// More variants may be added, so matches need a wildcard arm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The file doesn't start with the expected signature
+    BadSignature {
+        /// Offset of the signature
+        offset: u64,
+    },
+
+    /// The CRC stored after a chunk doesn't match its contents
+    CrcMismatch {
+        chunktype: [u8; 4],
+
+        /// Offset of the start of the chunk
+        offset: u64,
+
+        /// The CRC stored in the file
+        expected: u32,
+
+        /// The CRC computed from the chunk type and data
+        actual: u32,
+    },
+
+    /// A chunk contains a value that is out of range, or can't be parsed
+    InvalidFieldValue {
+        chunktype: [u8; 4],
+
+        /// Offset of the start of the chunk
+        offset: u64,
+
+        message: String,
+    },
+
+    /// A chunk type that isn't allowed in this type of file
+    DisallowedChunk {
+        chunktype: [u8; 4],
+
+        /// Offset of the start of the chunk
+        offset: u64,
+
+        filetype: PngFileType,
+    },
+
+    /// A chunk is in the wrong place relative to other chunks
+    OrderingViolation {
+        chunktype: [u8; 4],
+
+        /// Offset of the start of the chunk
+        offset: u64,
+
+        message: String,
+    },
+
+    /// The data ended before a complete chunk or field could be read
+    TruncatedData {
+        chunktype: Option<[u8; 4]>,
+
+        /// Offset of the start of the chunk, or of where more data was expected
+        offset: u64,
+
+        message: String,
+    },
+
+    /// One of the [Limits](crate::limits::Limits) was exceeded
+    LimitExceeded {
+        chunktype: Option<[u8; 4]>,
+
+        /// Offset of the start of the chunk, if known
+        offset: Option<u64>,
+
+        limit: LimitExceeded,
+    },
+}
+
+impl Error {
+    /// Get the error from the payload of an I/O error, if it has one
+    pub fn from_io_error(e: &std::io::Error) -> Option<&Self> {
+        e.get_ref().and_then(|inner| inner.downcast_ref::<Self>())
+    }
+
+    /// Chunk type the error was found in, if any
+    pub fn chunktype(&self) -> Option<[u8; 4]> {
+        match self {
+            Self::BadSignature { .. } => None,
+            Self::CrcMismatch { chunktype, .. }
+            | Self::InvalidFieldValue { chunktype, .. }
+            | Self::DisallowedChunk { chunktype, .. }
+            | Self::OrderingViolation { chunktype, .. } => Some(*chunktype),
+            Self::TruncatedData { chunktype, .. } | Self::LimitExceeded { chunktype, .. } => {
+                *chunktype
+            }
+        }
+    }
+
+    /// Offset in the file the error was found at, if known
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Self::BadSignature { offset }
+            | Self::CrcMismatch { offset, .. }
+            | Self::InvalidFieldValue { offset, .. }
+            | Self::DisallowedChunk { offset, .. }
+            | Self::OrderingViolation { offset, .. }
+            | Self::TruncatedData { offset, .. } => Some(*offset),
+            Self::LimitExceeded { offset, .. } => *offset,
+        }
+    }
+
+    /// The kind of I/O error this converts into
+    pub fn kind(&self) -> std::io::ErrorKind {
+        match self {
+            Self::TruncatedData { .. } => std::io::ErrorKind::UnexpectedEof,
+            Self::LimitExceeded { .. } => std::io::ErrorKind::QuotaExceeded,
+            _ => std::io::ErrorKind::InvalidData,
+        }
+    }
+
+    /// Add the chunk context to an error from parsing the contents of a chunk
+    ///
+    /// Errors that already have an [Error] payload, and errors of other kinds, are returned
+    /// unchanged.
+    pub(crate) fn in_chunk(e: std::io::Error, chunkref: &PngChunkRef) -> std::io::Error {
+        if Self::from_io_error(&e).is_some() {
+            return e;
+        }
+
+        match e.kind() {
+            std::io::ErrorKind::InvalidData => Self::InvalidFieldValue {
+                chunktype: chunkref.chunktype,
+                offset: chunkref.position,
+                message: e.to_string(),
+            }
+            .into(),
+
+            std::io::ErrorKind::UnexpectedEof => Self::TruncatedData {
+                chunktype: Some(chunkref.chunktype),
+                offset: chunkref.position,
+                message: e.to_string(),
+            }
+            .into(),
+
+            _ => e,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadSignature { offset } => write!(f, "PNG: Bad signature at {}", offset),
+
+            Self::CrcMismatch {
+                chunktype,
+                offset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "PNG: Read CRC ({:#x}) of {} chunk at {} doesn't match the computed one ({:#x})",
+                expected,
+                String::from_utf8_lossy(chunktype),
+                offset,
+                actual
+            ),
+
+            Self::InvalidFieldValue {
+                chunktype,
+                offset,
+                message,
+            } => write!(
+                f,
+                "{} ({} chunk at {})",
+                message,
+                String::from_utf8_lossy(chunktype),
+                offset
+            ),
+
+            Self::DisallowedChunk {
+                chunktype,
+                offset,
+                filetype,
+            } => write!(
+                f,
+                "PNG: {} chunk at {} isn't allowed in {:?} files",
+                String::from_utf8_lossy(chunktype),
+                offset,
+                filetype
+            ),
+
+            Self::OrderingViolation {
+                chunktype,
+                offset,
+                message,
+            } => write!(
+                f,
+                "PNG: {} chunk at {} is out of order ({})",
+                String::from_utf8_lossy(chunktype),
+                offset,
+                message
+            ),
+
+            Self::TruncatedData {
+                chunktype: Some(chunktype),
+                offset,
+                message,
+            } => write!(
+                f,
+                "{} ({} chunk at {})",
+                message,
+                String::from_utf8_lossy(chunktype),
+                offset
+            ),
+
+            Self::TruncatedData {
+                chunktype: None,
+                message,
+                ..
+            } => write!(f, "{}", message),
+
+            Self::LimitExceeded {
+                chunktype: Some(chunktype),
+                offset: Some(offset),
+                limit,
+            } => write!(
+                f,
+                "{} ({} chunk at {})",
+                limit,
+                String::from_utf8_lossy(chunktype),
+                offset
+            ),
+
+            Self::LimitExceeded {
+                chunktype: Some(chunktype),
+                offset: None,
+                limit,
+            } => write!(
+                f,
+                "{} ({} chunk)",
+                limit,
+                String::from_utf8_lossy(chunktype)
+            ),
+
+            Self::LimitExceeded { limit, .. } => write!(f, "{}", limit),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for std::io::Error {
+    fn from(e: Error) -> Self {
+        std::io::Error::new(e.kind(), e)
+    }
+}
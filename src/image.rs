@@ -0,0 +1,405 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Conversion of unfiltered image data into typed pixel buffers
+ */
+
+use crate::chunks::*;
+use crate::decode::check_bit_depth;
+use crate::types::*;
+
+/// Pixel formats for decoded images
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    Gray8,
+    Gray16,
+    GrayAlpha8,
+    GrayAlpha16,
+    Rgb8,
+    Rgb16,
+    Rgba8,
+    Rgba16,
+}
+
+impl PixelFormat {
+    /// The format that holds the image data without loss
+    ///
+    /// Indexed colour images are expanded to RGB, or RGBA if `alpha` is true (i.e. there is a
+    /// tRNS chunk).
+    pub fn for_ihdr(ihdr: &Ihdr, alpha: bool) -> Self {
+        let colour = matches!(
+            ihdr.colour_type,
+            PngColourType::TrueColour
+                | PngColourType::IndexedColour
+                | PngColourType::TrueColourAlpha
+        );
+        let alpha = alpha
+            || matches!(
+                ihdr.colour_type,
+                PngColourType::GreyscaleAlpha | PngColourType::TrueColourAlpha
+            );
+
+        match (colour, alpha, ihdr.bit_depth == 16) {
+            (false, false, false) => Self::Gray8,
+            (false, false, true) => Self::Gray16,
+            (false, true, false) => Self::GrayAlpha8,
+            (false, true, true) => Self::GrayAlpha16,
+            (true, false, false) => Self::Rgb8,
+            (true, false, true) => Self::Rgb16,
+            (true, true, false) => Self::Rgba8,
+            (true, true, true) => Self::Rgba16,
+        }
+    }
+
+    /// Number of channels per pixel
+    pub fn channels(&self) -> usize {
+        match self {
+            Self::Gray8 | Self::Gray16 => 1,
+            Self::GrayAlpha8 | Self::GrayAlpha16 => 2,
+            Self::Rgb8 | Self::Rgb16 => 3,
+            Self::Rgba8 | Self::Rgba16 => 4,
+        }
+    }
+
+    /// Does this format have 16 bits per channel?
+    pub fn is_16bit(&self) -> bool {
+        matches!(
+            self,
+            Self::Gray16 | Self::GrayAlpha16 | Self::Rgb16 | Self::Rgba16
+        )
+    }
+
+    /// Does this format have an alpha channel?
+    pub fn has_alpha(&self) -> bool {
+        matches!(
+            self,
+            Self::GrayAlpha8 | Self::GrayAlpha16 | Self::Rgba8 | Self::Rgba16
+        )
+    }
+
+    /// Does this format have colour channels?
+    pub fn is_colour(&self) -> bool {
+        matches!(self, Self::Rgb8 | Self::Rgb16 | Self::Rgba8 | Self::Rgba16)
+    }
+}
+
+/// Options for decoding images
+#[derive(Copy, Clone, Debug)]
+pub struct DecodeOptions {
+    /// Pixel format of the decoded image
+    pub format: PixelFormat,
+
+    /// Apply the tRNS chunk, if there is one and the format has an alpha channel
+    pub apply_trns: bool,
+
+    /// Rescale samples to their full range according to the sBIT chunk, if there is one
+    pub scale_sbit: bool,
+}
+
+impl DecodeOptions {
+    /// Constructor
+    ///
+    /// tRNS is applied and sBIT is ignored.
+    pub fn new(format: PixelFormat) -> Self {
+        Self {
+            format,
+            apply_trns: true,
+            scale_sbit: false,
+        }
+    }
+}
+
+/// Pixel data of a decoded image
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageBuffer {
+    /// 8 bits per channel
+    U8(Vec<u8>),
+
+    /// 16 bits per channel
+    U16(Vec<u16>),
+}
+
+/// A decoded image
+#[derive(Clone, Debug)]
+pub struct DecodedImage {
+    /// Width in pixels
+    pub width: u32,
+
+    /// Height in pixels
+    pub height: u32,
+
+    /// Pixel format
+    pub format: PixelFormat,
+
+    /// Pixel data, in rows from the top with channels interleaved
+    pub data: ImageBuffer,
+}
+
+/// Converts unfiltered (and deinterlaced) image data into a pixel format
+///
+/// Images without an alpha channel decoded to a format with one get an opaque alpha channel,
+/// unless a tRNS chunk says otherwise. Alpha is dropped, not composited, when decoding to a
+/// format without an alpha channel. Colour is converted to grey using the Rec. 709 luma
+/// coefficients.
+#[derive(Clone, Debug)]
+pub struct PixelConverter {
+    pub ihdr: Ihdr,
+    pub plte: Option<Plte>,
+    pub trns: Option<Trns>,
+    pub sbit: Option<Sbit>,
+}
+
+impl PixelConverter {
+    /// Constructor
+    pub fn new(ihdr: &Ihdr) -> Self {
+        Self {
+            ihdr: *ihdr,
+            plte: None,
+            trns: None,
+            sbit: None,
+        }
+    }
+
+    /// Remember a PLTE, tRNS, or sBIT chunk for use in conversion
+    ///
+    /// Other chunk types are ignored.
+    pub fn add_chunk(&mut self, chunk: &PngChunkData) {
+        match chunk {
+            PngChunkData::Plte(plte) => self.plte = Some(*plte.clone()),
+            PngChunkData::Trns(trns) => self.trns = Some(*trns.clone()),
+            PngChunkData::Sbit(sbit) => self.sbit = Some(*sbit),
+            _ => (),
+        }
+    }
+
+    /// Significant bits of the grey/red, green, blue, and alpha channels
+    fn significant_bits(&self, options: &DecodeOptions) -> [u8; 4] {
+        let bd = if self.ihdr.colour_type == PngColourType::IndexedColour {
+            8
+        } else {
+            self.ihdr.bit_depth
+        };
+
+        let Some(sbit) = self.sbit.filter(|_| options.scale_sbit) else {
+            return [bd; 4];
+        };
+
+        let [r, g, b, a] = match sbit {
+            Sbit::Greyscale { grey_bits } => [grey_bits, grey_bits, grey_bits, bd],
+            Sbit::Colour {
+                red_bits,
+                green_bits,
+                blue_bits,
+            } => [red_bits, green_bits, blue_bits, bd],
+            Sbit::GreyscaleAlpha {
+                grey_bits,
+                alpha_bits,
+            } => [grey_bits, grey_bits, grey_bits, alpha_bits],
+            Sbit::TrueColourAlpha {
+                red_bits,
+                green_bits,
+                blue_bits,
+                alpha_bits,
+            } => [red_bits, green_bits, blue_bits, alpha_bits],
+        };
+
+        // Ignore invalid values
+        [r, g, b, a].map(|bits| if (1..=bd).contains(&bits) { bits } else { bd })
+    }
+
+    /// Convert image data
+    ///
+    /// `data`: Unfiltered rows of packed pixels, as described by the IHDR chunk.
+    /// `width`/`height`: Dimensions of the image, which may differ from IHDR for APNG frames.
+    pub fn convert(
+        &self,
+        data: &[u8],
+        width: u32,
+        height: u32,
+        options: &DecodeOptions,
+    ) -> Result<DecodedImage, std::io::Error> {
+        check_bit_depth(&self.ihdr)?;
+
+        let colour_type = self.ihdr.colour_type;
+        let bit_depth = self.ihdr.bit_depth as usize;
+        let num_components = colour_type.num_components() as usize;
+        let line_size = (width as usize * self.ihdr.pixel_bits() as usize).div_ceil(8);
+        let size = line_size * height as usize;
+        if data.len() < size {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("PNG: Image data is too short ({} < {})", data.len(), size),
+            ));
+        }
+
+        let palette = match (&self.plte, colour_type) {
+            (Some(plte), _) => plte.0.as_slice(),
+            (None, PngColourType::IndexedColour) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "PNG: Indexed colour image without a PLTE chunk",
+                ));
+            }
+            (None, _) => &[],
+        };
+        let trns = self
+            .trns
+            .as_ref()
+            .filter(|_| options.apply_trns && options.format.has_alpha());
+        let sig_bits = self.significant_bits(options);
+
+        let channels = options.format.channels();
+        let mut out = Vec::with_capacity(width as usize * height as usize * channels);
+        let mut samples = [0_u16; 4];
+        for row in data[..size]
+            .chunks_exact(line_size.max(1))
+            .take(height as usize)
+        {
+            for x in 0..width as usize {
+                for (i, sample) in samples.iter_mut().enumerate().take(num_components) {
+                    *sample = read_sample(row, x * num_components + i, bit_depth);
+                }
+
+                // Expand into RGBA at 16 bits per channel
+                let rgba = match colour_type {
+                    PngColourType::Greyscale => {
+                        let grey = scale_sample(samples[0], bit_depth, sig_bits[0]);
+                        let opaque = !matches!(trns, Some(Trns::Greyscale { value }) if *value == samples[0]);
+                        [grey, grey, grey, if opaque { 0xffff } else { 0 }]
+                    }
+
+                    PngColourType::TrueColour => {
+                        let opaque = !matches!(trns,
+                            Some(Trns::TrueColour { red, green, blue })
+                                if [*red, *green, *blue] == samples[0..3]);
+                        [
+                            scale_sample(samples[0], bit_depth, sig_bits[0]),
+                            scale_sample(samples[1], bit_depth, sig_bits[1]),
+                            scale_sample(samples[2], bit_depth, sig_bits[2]),
+                            if opaque { 0xffff } else { 0 },
+                        ]
+                    }
+
+                    PngColourType::IndexedColour => {
+                        let index = samples[0] as usize;
+                        let entry = palette.get(index).ok_or_else(|| {
+                            std::io::Error::new(
+                                std::io::ErrorKind::InvalidData,
+                                format!(
+                                    "PNG: Palette index ({}) out of range ({} entries)",
+                                    index,
+                                    palette.len()
+                                ),
+                            )
+                        })?;
+                        let alpha = match trns {
+                            Some(Trns::IndexedColour { values }) => {
+                                values.get(index).copied().unwrap_or(0xff)
+                            }
+                            _ => 0xff,
+                        };
+                        [
+                            scale_sample(entry.red as u16, 8, sig_bits[0]),
+                            scale_sample(entry.green as u16, 8, sig_bits[1]),
+                            scale_sample(entry.blue as u16, 8, sig_bits[2]),
+                            alpha as u16 * 257,
+                        ]
+                    }
+
+                    PngColourType::GreyscaleAlpha => {
+                        let grey = scale_sample(samples[0], bit_depth, sig_bits[0]);
+                        [
+                            grey,
+                            grey,
+                            grey,
+                            scale_sample(samples[1], bit_depth, sig_bits[3]),
+                        ]
+                    }
+
+                    PngColourType::TrueColourAlpha => [
+                        scale_sample(samples[0], bit_depth, sig_bits[0]),
+                        scale_sample(samples[1], bit_depth, sig_bits[1]),
+                        scale_sample(samples[2], bit_depth, sig_bits[2]),
+                        scale_sample(samples[3], bit_depth, sig_bits[3]),
+                    ],
+                };
+
+                let is_grey = matches!(
+                    colour_type,
+                    PngColourType::Greyscale | PngColourType::GreyscaleAlpha
+                );
+                let grey = if is_grey {
+                    rgba[0]
+                } else {
+                    luma(rgba[0], rgba[1], rgba[2])
+                };
+
+                match options.format.channels() {
+                    1 => out.push(grey),
+                    2 => out.extend_from_slice(&[grey, rgba[3]]),
+                    3 => out.extend_from_slice(&rgba[0..3]),
+                    _ => out.extend_from_slice(&rgba),
+                }
+            }
+        }
+
+        let data = if options.format.is_16bit() {
+            ImageBuffer::U16(out)
+        } else {
+            ImageBuffer::U8(
+                out.into_iter()
+                    .map(|v| ((v as u32 * 255 + 32767) / 65535) as u8)
+                    .collect(),
+            )
+        };
+
+        Ok(DecodedImage {
+            width,
+            height,
+            format: options.format,
+            data,
+        })
+    }
+}
+
+/// Read sample number `index` from a row of packed samples
+fn read_sample(row: &[u8], index: usize, bit_depth: usize) -> u16 {
+    match bit_depth {
+        16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+        8 => row[index] as u16,
+        _ => {
+            let bit = index * bit_depth;
+            let shift = 8 - bit_depth - (bit % 8);
+            ((row[bit / 8] >> shift) & ((1 << bit_depth) - 1)) as u16
+        }
+    }
+}
+
+/// Scale a sample to 16 bits, taking only the significant bits
+fn scale_sample(sample: u16, bit_depth: usize, significant_bits: u8) -> u16 {
+    let bits = significant_bits as usize;
+    let value = (sample >> (bit_depth - bits)) as u32;
+    let max = (1_u32 << bits) - 1;
+
+    ((value * 0xffff + max / 2) / max) as u16
+}
+
+/// Rec. 709 luma, in 15-bit fixed point
+fn luma(red: u16, green: u16, blue: u16) -> u16 {
+    ((red as u32 * 6966 + green as u32 * 23436 + blue as u32 * 2366 + 16384) >> 15) as u16
+}
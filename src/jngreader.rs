@@ -19,9 +19,15 @@
 /*! JNG reader
  */
 
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Read, Seek, SeekFrom, Write};
 
+use crate::bodyreader::ChunkBodyReader;
 use crate::chunks::*;
+use crate::error::Error;
+use crate::limits::*;
+use crate::registry::ChunkRegistry;
+use crate::rewrite::*;
+use crate::strip::*;
 use crate::types::*;
 
 /// A JNG file reader
@@ -45,7 +51,16 @@ pub struct JngReader<R> {
     /// The IEND chunk
     pub iend: PngChunkRef,
 
+    /// User-defined chunk types to consult when reading chunks
+    pub registry: ChunkRegistry,
+
+    /// Resource limits for reading untrusted files
+    pub limits: Limits,
+
     next_chunk_pos: u64,
+    chunk_count: u64,
+    scanned_to: u64,
+    text_size: u64,
 }
 
 impl<R> JngReader<R>
@@ -59,7 +74,7 @@ where
             let mut signature = [0; 8];
             stream.read_exact(&mut signature)?;
             if signature != [0x8b, 0x4a, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a] {
-                return Err(std::io::Error::other("JNG: Bad file signature"));
+                return Err(Error::BadSignature { offset: 0 }.into());
             }
         }
 
@@ -70,7 +85,12 @@ where
             stream,
             jhdr: None,
             iend: PngChunkRef::default(),
+            registry: ChunkRegistry::default(),
+            limits: Limits::default(),
             next_chunk_pos: 8,
+            chunk_count: 0,
+            scanned_to: 0,
+            text_size: 0,
         })
     }
 
@@ -122,10 +142,21 @@ where
             | (chunkref.chunktype == *b"fcTL")
             | (chunkref.chunktype == *b"fdAT")
         {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("JNG: Invalid chunk type \"{:?}\"", chunkref.chunktype),
-            ));
+            return Err(Error::DisallowedChunk {
+                chunktype: chunkref.chunktype,
+                offset: chunkref.position,
+                filetype: PngFileType::Jng,
+            }
+            .into());
+        }
+
+        // Only count each chunk once, however many times the file is scanned
+        if chunkref.position >= self.scanned_to {
+            self.chunk_count += 1;
+            self.limits
+                .check_chunk_count(self.chunk_count)
+                .map_err(|e| e.in_chunk(&chunkref))?;
+            self.scanned_to = chunkref.position + 4 + 4 + chunkref.length as u64 + 4;
         }
 
         self.next_chunk_pos += 4 + 4 + chunkref.length as u64 + 4;
@@ -135,6 +166,9 @@ where
                 let oldpos = self.stream.stream_position()?;
                 // Fill in image metadata
                 if let PngChunkData::Jhdr(jhdr) = chunkref.read_chunk(&mut self.stream, None)? {
+                    self.limits
+                        .check_image_size(jhdr.width, jhdr.height, jhdr.pixel_bits())
+                        .map_err(|e| e.in_chunk(&chunkref))?;
                     self.jhdr = Some(*jhdr);
                     self.width = jhdr.width;
                     self.height = jhdr.height;
@@ -170,10 +204,81 @@ where
     }
 
     /// Read the chunk data after seeking to the start of its data
+    ///
+    /// Chunks longer than [max_chunk_length](Limits::max_chunk_length) are rejected before
+    /// anything is allocated.
     pub fn read_chunk(&mut self, chunkref: &PngChunkRef) -> Result<PngChunkData, std::io::Error>
     where
         R: Read + Seek,
     {
-        chunkref.read_chunk(&mut self.stream, None)
+        self.limits
+            .check_chunk_length(chunkref.length)
+            .map_err(|e| e.in_chunk(chunkref))?;
+        chunkref.read_chunk_with_registry(&mut self.stream, None, &self.registry)
+    }
+
+    /// Stream the raw data of a chunk after seeking to the start of its data
+    ///
+    /// Unlike read_chunk(), this doesn't read the whole chunk into memory. The CRC is checked
+    /// once all of the data has been read.
+    pub fn chunk_body_reader(
+        &mut self,
+        chunkref: &PngChunkRef,
+    ) -> Result<ChunkBodyReader<&mut R>, std::io::Error> {
+        self.stream
+            .seek(SeekFrom::Start(chunkref.position + 4 + 4))?;
+        Ok(ChunkBodyReader::new(&mut self.stream, *chunkref))
+    }
+
+    /// Get the string of a tEXt, zTXt, or iTXt chunk
+    ///
+    /// Decompressed text counts towards the [max_text_size](Limits::max_text_size) limit, which
+    /// is shared by all of the chunks in the file. Returns None for other chunk types.
+    pub fn text_string(&mut self, chunk: &PngChunkData) -> Result<Option<String>, std::io::Error> {
+        text_string(&self.limits, &mut self.text_size, chunk)
+    }
+
+    /// Get the decompressed profile of an iCCP chunk
+    ///
+    /// Returns None for other chunk types.
+    pub fn iccp_profile(&self, chunk: &PngChunkData) -> Result<Option<Vec<u8>>, std::io::Error> {
+        iccp_profile(&self.limits, chunk)
+    }
+
+    /// Write a copy of the file with chunks inserted, replaced, or deleted
+    ///
+    /// Chunks that none of the operations apply to are copied from the stream byte-for-byte, CRC
+    /// included, without being parsed. Only new chunks are serialised. If critical chunks are
+    /// changed, unrecognised ancillary chunks that aren't safe to copy are deleted. Returns
+    /// references to the chunks written.
+    pub fn rewrite<W>(
+        &mut self,
+        ops: &[RewriteOp],
+        out: W,
+    ) -> Result<Vec<PngChunkRef>, std::io::Error>
+    where
+        W: Write,
+    {
+        self.reset_next_chunk_position();
+        let chunks = self.scan_all_chunks()?;
+
+        Ok(rewrite_chunks(&mut self.stream, &self.limits, &chunks, ops, out)?.written)
+    }
+
+    /// Write a copy of the file with ancillary chunks removed by a policy
+    ///
+    /// The chunks that are kept are copied byte-for-byte.
+    pub fn strip<W>(&mut self, policy: StripPolicy, out: W) -> Result<StripReport, std::io::Error>
+    where
+        W: Write,
+    {
+        self.reset_next_chunk_position();
+        let chunks = self.scan_all_chunks()?;
+        let ops = [RewriteOp::Delete(ChunkSelector::Predicate(Box::new(
+            move |chunkref| policy.strips(chunkref),
+        )))];
+
+        let rewritten = rewrite_chunks(&mut self.stream, &self.limits, &chunks, &ops, out)?;
+        Ok(StripReport::new(rewritten.removed))
     }
 }
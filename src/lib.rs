@@ -22,21 +22,51 @@
  * and [JNG](https://en.wikipedia.org/wiki/JPEG_Network_Graphics) files.
  * Maybe [MNG](https://en.wikipedia.org/wiki/Multiple-image_Network_Graphics) in the future.
  *
- * Note that this crate only concerns itself with the 'container' aspect of
- * a PNG/APNG/etc file. It implements structs and enums for working with chunks
- * and their data, but leaves the decoding of that data into images up to
- * another crate.
+ * This crate is mainly concerned with the 'container' aspect of a PNG/APNG/etc
+ * file. It implements structs and enums for working with chunks and their data.
+ * With the `decode` feature, PNG image data can also be decompressed, unfiltered,
+ * deinterlaced, and converted into pixel buffers by the `decode` and `image` modules.
+ * Decoding JNG (i.e. JPEG) image data is left up to another crate.
  */
 
+#[cfg(feature = "decode")]
+pub mod adam7;
+#[cfg(feature = "tokio")]
+pub mod asyncreader;
+pub mod bodyreader;
 pub mod chunks;
+pub mod countingwriter;
 pub mod crc;
+#[cfg(feature = "decode")]
+pub mod decode;
+pub mod document;
+pub mod error;
+#[cfg(feature = "decode")]
+pub mod image;
 pub mod jngreader;
+pub mod limits;
+pub mod ordering;
+pub mod pushparser;
 pub mod reader;
+pub mod recovery;
+pub mod registry;
+pub mod rewrite;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod slice;
+pub mod streamreader;
+pub mod strip;
 pub mod types;
+pub mod validate;
+pub mod writer;
 
+pub use error::Error;
+
+/// Convert an error from parsing chunk data into an [InvalidData](std::io::ErrorKind::InvalidData)
+/// I/O error
 pub fn to_io_error<T>(e: T) -> std::io::Error
 where
     T: ToString,
 {
-    std::io::Error::other(e.to_string())
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
 }
@@ -0,0 +1,276 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Resource limits for reading untrusted files
+ *
+ * Every reader and parser has a `limits` field, which starts out as [Limits::default()].
+ * Exceeding a limit returns an I/O error of kind
+ * [QuotaExceeded](std::io::ErrorKind::QuotaExceeded), with an [Error::LimitExceeded] as its
+ * payload.
+ *
+ * The decompressing methods of the chunks themselves, e.g. [Ztxt::string()], aren't limited.
+ * Use the `*_with_limit()` versions, or the readers' `text_string()` and `iccp_profile()`, for
+ * untrusted files.
+ */
+
+use std::io::Read;
+
+use flate2::bufread::ZlibDecoder;
+
+use crate::chunks::*;
+use crate::error::Error;
+
+/// Which limit was exceeded
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LimitKind {
+    /// Length of a single chunk's data
+    ChunkLength,
+
+    /// Total size of decompressed text
+    TextSize,
+
+    /// Size of a decompressed ICC profile
+    IccProfileSize,
+
+    /// Number of chunks in a file
+    ChunkCount,
+
+    /// Size of the decoded image in bytes
+    ImageSize,
+}
+
+impl LimitKind {
+    fn description(&self) -> &'static str {
+        match self {
+            Self::ChunkLength => "Chunk length",
+            Self::TextSize => "Decompressed text size",
+            Self::IccProfileSize => "Decompressed ICC profile size",
+            Self::ChunkCount => "Number of chunks",
+            Self::ImageSize => "Image size",
+        }
+    }
+}
+
+/// The limit that was exceeded, and its value
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LimitExceeded {
+    /// The limit that was exceeded
+    pub kind: LimitKind,
+
+    /// Value of the limit
+    pub limit: u64,
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PNG: {} exceeds the limit ({})",
+            self.kind.description(),
+            self.limit
+        )
+    }
+}
+
+impl LimitExceeded {
+    /// Add the chunk it was exceeded in
+    pub fn in_chunk(self, chunkref: &PngChunkRef) -> Error {
+        Error::LimitExceeded {
+            chunktype: Some(chunkref.chunktype),
+            offset: Some(chunkref.position),
+            limit: self,
+        }
+    }
+}
+
+impl From<LimitExceeded> for Error {
+    fn from(limit: LimitExceeded) -> Self {
+        Error::LimitExceeded {
+            chunktype: None,
+            offset: None,
+            limit,
+        }
+    }
+}
+
+impl From<LimitExceeded> for std::io::Error {
+    fn from(e: LimitExceeded) -> Self {
+        Error::from(e).into()
+    }
+}
+
+/// Limits on the resources used when reading a file
+///
+/// The defaults are generous for real images, but reject decompression bombs and headers that
+/// would cause huge allocations. Use [unlimited()](Self::unlimited) for trusted files.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum length of the data of a single chunk read into memory
+    pub max_chunk_length: u32,
+
+    /// Maximum total size of text decompressed from zTXt and iTXt chunks
+    pub max_text_size: u64,
+
+    /// Maximum size of a decompressed ICC profile
+    pub max_icc_profile_size: u64,
+
+    /// Maximum number of chunks in a file
+    pub max_chunks: u64,
+
+    /// Maximum size of the decoded image in bytes, i.e. width × height × bytes per pixel
+    pub max_image_size: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_chunk_length: 64 << 20,
+            max_text_size: 16 << 20,
+            max_icc_profile_size: 16 << 20,
+            max_chunks: 1 << 20,
+            max_image_size: 1 << 30,
+        }
+    }
+}
+
+impl Limits {
+    /// No limits at all
+    pub fn unlimited() -> Self {
+        Self {
+            max_chunk_length: u32::MAX,
+            max_text_size: u64::MAX,
+            max_icc_profile_size: u64::MAX,
+            max_chunks: u64::MAX,
+            max_image_size: u64::MAX,
+        }
+    }
+
+    /// Check the length of a chunk before reading it into memory
+    pub fn check_chunk_length(&self, length: u32) -> Result<(), LimitExceeded> {
+        check(
+            LimitKind::ChunkLength,
+            length as u64,
+            self.max_chunk_length as u64,
+        )
+    }
+
+    /// Check the number of chunks read so far
+    pub fn check_chunk_count(&self, count: u64) -> Result<(), LimitExceeded> {
+        check(LimitKind::ChunkCount, count, self.max_chunks)
+    }
+
+    /// Check the size of an image with the given dimensions and bits per pixel
+    pub fn check_image_size(
+        &self,
+        width: u32,
+        height: u32,
+        pixel_bits: u8,
+    ) -> Result<(), LimitExceeded> {
+        let line_size = (width as u64 * pixel_bits as u64).div_ceil(8);
+        check(
+            LimitKind::ImageSize,
+            line_size.saturating_mul(height as u64),
+            self.max_image_size,
+        )
+    }
+}
+
+/// Get the string of a text chunk, counting decompressed text against the limit
+///
+/// `text_size`: Total size of text decompressed so far, which is updated.
+pub(crate) fn text_string(
+    limits: &Limits,
+    text_size: &mut u64,
+    chunk: &PngChunkData,
+) -> std::io::Result<Option<String>> {
+    let remaining = limits.max_text_size.saturating_sub(*text_size);
+    let (chunktype, result) = match chunk {
+        PngChunkData::Text(text) => return Ok(Some(text.string.clone())),
+        PngChunkData::Ztxt(ztxt) => (Ztxt::TYPE, ztxt.string_with_limit(remaining)),
+        PngChunkData::Itxt(itxt) => (Itxt::TYPE, itxt.string_with_limit(remaining)),
+        _ => return Ok(None),
+    };
+    let string = result.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::QuotaExceeded {
+            // Report the total limit, not what was left of it
+            Error::LimitExceeded {
+                chunktype: Some(chunktype),
+                offset: None,
+                limit: LimitExceeded {
+                    kind: LimitKind::TextSize,
+                    limit: limits.max_text_size,
+                },
+            }
+            .into()
+        } else {
+            e
+        }
+    })?;
+
+    *text_size += string.len() as u64;
+
+    Ok(Some(string))
+}
+
+/// Get the decompressed profile of an iCCP chunk
+pub(crate) fn iccp_profile(
+    limits: &Limits,
+    chunk: &PngChunkData,
+) -> std::io::Result<Option<Vec<u8>>> {
+    match chunk {
+        PngChunkData::Iccp(iccp) => Ok(Some(iccp.profile_with_limit(limits.max_icc_profile_size)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Return a [LimitExceeded] error if a value is over a limit
+fn check(kind: LimitKind, value: u64, limit: u64) -> Result<(), LimitExceeded> {
+    if value > limit {
+        return Err(LimitExceeded { kind, limit });
+    }
+
+    Ok(())
+}
+
+/// Decompress zlib data from a chunk, stopping as soon as the output would be larger than
+/// `max_size`
+pub(crate) fn inflate_limited(
+    data: &[u8],
+    max_size: u64,
+    kind: LimitKind,
+    chunktype: [u8; 4],
+) -> std::io::Result<Vec<u8>> {
+    let mut decoder = ZlibDecoder::new(data).take(max_size.saturating_add(1));
+    let mut out = Vec::new();
+    decoder
+        .read_to_end(&mut out)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    if out.len() as u64 > max_size {
+        return Err(Error::LimitExceeded {
+            chunktype: Some(chunktype),
+            offset: None,
+            limit: LimitExceeded {
+                kind,
+                limit: max_size,
+            },
+        }
+        .into());
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,276 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Chunk ordering and multiplicity rules of PNG and APNG files
+ *
+ * The rules are in a table, [RULES], with one entry per chunk type. Chunk types that aren't in
+ * the table may appear anywhere between IHDR and IEND, any number of times.
+ *
+ * An [OrderValidator] is fed chunks one at a time, so it can be used while reading or writing.
+ */
+
+use std::collections::HashSet;
+
+use crate::chunks::PngChunkRef;
+use crate::error::Error;
+
+/// Where a chunk may appear relative to other chunks
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// The first chunk
+    First,
+
+    /// The last chunk
+    Last,
+
+    /// Before PLTE and the first IDAT
+    BeforePlte,
+
+    /// After PLTE, if there is one, and before the first IDAT
+    AfterPlte,
+
+    /// Before the first IDAT
+    BeforeIdat,
+
+    /// After the first IDAT
+    AfterIdat,
+
+    /// In a single run of consecutive chunks
+    Consecutive,
+
+    /// Anywhere between the first and last chunks
+    Anywhere,
+}
+
+/// Ordering and multiplicity rule for a chunk type
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ChunkRule {
+    pub chunktype: [u8; 4],
+
+    /// Where the chunk may appear
+    pub placement: Placement,
+
+    /// Can there be more than one of this chunk?
+    pub multiple: bool,
+}
+
+const fn rule(chunktype: &[u8; 4], placement: Placement, multiple: bool) -> ChunkRule {
+    ChunkRule {
+        chunktype: *chunktype,
+        placement,
+        multiple,
+    }
+}
+
+/// Rules for the chunk types of PNG and APNG files
+pub const RULES: &[ChunkRule] = &[
+    // Critical chunks
+    rule(b"IHDR", Placement::First, false),
+    rule(b"PLTE", Placement::BeforeIdat, false),
+    rule(b"IDAT", Placement::Consecutive, true),
+    rule(b"IEND", Placement::Last, false),
+    // Colour space information
+    rule(b"cHRM", Placement::BeforePlte, false),
+    rule(b"gAMA", Placement::BeforePlte, false),
+    rule(b"iCCP", Placement::BeforePlte, false),
+    rule(b"sBIT", Placement::BeforePlte, false),
+    rule(b"sRGB", Placement::BeforePlte, false),
+    rule(b"cICP", Placement::BeforePlte, false),
+    rule(b"mDCV", Placement::BeforePlte, false),
+    rule(b"cLLI", Placement::BeforePlte, false),
+    // Palette-dependent chunks
+    rule(b"tRNS", Placement::AfterPlte, false),
+    rule(b"bKGD", Placement::AfterPlte, false),
+    rule(b"hIST", Placement::AfterPlte, false),
+    // Other chunks about the whole image
+    rule(b"pHYs", Placement::BeforeIdat, false),
+    rule(b"sPLT", Placement::BeforeIdat, true),
+    rule(b"eXIf", Placement::BeforeIdat, false),
+    rule(b"oFFs", Placement::BeforeIdat, false),
+    rule(b"pCAL", Placement::BeforeIdat, false),
+    rule(b"sCAL", Placement::BeforeIdat, false),
+    rule(b"sTER", Placement::BeforeIdat, false),
+    rule(b"iDOT", Placement::BeforeIdat, false),
+    rule(b"tIME", Placement::Anywhere, false),
+    rule(b"tEXt", Placement::Anywhere, true),
+    rule(b"zTXt", Placement::Anywhere, true),
+    rule(b"iTXt", Placement::Anywhere, true),
+    rule(b"gIFg", Placement::Anywhere, true),
+    rule(b"gIFx", Placement::Anywhere, true),
+    // Animation
+    rule(b"acTL", Placement::BeforeIdat, false),
+    rule(b"fcTL", Placement::Anywhere, true),
+    rule(b"fdAT", Placement::AfterIdat, true),
+];
+
+/// Find the rule for a chunk type
+pub fn find_rule(chunktype: [u8; 4]) -> Option<&'static ChunkRule> {
+    RULES.iter().find(|rule| rule.chunktype == chunktype)
+}
+
+/// Checks the order and number of chunks as they are read or written
+#[derive(Clone, Debug, Default)]
+pub struct OrderValidator {
+    seen: HashSet<[u8; 4]>,
+    after_plte_seen: Option<[u8; 4]>,
+    last_chunktype: Option<[u8; 4]>,
+    seen_plte: bool,
+    seen_idat: bool,
+    idat_ended: bool,
+    seen_iend: bool,
+    ihdr_not_first: bool,
+}
+
+impl OrderValidator {
+    /// Constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check the next chunk, returning any rules it breaks
+    pub fn check(&mut self, chunkref: &PngChunkRef) -> Vec<Error> {
+        let chunktype = chunkref.chunktype;
+        let mut violations = Vec::new();
+        let mut violation = |message: String| {
+            violations.push(Error::OrderingViolation {
+                chunktype,
+                offset: chunkref.position,
+                message,
+            })
+        };
+
+        if self.seen_iend {
+            violation("PNG: Chunk after IEND".to_string());
+        } else if self.last_chunktype.is_none() && (chunktype != *b"IHDR") {
+            violation("PNG: IHDR must be the first chunk".to_string());
+            self.ihdr_not_first = true;
+        }
+
+        let rule = find_rule(chunktype);
+        if let Some(rule) = rule {
+            if !rule.multiple && self.seen.contains(&chunktype) {
+                violation(format!(
+                    "PNG: Only one {} chunk is allowed",
+                    chunkref.type_str()
+                ));
+            }
+
+            match rule.placement {
+                // Only reported once, against whichever chunk came first
+                Placement::First => {
+                    if self.last_chunktype.is_some() && !self.ihdr_not_first {
+                        violation("PNG: IHDR must be the first chunk".to_string());
+                    }
+                }
+
+                Placement::Last => {
+                    if !self.seen_idat {
+                        violation("PNG: No IDAT chunks before IEND".to_string());
+                    }
+                }
+
+                Placement::BeforePlte => {
+                    if self.seen_plte {
+                        violation(format!("PNG: {} must be before PLTE", chunkref.type_str()));
+                    }
+                    if self.seen_idat {
+                        violation(format!("PNG: {} must be before IDAT", chunkref.type_str()));
+                    }
+                }
+
+                Placement::AfterPlte | Placement::BeforeIdat => {
+                    if self.seen_idat {
+                        violation(format!("PNG: {} must be before IDAT", chunkref.type_str()));
+                    }
+                }
+
+                Placement::AfterIdat => {
+                    if !self.seen_idat {
+                        violation(format!("PNG: {} must be after IDAT", chunkref.type_str()));
+                    }
+                }
+
+                Placement::Consecutive => {
+                    if self.idat_ended {
+                        violation(format!(
+                            "PNG: {} chunks must be consecutive",
+                            chunkref.type_str()
+                        ));
+                    }
+                }
+
+                Placement::Anywhere => (),
+            }
+
+            if chunktype == *b"PLTE"
+                && let Some(after_plte) = self.after_plte_seen
+            {
+                violation(format!(
+                    "PNG: PLTE must be before {}",
+                    String::from_utf8_lossy(&after_plte)
+                ));
+            }
+
+            if (rule.placement == Placement::AfterPlte) && self.after_plte_seen.is_none() {
+                self.after_plte_seen = Some(chunktype);
+            }
+        }
+
+        if self.seen_idat && (self.last_chunktype == Some(*b"IDAT")) && (chunktype != *b"IDAT") {
+            self.idat_ended = true;
+        }
+        self.seen_plte |= chunktype == *b"PLTE";
+        self.seen_idat |= chunktype == *b"IDAT";
+        self.seen_iend |= chunktype == *b"IEND";
+        self.seen.insert(chunktype);
+        self.last_chunktype = Some(chunktype);
+
+        violations
+    }
+
+    /// Check that the chunks were complete, once there are no more
+    ///
+    /// `end`: Offset of the end of the file.
+    pub fn finish(&self, end: u64) -> Vec<Error> {
+        if self.seen_iend {
+            return Vec::new();
+        }
+
+        vec![Error::OrderingViolation {
+            chunktype: *b"IEND",
+            offset: end,
+            message: "PNG: IEND must be the last chunk".to_string(),
+        }]
+    }
+}
+
+/// Check the order and number of a whole file's chunks
+pub fn check_chunk_order(chunks: &[PngChunkRef]) -> Vec<Error> {
+    let mut validator = OrderValidator::new();
+    let mut violations: Vec<Error> = chunks
+        .iter()
+        .flat_map(|chunkref| validator.check(chunkref))
+        .collect();
+
+    let end = chunks
+        .last()
+        .map_or(8, |c| c.position + 4 + 4 + c.length as u64 + 4);
+    violations.extend(validator.finish(end));
+
+    violations
+}
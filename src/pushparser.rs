@@ -0,0 +1,411 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Push-based incremental PNG parser
+ *
+ * Bytes are fed to the parser as they arrive, in slices of any size, and it returns events as
+ * soon as they can be determined. It never blocks and never needs the whole file.
+ */
+
+use crate::chunks::*;
+use crate::crc::*;
+use crate::error::Error;
+use crate::limits::Limits;
+use crate::registry::ChunkRegistry;
+use crate::types::*;
+
+/// Events produced by [PngPushParser]
+#[derive(Clone, Debug)]
+pub enum PngEvent {
+    /// The file signature has been validated
+    Signature,
+
+    /// The IHDR chunk has been read and its CRC checked
+    Ihdr(PngChunkRef, Ihdr),
+
+    /// Some data from an IDAT chunk is available
+    ///
+    /// The CRC of the chunk is only checked once all of its data has arrived.
+    ImageData(Vec<u8>),
+
+    /// All of the data of an IDAT chunk has arrived and its CRC checked
+    ImageDataComplete(PngChunkRef),
+
+    /// Some data from an fdAT chunk is available, with the chunk's sequence number
+    ///
+    /// The sequence number isn't included in the data. The CRC of the chunk is only checked once
+    /// all of its data has arrived.
+    FrameData(u32, Vec<u8>),
+
+    /// All of the data of an fdAT chunk has arrived and its CRC checked
+    FrameDataComplete(PngChunkRef),
+
+    /// Any other chunk has been read and its CRC checked
+    Chunk(PngChunkRef, PngChunkData),
+
+    /// The IEND chunk has been reached
+    End(PngChunkRef),
+}
+
+/// Parser states
+#[derive(Clone, Debug)]
+enum State {
+    /// Waiting for the file signature
+    Signature,
+
+    /// Waiting for a chunk length and type
+    Header,
+
+    /// Waiting for the data and CRC of a (non-IDAT/fdAT) chunk
+    Contents(PngChunkRef),
+
+    /// Waiting for the sequence number of an fdAT chunk
+    SequenceNumber {
+        chunkref: PngChunkRef,
+        data_crc: CRC,
+    },
+
+    /// Passing through IDAT or fdAT data
+    ///
+    /// `sequence_number` is only set for fdAT chunks.
+    ImageData {
+        chunkref: PngChunkRef,
+        sequence_number: Option<u32>,
+        remaining: u32,
+        data_crc: CRC,
+    },
+
+    /// Waiting for the CRC of an IDAT or fdAT chunk
+    ImageDataCrc {
+        chunkref: PngChunkRef,
+        data_crc: CRC,
+    },
+
+    /// IEND has been read, or there was an error
+    Finished,
+}
+
+impl State {
+    /// Pass through the data of an IDAT or fdAT chunk, or go straight to its CRC if it has none
+    fn image_data(
+        chunkref: PngChunkRef,
+        sequence_number: Option<u32>,
+        remaining: u32,
+        data_crc: CRC,
+    ) -> Self {
+        if remaining > 0 {
+            State::ImageData {
+                chunkref,
+                sequence_number,
+                remaining,
+                data_crc,
+            }
+        } else {
+            State::ImageDataCrc { chunkref, data_crc }
+        }
+    }
+}
+
+/// A push-based incremental PNG parser
+#[derive(Clone, Debug)]
+pub struct PngPushParser {
+    /// The IHDR chunk data
+    pub ihdr: Option<Ihdr>,
+
+    /// User-defined chunk types to consult when parsing chunks
+    pub registry: ChunkRegistry,
+
+    /// Resource limits for parsing untrusted files
+    ///
+    /// IDAT and fdAT data is passed through rather than buffered, so only the other chunks are
+    /// held to [max_chunk_length](Limits::max_chunk_length).
+    pub limits: Limits,
+
+    state: State,
+    buf: Vec<u8>,
+    position: u64,
+    chunk_count: u64,
+}
+
+impl Default for PngPushParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PngPushParser {
+    /// Constructor
+    pub fn new() -> Self {
+        Self {
+            ihdr: None,
+            registry: ChunkRegistry::default(),
+            limits: Limits::default(),
+            state: State::Signature,
+            buf: Vec::with_capacity(8),
+            position: 0,
+            chunk_count: 0,
+        }
+    }
+
+    /// Number of bytes the parser needs before it can produce its next event
+    ///
+    /// Returns 0 once the IEND chunk has been read.
+    pub fn bytes_needed(&self) -> usize {
+        match &self.state {
+            State::Signature | State::Header => 8 - self.buf.len(),
+            State::Contents(chunkref) => chunkref.length as usize + 4 - self.buf.len(),
+            State::SequenceNumber { .. } => 4 - self.buf.len(),
+            State::ImageData { remaining, .. } => *remaining as usize,
+            State::ImageDataCrc { .. } => 4 - self.buf.len(),
+            State::Finished => 0,
+        }
+    }
+
+    /// Has the IEND chunk been read?
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, State::Finished)
+    }
+
+    /// Total number of bytes consumed so far
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Copy bytes from the input into the internal buffer until it holds `wanted` bytes
+    ///
+    /// Returns true once the buffer is full.
+    fn fill_buf(&mut self, data: &mut &[u8], wanted: usize) -> bool {
+        let take = (wanted - self.buf.len()).min(data.len());
+        self.buf.extend_from_slice(&data[..take]);
+        *data = &data[take..];
+        self.position += take as u64;
+
+        self.buf.len() == wanted
+    }
+
+    /// Feed some bytes into the parser, returning any events that they complete
+    ///
+    /// After an error the parser can't be used any further.
+    pub fn push(&mut self, data: &[u8]) -> Result<Vec<PngEvent>, std::io::Error> {
+        let mut events = Vec::new();
+        let mut data = data;
+        if let Err(e) = self.parse(&mut data, &mut events) {
+            self.state = State::Finished;
+            return Err(e);
+        }
+
+        Ok(events)
+    }
+
+    fn parse(&mut self, data: &mut &[u8], events: &mut Vec<PngEvent>) -> std::io::Result<()> {
+        while !data.is_empty() {
+            match &mut self.state {
+                State::Signature => {
+                    if !self.fill_buf(data, 8) {
+                        break;
+                    }
+                    if self.buf != [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a] {
+                        return Err(Error::BadSignature { offset: 0 }.into());
+                    }
+
+                    self.buf.clear();
+                    self.state = State::Header;
+                    events.push(PngEvent::Signature);
+                }
+
+                State::Header => {
+                    let position = self.position - self.buf.len() as u64;
+                    if !self.fill_buf(data, 8) {
+                        break;
+                    }
+                    let chunkref = PngChunkRef::from_stream_at(&mut self.buf.as_slice(), position)?;
+                    self.buf.clear();
+
+                    // Invalid chunk types for PNG/APNG files
+                    if matches!(&chunkref.chunktype, b"JHDR" | b"JDAT" | b"JDAA" | b"JSEP") {
+                        return Err(Error::DisallowedChunk {
+                            chunktype: chunkref.chunktype,
+                            offset: chunkref.position,
+                            filetype: PngFileType::Png,
+                        }
+                        .into());
+                    }
+
+                    if self.ihdr.is_none() && (chunkref.chunktype != *b"IHDR") {
+                        return Err(Error::OrderingViolation {
+                            chunktype: chunkref.chunktype,
+                            offset: chunkref.position,
+                            message: "PNG: The file doesn't start with an IHDR chunk".to_string(),
+                        }
+                        .into());
+                    }
+
+                    self.chunk_count += 1;
+                    self.limits
+                        .check_chunk_count(self.chunk_count)
+                        .map_err(|e| e.in_chunk(&chunkref))?;
+
+                    let mut data_crc = CRC::new();
+                    data_crc.consume(&chunkref.chunktype);
+                    match &chunkref.chunktype {
+                        b"IDAT" => {
+                            self.state =
+                                State::image_data(chunkref, None, chunkref.length, data_crc);
+                        }
+
+                        b"fdAT" if chunkref.length < 4 => {
+                            return Err(Error::InvalidFieldValue {
+                                chunktype: chunkref.chunktype,
+                                offset: chunkref.position,
+                                message: "fdAT chunk is too short for a sequence number"
+                                    .to_string(),
+                            }
+                            .into());
+                        }
+
+                        b"fdAT" => {
+                            self.state = State::SequenceNumber { chunkref, data_crc };
+                        }
+
+                        _ => {
+                            // Anything else is buffered in full before it's parsed
+                            self.limits
+                                .check_chunk_length(chunkref.length)
+                                .map_err(|e| e.in_chunk(&chunkref))?;
+                            self.state = State::Contents(chunkref);
+                        }
+                    }
+                }
+
+                State::Contents(chunkref) => {
+                    let chunkref = *chunkref;
+                    if !self.fill_buf(data, chunkref.length as usize + 4) {
+                        break;
+                    }
+                    let chunk = chunkref.read_contents(
+                        &mut self.buf.as_slice(),
+                        self.ihdr.as_ref(),
+                        &self.registry,
+                    )?;
+                    self.buf.clear();
+
+                    match chunk {
+                        PngChunkData::Ihdr(ihdr) => {
+                            self.limits
+                                .check_image_size(ihdr.width, ihdr.height, ihdr.pixel_bits())
+                                .map_err(|e| e.in_chunk(&chunkref))?;
+                            self.ihdr = Some(ihdr);
+                            self.state = State::Header;
+                            events.push(PngEvent::Ihdr(chunkref, ihdr));
+                        }
+
+                        PngChunkData::Iend => {
+                            self.state = State::Finished;
+                            events.push(PngEvent::End(chunkref));
+                        }
+
+                        _ => {
+                            self.state = State::Header;
+                            events.push(PngEvent::Chunk(chunkref, chunk));
+                        }
+                    }
+                }
+
+                State::SequenceNumber { chunkref, data_crc } => {
+                    let chunkref = *chunkref;
+                    let mut data_crc = *data_crc;
+                    if !self.fill_buf(data, 4) {
+                        break;
+                    }
+                    data_crc.consume(&self.buf);
+                    let sequence_number =
+                        u32::from_be_bytes([self.buf[0], self.buf[1], self.buf[2], self.buf[3]]);
+                    self.buf.clear();
+
+                    self.state = State::image_data(
+                        chunkref,
+                        Some(sequence_number),
+                        chunkref.length - 4,
+                        data_crc,
+                    );
+                }
+
+                State::ImageData {
+                    chunkref,
+                    sequence_number,
+                    remaining,
+                    data_crc,
+                } => {
+                    let take = (*remaining as usize).min(data.len());
+                    if take > 0 {
+                        let bytes = &data[..take];
+                        data_crc.consume(bytes);
+                        events.push(match sequence_number {
+                            Some(sequence_number) => {
+                                PngEvent::FrameData(*sequence_number, bytes.to_vec())
+                            }
+                            None => PngEvent::ImageData(bytes.to_vec()),
+                        });
+                        *remaining -= take as u32;
+                        *data = &data[take..];
+                        self.position += take as u64;
+                    }
+
+                    if *remaining == 0 {
+                        self.state = State::ImageDataCrc {
+                            chunkref: *chunkref,
+                            data_crc: std::mem::take(data_crc),
+                        };
+                    }
+                }
+
+                State::ImageDataCrc { chunkref, data_crc } => {
+                    let chunkref = *chunkref;
+                    let computed = data_crc.value();
+                    if !self.fill_buf(data, 4) {
+                        break;
+                    }
+                    let crc =
+                        u32::from_be_bytes([self.buf[0], self.buf[1], self.buf[2], self.buf[3]]);
+                    self.buf.clear();
+                    if crc != computed {
+                        return Err(Error::CrcMismatch {
+                            chunktype: chunkref.chunktype,
+                            offset: chunkref.position,
+                            expected: crc,
+                            actual: computed,
+                        }
+                        .into());
+                    }
+
+                    self.state = State::Header;
+                    events.push(if chunkref.chunktype == *b"fdAT" {
+                        PngEvent::FrameDataComplete(chunkref)
+                    } else {
+                        PngEvent::ImageDataComplete(chunkref)
+                    });
+                }
+
+                // Ignore anything after the IEND chunk
+                State::Finished => break,
+            }
+        }
+
+        Ok(())
+    }
+}
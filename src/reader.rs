@@ -20,9 +20,21 @@
  */
 
 use std::collections::HashMap;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Read, Seek, SeekFrom, Write};
 
+use crate::bodyreader::{ChunkBodyReader, ImageDataReader};
 use crate::chunks::*;
+#[cfg(feature = "decode")]
+use crate::decode::ScanlineDecoder;
+use crate::error::Error;
+#[cfg(feature = "decode")]
+use crate::image::*;
+use crate::limits::*;
+use crate::ordering::check_chunk_order;
+use crate::recovery::*;
+use crate::registry::ChunkRegistry;
+use crate::rewrite::*;
+use crate::strip::*;
 use crate::types::*;
 
 /// A PNG/APNG file reader
@@ -51,10 +63,20 @@ pub struct PngReader<R> {
     /// The IHDR chunk data
     pub ihdr: Option<Ihdr>,
 
+    /// User-defined chunk types to consult when reading chunks
+    pub registry: ChunkRegistry,
+
+    /// Resource limits for reading untrusted files
+    pub limits: Limits,
+
     next_chunk_pos: u64,
 
     in_header: bool,
     first_frame_is_static: bool,
+
+    chunk_count: u64,
+    scanned_to: u64,
+    text_size: u64,
 }
 
 impl<R> PngReader<R>
@@ -70,7 +92,7 @@ where
             let mut signature = [0; 8];
             stream.read_exact(&mut signature)?;
             if signature != [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a] {
-                return Err(std::io::Error::other("PNG: Bad signature"));
+                return Err(Error::BadSignature { offset: 0 }.into());
             }
         }
 
@@ -82,9 +104,14 @@ where
             colour_type: PngColourType::Greyscale,
             stream,
             ihdr: None,
+            registry: ChunkRegistry::default(),
+            limits: Limits::default(),
             next_chunk_pos: 8,
             in_header: true,
             first_frame_is_static: false,
+            chunk_count: 0,
+            scanned_to: 0,
+            text_size: 0,
         })
     }
 
@@ -149,10 +176,21 @@ where
             | (chunkref.chunktype == *b"JDAA")
             | (chunkref.chunktype == *b"JSEP")
         {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("PNG: Invalid chunk type \"{:?}\"", chunkref.chunktype),
-            ));
+            return Err(Error::DisallowedChunk {
+                chunktype: chunkref.chunktype,
+                offset: chunkref.position,
+                filetype: PngFileType::Png,
+            }
+            .into());
+        }
+
+        // Only count each chunk once, however many times the file is scanned
+        if chunkref.position >= self.scanned_to {
+            self.chunk_count += 1;
+            self.limits
+                .check_chunk_count(self.chunk_count)
+                .map_err(|e| e.in_chunk(&chunkref))?;
+            self.scanned_to = chunkref.position + 4 + 4 + chunkref.length as u64 + 4;
         }
 
         self.next_chunk_pos += 4 + 4 + chunkref.length as u64 + 4;
@@ -162,11 +200,7 @@ where
                 let oldpos = self.stream.stream_position()?;
                 // Fill in image metadata
                 if let PngChunkData::Ihdr(ihdr) = chunkref.read_chunk(&mut self.stream, None)? {
-                    self.ihdr = Some(ihdr);
-                    self.width = ihdr.width;
-                    self.height = ihdr.height;
-                    self.bit_depth = ihdr.bit_depth;
-                    self.colour_type = ihdr.colour_type;
+                    self.set_ihdr(&chunkref, ihdr)?;
                 }
 
                 self.stream.seek(SeekFrom::Start(oldpos))?;
@@ -193,6 +227,265 @@ where
         Ok(chunkref)
     }
 
+    /// Check the order and number of all of the chunks in the file
+    ///
+    /// Returns a list of [OrderingViolation](Error::OrderingViolation) errors, which is empty for a
+    /// valid file.
+    pub fn check_chunk_order(&mut self) -> Result<Vec<Error>, std::io::Error> {
+        self.reset_next_chunk_position();
+        let chunks = self.scan_all_chunks()?;
+
+        Ok(check_chunk_order(&chunks))
+    }
+
+    /// Write a copy of the file with chunks inserted, replaced, or deleted
+    ///
+    /// Chunks that none of the operations apply to are copied from the stream byte-for-byte, CRC
+    /// included, without being parsed. Only new chunks are serialised. If critical chunks are
+    /// changed, unrecognised ancillary chunks that aren't safe to copy are deleted. Returns
+    /// references to the chunks written.
+    pub fn rewrite<W>(
+        &mut self,
+        ops: &[RewriteOp],
+        out: W,
+    ) -> Result<Vec<PngChunkRef>, std::io::Error>
+    where
+        W: Write,
+    {
+        self.reset_next_chunk_position();
+        let chunks = self.scan_all_chunks()?;
+
+        Ok(rewrite_chunks(&mut self.stream, &self.limits, &chunks, ops, out)?.written)
+    }
+
+    /// Write a copy of the file with ancillary chunks removed by a policy
+    ///
+    /// The chunks that are kept are copied byte-for-byte.
+    pub fn strip<W>(&mut self, policy: StripPolicy, out: W) -> Result<StripReport, std::io::Error>
+    where
+        W: Write,
+    {
+        self.reset_next_chunk_position();
+        let chunks = self.scan_all_chunks()?;
+        let ops = [RewriteOp::Delete(ChunkSelector::Predicate(Box::new(
+            move |chunkref| policy.strips(chunkref),
+        )))];
+
+        let rewritten = rewrite_chunks(&mut self.stream, &self.limits, &chunks, &ops, out)?;
+        Ok(StripReport::new(rewritten.removed))
+    }
+
+    /// Error for a file without an IHDR chunk at the start
+    #[cfg(feature = "decode")]
+    fn no_ihdr(&self) -> std::io::Error {
+        Error::OrderingViolation {
+            chunktype: Ihdr::TYPE,
+            offset: 8,
+            message: "PNG: The file doesn't start with an IHDR chunk".to_string(),
+        }
+        .into()
+    }
+
+    /// Fill in image metadata from the IHDR chunk
+    fn set_ihdr(&mut self, chunkref: &PngChunkRef, ihdr: Ihdr) -> Result<(), std::io::Error> {
+        self.limits
+            .check_image_size(ihdr.width, ihdr.height, ihdr.pixel_bits())
+            .map_err(|e| e.in_chunk(chunkref))?;
+        self.ihdr = Some(ihdr);
+        self.width = ihdr.width;
+        self.height = ihdr.height;
+        self.bit_depth = ihdr.bit_depth;
+        self.colour_type = ihdr.colour_type;
+
+        Ok(())
+    }
+
+    /// Scan all of the chunks in a damaged file, recovering as many as possible
+    ///
+    /// Unlike scan_all_chunks(), the CRC of every chunk is checked, and problems are recorded as
+    /// diagnostics instead of ending the scan:
+    /// - Chunks with a bad CRC are kept.
+    /// - Garbage is skipped by searching for the next plausible chunk header, i.e. one with a
+    ///   valid chunk type, that fits in the file, and whose CRC matches.
+    /// - The end of the file is treated as the end of the chunks if there is no IEND chunk.
+    ///
+    /// I/O errors and exceeded [limits](Self::limits) still end the scan.
+    pub fn recover_chunks(&mut self) -> Result<RecoveredChunks, std::io::Error> {
+        let end = self.stream.seek(SeekFrom::End(0))?;
+        self.reset_next_chunk_position();
+
+        let mut recovered = RecoveredChunks::default();
+        loop {
+            let position = self.next_chunk_pos;
+            let Some(chunkref) = self.plausible_chunk_at(position, end)? else {
+                if let Some(next) = self.find_chunk_header(position + 1, end)? {
+                    recovered.diagnostics.push(Diagnostic::SkippedBytes {
+                        offset: position,
+                        length: next - position,
+                    });
+                    self.next_chunk_pos = next;
+                    continue;
+                }
+
+                self.recover_tail(position, end, &mut recovered.diagnostics)?;
+                break;
+            };
+
+            if let Err(e) = self.check_chunk_crc(&chunkref) {
+                match Error::from_io_error(&e) {
+                    Some(&Error::CrcMismatch {
+                        chunktype,
+                        offset,
+                        expected,
+                        actual,
+                    }) => recovered.diagnostics.push(Diagnostic::CrcMismatch {
+                        chunktype,
+                        offset,
+                        expected,
+                        actual,
+                    }),
+
+                    _ => return Err(e),
+                }
+            }
+
+            self.next_chunk_pos = position;
+            if let Err(e) = self.scan_next_chunk() {
+                self.set_next_chunk_position_after(&chunkref);
+                let e = match Error::from_io_error(&e) {
+                    // Use the damaged IHDR chunk anyway, it's needed for reading other chunks
+                    Some(Error::CrcMismatch { .. }) if chunkref.chunktype == Ihdr::TYPE => {
+                        self.stream.seek(SeekFrom::Start(position + 4 + 4))?;
+                        match Ihdr::from_contents_stream(&mut self.stream, None) {
+                            Ok(ihdr) => {
+                                self.set_ihdr(&chunkref, ihdr)?;
+                                recovered.chunks.push(chunkref);
+                                continue;
+                            }
+                            Err(e) => Error::in_chunk(e, &chunkref),
+                        }
+                    }
+
+                    _ => e,
+                };
+
+                match Error::from_io_error(&e) {
+                    Some(Error::LimitExceeded { .. }) | None => return Err(e),
+                    Some(error) => {
+                        recovered
+                            .diagnostics
+                            .push(Diagnostic::InvalidChunk(error.clone()));
+                        continue;
+                    }
+                }
+            }
+
+            recovered.chunks.push(chunkref);
+            if chunkref.chunktype == *b"IEND" {
+                break;
+            }
+        }
+
+        Ok(recovered)
+    }
+
+    /// Read the chunk header at a position if it's plausible
+    fn plausible_chunk_at(
+        &mut self,
+        position: u64,
+        end: u64,
+    ) -> Result<Option<PngChunkRef>, std::io::Error> {
+        if position + 4 + 4 + 4 > end {
+            return Ok(None);
+        }
+
+        let mut header = [0_u8; 8];
+        self.stream.seek(SeekFrom::Start(position))?;
+        self.stream.read_exact(&mut header)?;
+        if !is_plausible_chunk_header(&header, position, end) {
+            return Ok(None);
+        }
+
+        Ok(Some(PngChunkRef::from_stream_at(
+            &mut header.as_slice(),
+            position,
+        )?))
+    }
+
+    /// Read the whole chunk to check its CRC
+    fn check_chunk_crc(&mut self, chunkref: &PngChunkRef) -> Result<(), std::io::Error> {
+        std::io::copy(&mut self.chunk_body_reader(chunkref)?, &mut std::io::sink())?;
+
+        Ok(())
+    }
+
+    /// Search for the next plausible chunk header with a matching CRC
+    fn find_chunk_header(&mut self, start: u64, end: u64) -> Result<Option<u64>, std::io::Error> {
+        const WINDOW: usize = 64 * 1024;
+
+        let mut buf = vec![0_u8; WINDOW + 8];
+        let mut window_start = start;
+        while window_start + 4 + 4 + 4 <= end {
+            // Windows overlap so that headers crossing the end of one are found in the next
+            let size = (WINDOW + 8).min((end - window_start) as usize);
+            self.stream.seek(SeekFrom::Start(window_start))?;
+            self.stream.read_exact(&mut buf[..size])?;
+
+            let mut header = [0_u8; 8];
+            for (i, window) in buf[..size].windows(8).enumerate() {
+                let position = window_start + i as u64;
+                header.copy_from_slice(window);
+                if !is_plausible_chunk_header(&header, position, end) {
+                    continue;
+                }
+
+                let chunkref = PngChunkRef::from_stream_at(&mut header.as_slice(), position)?;
+                match self.check_chunk_crc(&chunkref) {
+                    Ok(()) => return Ok(Some(position)),
+                    Err(e) if Error::from_io_error(&e).is_some() => (),
+                    Err(e) => return Err(e),
+                }
+            }
+
+            window_start += WINDOW as u64;
+        }
+
+        Ok(None)
+    }
+
+    /// Record what's left at the end of a file with no more chunks
+    fn recover_tail(
+        &mut self,
+        position: u64,
+        end: u64,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<(), std::io::Error> {
+        if position < end {
+            let mut header = [0_u8; 8];
+            let available = ((end - position) as usize).min(8);
+            self.stream.seek(SeekFrom::Start(position))?;
+            self.stream.read_exact(&mut header[..available])?;
+
+            let chunktype = [header[4], header[5], header[6], header[7]];
+            if (available == 8) && is_plausible_chunk_type(&chunktype) {
+                diagnostics.push(Diagnostic::TruncatedChunk {
+                    chunktype,
+                    offset: position,
+                    length: u32::from_be_bytes([header[0], header[1], header[2], header[3]]),
+                });
+            } else {
+                diagnostics.push(Diagnostic::SkippedBytes {
+                    offset: position,
+                    length: end - position,
+                });
+            }
+        }
+
+        diagnostics.push(Diagnostic::MissingIend { offset: end });
+
+        Ok(())
+    }
+
     /// Reset the position of the next chunk to scan back to the start of the file
     pub fn reset_next_chunk_position(&mut self) {
         self.next_chunk_pos = 8;
@@ -210,8 +503,84 @@ where
     }
 
     /// Read the chunk data after seeking to the start of its data
+    ///
+    /// Chunks longer than [max_chunk_length](Limits::max_chunk_length) are rejected before
+    /// anything is allocated.
     pub fn read_chunk(&mut self, chunkref: &PngChunkRef) -> Result<PngChunkData, std::io::Error> {
-        chunkref.read_chunk(&mut self.stream, self.ihdr.as_ref())
+        self.limits
+            .check_chunk_length(chunkref.length)
+            .map_err(|e| e.in_chunk(chunkref))?;
+        chunkref.read_chunk_with_registry(&mut self.stream, self.ihdr.as_ref(), &self.registry)
+    }
+
+    /// Stream the raw data of a chunk after seeking to the start of its data
+    ///
+    /// Unlike read_chunk(), this doesn't read the whole chunk into memory. The CRC is checked
+    /// once all of the data has been read.
+    pub fn chunk_body_reader(
+        &mut self,
+        chunkref: &PngChunkRef,
+    ) -> Result<ChunkBodyReader<&mut R>, std::io::Error> {
+        self.stream
+            .seek(SeekFrom::Start(chunkref.position + 4 + 4))?;
+        Ok(ChunkBodyReader::new(&mut self.stream, *chunkref))
+    }
+
+    /// Get the string of a tEXt, zTXt, or iTXt chunk
+    ///
+    /// Decompressed text counts towards the [max_text_size](Limits::max_text_size) limit, which
+    /// is shared by all of the chunks in the file. Returns None for other chunk types.
+    pub fn text_string(&mut self, chunk: &PngChunkData) -> Result<Option<String>, std::io::Error> {
+        text_string(&self.limits, &mut self.text_size, chunk)
+    }
+
+    /// Get the decompressed profile of an iCCP chunk
+    ///
+    /// Returns None for other chunk types.
+    pub fn iccp_profile(&self, chunk: &PngChunkData) -> Result<Option<Vec<u8>>, std::io::Error> {
+        iccp_profile(&self.limits, chunk)
+    }
+
+    /// Read the image data of all IDAT chunks as one stream
+    ///
+    /// This scans the whole file for IDAT chunks. The CRC of each one is checked as it's read.
+    pub fn image_data_reader(&mut self) -> Result<ImageDataReader<&mut R>, std::io::Error> {
+        self.reset_next_chunk_position();
+        let idats = self.scan_chunks_filtered(|ct| ct == Idat::TYPE)?;
+
+        Ok(ImageDataReader::new(&mut self.stream, idats))
+    }
+
+    /// Decompress and unfilter the image data, one row at a time
+    #[cfg(feature = "decode")]
+    pub fn scanline_decoder(
+        &mut self,
+    ) -> Result<ScanlineDecoder<ImageDataReader<&mut R>>, std::io::Error> {
+        let ihdr = self.ihdr.ok_or_else(|| self.no_ihdr())?;
+
+        ScanlineDecoder::new(self.image_data_reader()?, &ihdr)
+    }
+
+    /// Decode the image into a pixel format
+    ///
+    /// This scans the whole file for the PLTE, tRNS, and sBIT chunks, and then the image data.
+    #[cfg(feature = "decode")]
+    pub fn decode_image(
+        &mut self,
+        options: &DecodeOptions,
+    ) -> Result<DecodedImage, std::io::Error> {
+        self.reset_next_chunk_position();
+        let chunkrefs = self
+            .scan_chunks_filtered(|ct| ct == Plte::TYPE || ct == Trns::TYPE || ct == Sbit::TYPE)?;
+        let ihdr = self.ihdr.ok_or_else(|| self.no_ihdr())?;
+
+        let mut converter = PixelConverter::new(&ihdr);
+        for chunkref in chunkrefs {
+            converter.add_chunk(&self.read_chunk(&chunkref)?);
+        }
+
+        let data = self.scanline_decoder()?.read_all()?;
+        converter.convert(&data, ihdr.width, ihdr.height, options)
     }
 
     pub fn apng_scan_frames(&mut self) -> std::io::Result<Vec<ApngFrame>> {
@@ -234,19 +603,23 @@ where
                 });
         }
 
-        // Sort chunks by their sequence number
-        chunkrefs.sort_by_cached_key(|cr| {
-            if cr.chunktype == *b"IDAT" {
-                idat_seq_nums[&cr.position]
-            } else {
-                let seq_num = cr.read_fctl_fdat_sequence_number(&mut self.stream).unwrap();
+        // Read the sequence numbers of the fcTL and fdAT chunks
+        let mut seq_nums = idat_seq_nums;
+        let num_idats = seq_nums.len() as u32;
+        for cr in chunkrefs.iter().filter(|cr| cr.chunktype != *b"IDAT") {
+            let seq_num = cr.read_fctl_fdat_sequence_number(&mut self.stream)?;
+            seq_nums.insert(
+                cr.position,
                 if self.first_frame_is_static && (seq_num > 0) {
-                    seq_num + idat_seq_nums.len() as u32
+                    seq_num.saturating_add(num_idats)
                 } else {
                     seq_num
-                }
-            }
-        });
+                },
+            );
+        }
+
+        // Sort chunks by their sequence number
+        chunkrefs.sort_by_key(|cr| seq_nums[&cr.position]);
 
         // Group fcTL and fdAT chunks into frames
         let mut frames = Vec::new();
@@ -261,9 +634,12 @@ where
                 }
             } else {
                 if frames.is_empty() {
-                    return Err(std::io::Error::other(
-                        "At least one fcTL chunk must go before fdAT chunks".to_string(),
-                    ));
+                    return Err(Error::OrderingViolation {
+                        chunktype: chunkref.chunktype,
+                        offset: chunkref.position,
+                        message: "At least one fcTL chunk must go before fdAT chunks".to_string(),
+                    }
+                    .into());
                 }
                 let lasti = frames.len() - 1;
                 frames[lasti].dats.push(chunkref);
@@ -281,3 +657,15 @@ pub struct ApngFrame {
 
     pub dats: Vec<PngChunkRef>,
 }
+
+impl ApngFrame {
+    /// Read the image data of this frame's IDAT or fdAT chunks as one stream
+    ///
+    /// The fdAT sequence numbers are skipped and the CRC of each chunk is checked as it's read.
+    pub fn data_reader<R>(&self, stream: R) -> ImageDataReader<R>
+    where
+        R: Read + Seek,
+    {
+        ImageDataReader::new(stream, self.dats.clone())
+    }
+}
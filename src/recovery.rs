@@ -0,0 +1,157 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Recovering chunks from damaged files
+ *
+ * See [PngReader::recover_chunks()](crate::reader::PngReader::recover_chunks).
+ */
+
+use crate::chunks::PngChunkRef;
+use crate::error::Error;
+
+/// Maximum chunk length allowed by the PNG specification
+pub(crate) const MAX_CHUNK_LENGTH: u32 = 0x7fff_ffff;
+
+/// Something that was wrong with a damaged file, and what was done about it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// The CRC of a chunk doesn't match its contents
+    ///
+    /// The chunk is kept.
+    CrcMismatch {
+        chunktype: [u8; 4],
+
+        /// Offset of the start of the chunk
+        offset: u64,
+
+        /// The CRC stored in the file
+        expected: u32,
+
+        /// The CRC computed from the chunk type and data
+        actual: u32,
+    },
+
+    /// Bytes that don't start with a plausible chunk header were skipped
+    SkippedBytes {
+        /// Offset of the first skipped byte
+        offset: u64,
+
+        /// Number of bytes skipped
+        length: u64,
+    },
+
+    /// A chunk runs past the end of the file
+    ///
+    /// The chunk is dropped.
+    TruncatedChunk {
+        chunktype: [u8; 4],
+
+        /// Offset of the start of the chunk
+        offset: u64,
+
+        /// Length of the chunk data from its header
+        length: u32,
+    },
+
+    /// A chunk couldn't be used, e.g. it isn't allowed in this type of file
+    ///
+    /// The chunk is dropped.
+    InvalidChunk(Error),
+
+    /// The file ended without an IEND chunk
+    ///
+    /// The end of the file is treated as the end of the chunks.
+    MissingIend {
+        /// Offset of the end of the file
+        offset: u64,
+    },
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CrcMismatch {
+                chunktype,
+                offset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "CRC ({:#x}) of {} chunk at {} doesn't match the computed one ({:#x})",
+                expected,
+                String::from_utf8_lossy(chunktype),
+                offset,
+                actual
+            ),
+
+            Self::SkippedBytes { offset, length } => {
+                write!(f, "Skipped {} bytes of garbage at {}", length, offset)
+            }
+
+            Self::TruncatedChunk {
+                chunktype,
+                offset,
+                length,
+            } => write!(
+                f,
+                "{} chunk at {} with {} bytes of data runs past the end of the file",
+                String::from_utf8_lossy(chunktype),
+                offset,
+                length
+            ),
+
+            Self::InvalidChunk(e) => write!(f, "{}", e),
+
+            Self::MissingIend { offset } => write!(f, "No IEND chunk before the end at {}", offset),
+        }
+    }
+}
+
+/// The chunks that could be recovered from a file, and what was wrong with it
+#[derive(Clone, Debug, Default)]
+pub struct RecoveredChunks {
+    /// Chunks in file order
+    pub chunks: Vec<PngChunkRef>,
+
+    /// Problems found, in file order
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl RecoveredChunks {
+    /// Was the file undamaged?
+    pub fn is_clean(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+/// Is this a plausible chunk type, i.e. four ASCII letters?
+pub(crate) fn is_plausible_chunk_type(chunktype: &[u8; 4]) -> bool {
+    chunktype.iter().all(|b| b.is_ascii_alphabetic())
+}
+
+/// Could this be the header of a chunk that fits in the rest of the file?
+///
+/// `end`: Length of the file.
+pub(crate) fn is_plausible_chunk_header(header: &[u8; 8], position: u64, end: u64) -> bool {
+    let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    let chunktype = [header[4], header[5], header[6], header[7]];
+
+    (length <= MAX_CHUNK_LENGTH)
+        && is_plausible_chunk_type(&chunktype)
+        && (position + 4 + 4 + length as u64 + 4 <= end)
+}
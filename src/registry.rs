@@ -0,0 +1,210 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Registry of user-defined chunk types
+ *
+ * Implement [ChunkCodec] for a chunk struct and [ChunkRegistry::register()] it with a reader.
+ * Chunks of that type are then returned as [PngChunkData::Custom].
+ */
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::{Read, Write};
+
+use crate::chunks::{PngChunkData, PngChunkRef};
+use crate::crc::*;
+use crate::error::Error;
+
+/// A chunk type that can be read from and written to a stream
+pub trait ChunkCodec: Sized {
+    /// Chunk type
+    const TYPE: [u8; 4];
+
+    /// Read contents from a stream
+    fn from_contents_stream<R>(
+        stream: &mut R,
+        length: u32,
+        data_crc: Option<&mut CRC>,
+    ) -> std::io::Result<Self>
+    where
+        R: Read;
+
+    /// Length of the chunk contents
+    fn length(&self) -> u32;
+
+    /// Write contents to a stream
+    fn write_contents<W>(&self, stream: &mut W, data_crc: Option<&mut CRC>) -> std::io::Result<()>
+    where
+        W: Write;
+}
+
+/// Object-safe form of [ChunkCodec], used to hold registered chunks in [PngChunkData::Custom]
+///
+/// This is implemented for every [ChunkCodec] type that is also [Clone] and [Debug].
+pub trait CustomChunk: Any + Debug + Send + Sync {
+    /// Chunk type
+    fn chunk_type(&self) -> [u8; 4];
+
+    /// Length of the chunk contents
+    fn contents_length(&self) -> u32;
+
+    /// Write contents to a stream
+    fn write_dyn(&self, stream: &mut dyn Write, data_crc: Option<&mut CRC>) -> std::io::Result<()>;
+
+    /// Clone into a new box
+    fn clone_box(&self) -> Box<dyn CustomChunk>;
+
+    /// For downcasting back to the concrete type
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T> CustomChunk for T
+where
+    T: ChunkCodec + Clone + Debug + Send + Sync + 'static,
+{
+    fn chunk_type(&self) -> [u8; 4] {
+        T::TYPE
+    }
+
+    fn contents_length(&self) -> u32 {
+        ChunkCodec::length(self)
+    }
+
+    fn write_dyn(
+        &self,
+        mut stream: &mut dyn Write,
+        data_crc: Option<&mut CRC>,
+    ) -> std::io::Result<()> {
+        ChunkCodec::write_contents(self, &mut stream, data_crc)
+    }
+
+    fn clone_box(&self) -> Box<dyn CustomChunk> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn CustomChunk> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl PngChunkData {
+    /// Wrap a user-defined chunk
+    pub fn new_custom<T>(chunk: T) -> Self
+    where
+        T: ChunkCodec + Clone + Debug + Send + Sync + 'static,
+    {
+        Self::Custom(Box::new(chunk))
+    }
+
+    /// Get a reference to a user-defined chunk of a particular type
+    pub fn custom<T>(&self) -> Option<&T>
+    where
+        T: CustomChunk,
+    {
+        if let Self::Custom(custom) = self {
+            return custom.as_any().downcast_ref::<T>();
+        }
+
+        None
+    }
+}
+
+type ParseFn = fn(&mut dyn Read, u32, &mut CRC) -> std::io::Result<Box<dyn CustomChunk>>;
+
+fn parse_registered<T>(
+    mut stream: &mut dyn Read,
+    length: u32,
+    data_crc: &mut CRC,
+) -> std::io::Result<Box<dyn CustomChunk>>
+where
+    T: ChunkCodec + Clone + Debug + Send + Sync + 'static,
+{
+    Ok(Box::new(T::from_contents_stream(
+        &mut stream,
+        length,
+        Some(data_crc),
+    )?))
+}
+
+/// A set of user-defined chunk types for readers to consult
+///
+/// Registered types take precedence over the built-in ones.
+#[derive(Clone, Debug, Default)]
+pub struct ChunkRegistry {
+    parsers: HashMap<[u8; 4], ParseFn>,
+}
+
+impl ChunkRegistry {
+    /// Constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a chunk type
+    pub fn register<T>(&mut self)
+    where
+        T: ChunkCodec + Clone + Debug + Send + Sync + 'static,
+    {
+        self.parsers.insert(T::TYPE, parse_registered::<T>);
+    }
+
+    /// Remove a chunk type from the registry
+    pub fn unregister(&mut self, chunktype: [u8; 4]) {
+        self.parsers.remove(&chunktype);
+    }
+
+    /// Has a chunk type been registered?
+    pub fn is_registered(&self, chunktype: [u8; 4]) -> bool {
+        self.parsers.contains_key(&chunktype)
+    }
+
+    /// Parse the contents of a chunk of a registered type
+    pub(crate) fn parse<R>(
+        &self,
+        chunkref: &PngChunkRef,
+        stream: &mut R,
+        data_crc: &mut CRC,
+    ) -> std::io::Result<PngChunkData>
+    where
+        R: Read,
+    {
+        let parse = self.parsers.get(&chunkref.chunktype).ok_or_else(|| {
+            std::io::Error::from(Error::InvalidFieldValue {
+                chunktype: chunkref.chunktype,
+                offset: chunkref.position,
+                message: format!(
+                    "PNG: Chunk type {} has not been registered",
+                    chunkref.type_str()
+                ),
+            })
+        })?;
+
+        Ok(PngChunkData::Custom(parse(
+            stream,
+            chunkref.length,
+            data_crc,
+        )?))
+    }
+}
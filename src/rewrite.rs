@@ -0,0 +1,325 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Rewriting the chunks of a file without re-encoding it
+ *
+ * See [PngReader::rewrite()](crate::reader::PngReader::rewrite) and
+ * [JngReader::rewrite()](crate::jngreader::JngReader::rewrite). Chunks that aren't touched by
+ * any [RewriteOp] are copied byte-for-byte, CRC included, without being parsed.
+ *
+ * If any critical chunks are inserted, replaced, or deleted, ancillary chunks that aren't
+ * [safe to copy](PngChunkRef::is_safe_to_copy) are also deleted, unless they are chunk types with
+ * an [ordering rule](crate::ordering::RULES), as the PNG specification requires.
+ */
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::chunks::{PngChunkData, PngChunkRef};
+use crate::countingwriter::CountingWriter;
+use crate::limits::Limits;
+use crate::ordering::{Placement, find_rule};
+
+/// Which chunks an operation applies to
+pub enum ChunkSelector {
+    /// Chunks of a type
+    Type([u8; 4]),
+
+    /// tEXt, zTXt, and iTXt chunks with a keyword
+    ///
+    /// Only text chunks are parsed to find their keyword.
+    Keyword(String),
+
+    /// Chunks that pass a test
+    Predicate(Box<dyn Fn(&PngChunkRef) -> bool>),
+}
+
+impl std::fmt::Debug for ChunkSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Type(chunktype) => f
+                .debug_tuple("Type")
+                .field(&String::from_utf8_lossy(chunktype))
+                .finish(),
+            Self::Keyword(keyword) => f.debug_tuple("Keyword").field(keyword).finish(),
+            Self::Predicate(_) => f.debug_tuple("Predicate").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// An edit to make while rewriting a file
+#[derive(Debug)]
+pub enum RewriteOp {
+    /// Insert a chunk at the last position its [ordering rule](crate::ordering::RULES) allows
+    ///
+    /// Chunk types without a rule go before IEND, and PLTE goes before the chunks that must follow
+    /// it.
+    Insert(PngChunkData),
+
+    /// Replace the first selected chunk, and delete any others
+    ///
+    /// The chunk is inserted like [Insert](Self::Insert) if no chunks are selected.
+    Replace(ChunkSelector, PngChunkData),
+
+    /// Delete the selected chunks
+    Delete(ChunkSelector),
+}
+
+/// What happens to a chunk of the source file
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Fate {
+    Copy,
+    Delete,
+
+    /// Replaced by the chunk of an operation
+    Replace(usize),
+}
+
+/// The chunks written by a rewrite, and the chunks of the source that were deleted
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Rewritten {
+    pub written: Vec<PngChunkRef>,
+    pub removed: Vec<PngChunkRef>,
+}
+
+/// Can a chunk be copied once critical chunks have changed?
+fn is_copyable_after_critical_change(chunkref: &PngChunkRef) -> bool {
+    // iDOT holds the positions of IDAT chunks, so it's only valid for the original image data
+    !chunkref.is_ancillary()
+        || chunkref.is_safe_to_copy()
+        || (find_rule(chunkref.chunktype).is_some() && (chunkref.chunktype != *b"iDOT"))
+}
+
+/// Chunk types that hold image data, which header chunks must be before
+fn is_image_data(chunktype: [u8; 4]) -> bool {
+    matches!(&chunktype, b"IDAT" | b"JDAT" | b"JDAA" | b"JSEP")
+}
+
+/// Does a selector select a chunk?
+fn selects<R>(
+    selector: &ChunkSelector,
+    stream: &mut R,
+    limits: &Limits,
+    chunkref: &PngChunkRef,
+) -> Result<bool, std::io::Error>
+where
+    R: Read + Seek,
+{
+    match selector {
+        ChunkSelector::Type(chunktype) => Ok(chunkref.chunktype == *chunktype),
+
+        ChunkSelector::Keyword(keyword) => {
+            if !matches!(&chunkref.chunktype, b"tEXt" | b"zTXt" | b"iTXt") {
+                return Ok(false);
+            }
+
+            limits
+                .check_chunk_length(chunkref.length)
+                .map_err(|e| e.in_chunk(chunkref))?;
+            let chunk = chunkref.read_chunk(stream, None)?;
+            Ok(chunk.text_keyword() == Some(keyword.as_str()))
+        }
+
+        ChunkSelector::Predicate(test) => Ok(test(chunkref)),
+    }
+}
+
+/// Index of the chunk that an inserted chunk must go before
+fn insert_position(chunks: &[PngChunkRef], chunk: &PngChunkData) -> Result<usize, std::io::Error> {
+    let chunktype = chunk.chunktype();
+    let position = |test: &dyn Fn([u8; 4]) -> bool| {
+        chunks
+            .iter()
+            .position(|c| test(c.chunktype))
+            .unwrap_or(chunks.len())
+    };
+    let end = position(&|ct| ct == *b"IEND");
+
+    // Before the chunks that depend on it
+    if chunktype == *b"PLTE" {
+        return Ok(position(&|ct| {
+            is_image_data(ct)
+                || (ct == *b"IEND")
+                || find_rule(ct).is_some_and(|r| r.placement == Placement::AfterPlte)
+        }));
+    }
+
+    match find_rule(chunktype).map_or(Placement::Anywhere, |r| r.placement) {
+        Placement::First | Placement::Last => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "PNG: {} chunk can only be replaced, not inserted",
+                String::from_utf8_lossy(&chunktype)
+            ),
+        )),
+
+        Placement::BeforePlte => Ok(position(&|ct| {
+            (ct == *b"PLTE") || is_image_data(ct) || (ct == *b"IEND")
+        })),
+
+        Placement::AfterPlte | Placement::BeforeIdat => {
+            Ok(position(&|ct| is_image_data(ct) || (ct == *b"IEND")))
+        }
+
+        Placement::Consecutive => Ok(chunks
+            .iter()
+            .rposition(|c| c.chunktype == chunktype)
+            .map_or(end, |i| i + 1)),
+
+        Placement::AfterIdat | Placement::Anywhere => Ok(end),
+    }
+}
+
+/// Copy a chunk from the source stream without parsing it
+fn copy_chunk<R, W>(
+    stream: &mut R,
+    out: &mut CountingWriter<W>,
+    chunkref: &PngChunkRef,
+) -> Result<PngChunkRef, std::io::Error>
+where
+    R: Read + Seek,
+    W: Write,
+{
+    let position = out.position();
+    let total = 4 + 4 + chunkref.length as u64 + 4;
+    stream.seek(SeekFrom::Start(chunkref.position))?;
+    let copied = std::io::copy(&mut stream.by_ref().take(total), out)?;
+    if copied < total {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            format!(
+                "PNG: {} chunk at {} is truncated",
+                chunkref.type_str(),
+                chunkref.position
+            ),
+        ));
+    }
+
+    Ok(PngChunkRef {
+        position,
+        ..*chunkref
+    })
+}
+
+/// Rewrite a file from its chunks and a list of operations
+///
+/// The signature is copied from the start of the stream. Positions of the chunks written are
+/// counted from the start of the signature.
+pub(crate) fn rewrite_chunks<R, W>(
+    stream: &mut R,
+    limits: &Limits,
+    chunks: &[PngChunkRef],
+    ops: &[RewriteOp],
+    out: W,
+) -> Result<Rewritten, std::io::Error>
+where
+    R: Read + Seek,
+    W: Write,
+{
+    // Decide what happens to each chunk of the source
+    let mut replaced = vec![false; ops.len()];
+    let mut fates = Vec::with_capacity(chunks.len());
+    for chunkref in chunks {
+        let mut fate = Fate::Copy;
+        for (i, op) in ops.iter().enumerate() {
+            match op {
+                RewriteOp::Delete(selector) if selects(selector, stream, limits, chunkref)? => {
+                    fate = Fate::Delete;
+                    break;
+                }
+
+                RewriteOp::Replace(selector, _) if selects(selector, stream, limits, chunkref)? => {
+                    fate = if replaced[i] {
+                        Fate::Delete
+                    } else {
+                        Fate::Replace(i)
+                    };
+                    replaced[i] = true;
+                    break;
+                }
+
+                _ => (),
+            }
+        }
+        fates.push(fate);
+    }
+
+    // Find where new chunks go, relative to the source chunks
+    let mut inserts = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        match op {
+            RewriteOp::Insert(chunk) => inserts.push((insert_position(chunks, chunk)?, chunk)),
+
+            RewriteOp::Replace(_, chunk) if !replaced[i] => {
+                inserts.push((insert_position(chunks, chunk)?, chunk))
+            }
+
+            _ => (),
+        }
+    }
+
+    let critical_changed = chunks
+        .iter()
+        .zip(&fates)
+        .any(|(chunkref, fate)| !chunkref.is_ancillary() && (*fate != Fate::Copy))
+        || inserts
+            .iter()
+            .any(|(_, chunk)| chunk.chunktype()[0] & 0x20 == 0);
+    if critical_changed {
+        for (chunkref, fate) in chunks.iter().zip(fates.iter_mut()) {
+            if (*fate == Fate::Copy) && !is_copyable_after_critical_change(chunkref) {
+                *fate = Fate::Delete;
+            }
+        }
+    }
+
+    let mut out = CountingWriter::new(out);
+    let mut signature = [0_u8; 8];
+    stream.seek(SeekFrom::Start(0))?;
+    stream.read_exact(&mut signature)?;
+    out.write_all(&signature)?;
+
+    let mut rewritten = Rewritten::default();
+    for (index, (chunkref, fate)) in chunks.iter().zip(&fates).enumerate() {
+        for (_, chunk) in inserts.iter().filter(|(position, _)| *position == index) {
+            rewritten.written.push(out.write_chunk(chunk)?);
+        }
+
+        match fate {
+            Fate::Copy => rewritten
+                .written
+                .push(copy_chunk(stream, &mut out, chunkref)?),
+
+            Fate::Delete => rewritten.removed.push(*chunkref),
+
+            Fate::Replace(op) => {
+                if let RewriteOp::Replace(_, chunk) = &ops[*op] {
+                    rewritten.written.push(out.write_chunk(chunk)?);
+                }
+            }
+        }
+    }
+    for (_, chunk) in inserts
+        .iter()
+        .filter(|(position, _)| *position == chunks.len())
+    {
+        rewritten.written.push(out.write_chunk(chunk)?);
+    }
+
+    out.flush()?;
+    Ok(rewritten)
+}
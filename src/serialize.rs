@@ -0,0 +1,335 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Serde support, with the `serde` feature
+ *
+ * Binary payloads (image data, compressed text and profiles, Exif, etc) are base64 strings in
+ * human-readable formats like JSON, and byte arrays otherwise. Chunk types are strings.
+ *
+ * Chunks whose values are stored scaled or compressed are serialized with the decoded values
+ * alongside the raw fields:
+ * - cHRM and mDCV: `white`, `red`, `green`, and `blue` coordinates.
+ * - gAMA: `gamma_value`.
+ * - mDCV: `max_lum_value` and `min_lum_value`, in cd/m².
+ * - cLLI: `max_cll_value` and `max_fall_value`, in cd/m².
+ * - zTXt and iTXt: `string`, decompressed up to the default
+ *   [max_text_size](crate::limits::Limits::max_text_size), or null.
+ * - fcTL: `delay_value`, in seconds.
+ * - tIME: `time`, in RFC 3339 format.
+ *
+ * Decoded values are ignored when deserializing.
+ */
+
+use serde::{Serialize, Serializer, ser::SerializeStruct};
+use uom::si::{luminance::candela_per_square_meter, time::second};
+
+use crate::chunks::*;
+use crate::limits::Limits;
+use crate::registry::CustomChunk;
+
+/// Binary payloads as base64 strings or byte arrays
+pub(crate) mod bytes {
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use serde::de::{Deserializer, Error, SeqAccess, Visitor};
+    use serde::ser::Serializer;
+
+    pub fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&STANDARD.encode(bytes))
+        } else {
+            serializer.serialize_bytes(bytes)
+        }
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a base64 string or bytes")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: Error,
+        {
+            STANDARD.decode(v).map_err(E::custom)
+        }
+
+        fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+        where
+            E: Error,
+        {
+            Ok(v.to_vec())
+        }
+
+        fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+        where
+            E: Error,
+        {
+            Ok(v)
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            Ok(bytes)
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(BytesVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)
+        }
+    }
+}
+
+/// Chunk types as four-character strings
+pub(crate) mod chunktype {
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::ser::Serializer;
+
+    pub fn serialize<S>(chunktype: &[u8; 4], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&String::from_utf8_lossy(chunktype))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<[u8; 4], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let chunktype = String::deserialize(deserializer)?;
+        chunktype
+            .as_bytes()
+            .try_into()
+            .map_err(|_| D::Error::custom(format!("Invalid chunk type {:?}", chunktype)))
+    }
+}
+
+/// Custom chunks, with their chunk type and raw data
+#[allow(clippy::borrowed_box)]
+pub(crate) fn custom<S>(chunk: &Box<dyn CustomChunk>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut data = Vec::with_capacity(chunk.contents_length() as usize);
+    chunk
+        .write_dyn(&mut data, None)
+        .map_err(serde::ser::Error::custom)?;
+
+    #[derive(Serialize)]
+    struct Custom<'a> {
+        #[serde(with = "chunktype")]
+        chunktype: [u8; 4],
+        #[serde(with = "bytes")]
+        data: &'a Vec<u8>,
+    }
+
+    Custom {
+        chunktype: chunk.chunk_type(),
+        data: &data,
+    }
+    .serialize(serializer)
+}
+
+/// Raw bytes, for serializing fields with [bytes]
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        bytes::serialize(self.0, serializer)
+    }
+}
+
+impl Serialize for Chrm {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("Chrm", 12)?;
+        s.serialize_field("white_x", &self.white_x)?;
+        s.serialize_field("white_y", &self.white_y)?;
+        s.serialize_field("red_x", &self.red_x)?;
+        s.serialize_field("red_y", &self.red_y)?;
+        s.serialize_field("green_x", &self.green_x)?;
+        s.serialize_field("green_y", &self.green_y)?;
+        s.serialize_field("blue_x", &self.blue_x)?;
+        s.serialize_field("blue_y", &self.blue_y)?;
+        s.serialize_field("white", &self.white_coords())?;
+        s.serialize_field("red", &self.red_coords())?;
+        s.serialize_field("green", &self.green_coords())?;
+        s.serialize_field("blue", &self.blue_coords())?;
+        s.end()
+    }
+}
+
+impl Serialize for Gama {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("Gama", 2)?;
+        s.serialize_field("gamma", &self.gamma)?;
+        s.serialize_field("gamma_value", &self.gamma())?;
+        s.end()
+    }
+}
+
+impl Serialize for Mdcv {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("Mdcv", 16)?;
+        s.serialize_field("red_x", &self.red_x)?;
+        s.serialize_field("red_y", &self.red_y)?;
+        s.serialize_field("green_x", &self.green_x)?;
+        s.serialize_field("green_y", &self.green_y)?;
+        s.serialize_field("blue_x", &self.blue_x)?;
+        s.serialize_field("blue_y", &self.blue_y)?;
+        s.serialize_field("white_x", &self.white_x)?;
+        s.serialize_field("white_y", &self.white_y)?;
+        s.serialize_field("max_lum", &self.max_lum)?;
+        s.serialize_field("min_lum", &self.min_lum)?;
+        s.serialize_field("red", &self.red_coords())?;
+        s.serialize_field("green", &self.green_coords())?;
+        s.serialize_field("blue", &self.blue_coords())?;
+        s.serialize_field("white", &self.white_coords())?;
+        s.serialize_field(
+            "max_lum_value",
+            &self.max_lum().get::<candela_per_square_meter>(),
+        )?;
+        s.serialize_field(
+            "min_lum_value",
+            &self.min_lum().get::<candela_per_square_meter>(),
+        )?;
+        s.end()
+    }
+}
+
+impl Serialize for Clli {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("Clli", 4)?;
+        s.serialize_field("max_cll", &self.max_cll)?;
+        s.serialize_field("max_fall", &self.max_fall)?;
+        s.serialize_field(
+            "max_cll_value",
+            &self.max_cll().get::<candela_per_square_meter>(),
+        )?;
+        s.serialize_field(
+            "max_fall_value",
+            &self.max_fall().get::<candela_per_square_meter>(),
+        )?;
+        s.end()
+    }
+}
+
+impl Serialize for Ztxt {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("Ztxt", 4)?;
+        s.serialize_field("keyword", &self.keyword)?;
+        s.serialize_field("compression_method", &self.compression_method)?;
+        s.serialize_field("compressed_string", &Bytes(&self.compressed_string))?;
+        s.serialize_field(
+            "string",
+            &self.string_with_limit(Limits::default().max_text_size).ok(),
+        )?;
+        s.end()
+    }
+}
+
+impl Serialize for Itxt {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("Itxt", 6)?;
+        s.serialize_field("keyword", &self.keyword)?;
+        s.serialize_field("compression_method", &self.compression_method)?;
+        s.serialize_field("language", &self.language)?;
+        s.serialize_field("translated_keyword", &self.translated_keyword)?;
+        s.serialize_field("compressed_string", &Bytes(&self.compressed_string))?;
+        s.serialize_field(
+            "string",
+            &self.string_with_limit(Limits::default().max_text_size).ok(),
+        )?;
+        s.end()
+    }
+}
+
+impl Serialize for Fctl {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("Fctl", 10)?;
+        s.serialize_field("sequence_number", &self.sequence_number)?;
+        s.serialize_field("width", &self.width)?;
+        s.serialize_field("height", &self.height)?;
+        s.serialize_field("x_offset", &self.x_offset)?;
+        s.serialize_field("y_offset", &self.y_offset)?;
+        s.serialize_field("delay_num", &self.delay_num)?;
+        s.serialize_field("delay_den", &self.delay_den)?;
+        s.serialize_field("dispose_op", &self.dispose_op)?;
+        s.serialize_field("blend_op", &self.blend_op)?;
+        s.serialize_field("delay_value", &self.delay().get::<second>())?;
+        s.end()
+    }
+}
+
+impl Serialize for Time {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("Time", 7)?;
+        s.serialize_field("year", &self.year)?;
+        s.serialize_field("month", &self.month)?;
+        s.serialize_field("day", &self.day)?;
+        s.serialize_field("hour", &self.hour)?;
+        s.serialize_field("minute", &self.minute)?;
+        s.serialize_field("second", &self.second)?;
+        s.serialize_field("time", &self.time().map(|t| t.to_rfc3339()))?;
+        s.end()
+    }
+}
@@ -0,0 +1,396 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Zero-copy parsing of PNG/APNG/JNG files held in memory
+ *
+ * Chunk payloads are borrowed from the byte slice rather than copied. A memory-mapped file
+ * derefs to a byte slice, so the same API can scan very large files without allocating per
+ * chunk:
+ *
+ * ```ignore
+ * let mmap = unsafe { memmap2::Mmap::map(&file)? };
+ * let png = PngSlice::new(&mmap)?;
+ * for chunk in png.chunks() {
+ *     let chunk = chunk?;
+ *     // ...
+ * }
+ * ```
+ */
+
+use std::io::Read;
+
+use crate::chunks::*;
+use crate::crc::*;
+use crate::error::Error;
+use crate::limits::*;
+use crate::registry::ChunkRegistry;
+use crate::types::*;
+
+/// A PNG, APNG, or JNG file in a byte slice
+#[derive(Clone, Copy, Debug)]
+pub struct PngSlice<'a> {
+    /// Image file type
+    ///
+    /// PNG or JNG. APNG files are reported as PNG.
+    pub filetype: PngFileType,
+
+    /// Resource limits for reading untrusted files
+    ///
+    /// Borrowing chunks doesn't allocate, so [max_chunk_length](Limits::max_chunk_length) only
+    /// applies when a chunk is parsed with [read_chunk()](PngChunkSlice::read_chunk).
+    pub limits: Limits,
+
+    data: &'a [u8],
+}
+
+impl<'a> PngSlice<'a> {
+    /// Constructor from a byte slice
+    ///
+    /// This just checks the file signature.
+    pub fn new(data: &'a [u8]) -> Result<Self, std::io::Error> {
+        let filetype = match data.get(0..8) {
+            Some([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]) => PngFileType::Png,
+            Some([0x8b, 0x4a, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a]) => PngFileType::Jng,
+            _ => return Err(Error::BadSignature { offset: 0 }.into()),
+        };
+
+        Ok(Self {
+            filetype,
+            limits: Limits::default(),
+            data,
+        })
+    }
+
+    /// The whole file
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Iterate over the chunks in the file, checking the CRC of each one
+    ///
+    /// Iteration stops after the IEND chunk, or with an error after
+    /// [max_chunks](Limits::max_chunks) chunks.
+    pub fn chunks(&self) -> PngSliceChunks<'a> {
+        PngSliceChunks {
+            data: self.data,
+            limits: self.limits,
+            position: 8,
+            chunk_count: 0,
+            check_crc: true,
+            finished: false,
+        }
+    }
+
+    /// Iterate over the chunks in the file without checking their CRCs
+    pub fn chunks_unchecked(&self) -> PngSliceChunks<'a> {
+        PngSliceChunks {
+            check_crc: false,
+            ..self.chunks()
+        }
+    }
+
+    /// Get the chunk at the position given by a chunk reference
+    pub fn chunk_at(&self, chunkref: &PngChunkRef) -> Result<PngChunkSlice<'a>, std::io::Error> {
+        chunk_slice_at(self.data, &self.limits, chunkref.position)
+    }
+}
+
+/// Get the chunk at a position in a byte slice
+fn chunk_slice_at<'a>(
+    data: &'a [u8],
+    limits: &Limits,
+    position: u64,
+) -> Result<PngChunkSlice<'a>, std::io::Error> {
+    let truncated = || {
+        std::io::Error::from(Error::TruncatedData {
+            chunktype: None,
+            offset: position,
+            message: format!("PNG: Truncated chunk at position {}", position),
+        })
+    };
+
+    let start = usize::try_from(position).map_err(|_| truncated())?;
+    let data_start = start.checked_add(8).ok_or_else(truncated)?;
+    let header = data.get(start..data_start).ok_or_else(truncated)?;
+    let chunkref = PngChunkRef::from_stream_at(&mut &header[..], position)?;
+
+    let data_end = data_start
+        .checked_add(chunkref.length as usize)
+        .ok_or_else(truncated)?;
+    let crc_end = data_end.checked_add(4).ok_or_else(truncated)?;
+    let contents = data.get(data_start..data_end).ok_or_else(truncated)?;
+    let crc_bytes = data.get(data_end..crc_end).ok_or_else(truncated)?;
+
+    Ok(PngChunkSlice {
+        chunkref,
+        data: contents,
+        crc: u32::from_be_bytes([crc_bytes[0], crc_bytes[1], crc_bytes[2], crc_bytes[3]]),
+        limits: *limits,
+    })
+}
+
+/// Iterator over the chunks of a [PngSlice]
+#[derive(Clone, Debug)]
+pub struct PngSliceChunks<'a> {
+    data: &'a [u8],
+    limits: Limits,
+    position: u64,
+    chunk_count: u64,
+    check_crc: bool,
+    finished: bool,
+}
+
+impl<'a> Iterator for PngSliceChunks<'a> {
+    type Item = Result<PngChunkSlice<'a>, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished || self.position as usize >= self.data.len() {
+            return None;
+        }
+
+        self.chunk_count += 1;
+        let chunk = chunk_slice_at(self.data, &self.limits, self.position).and_then(|chunk| {
+            self.limits
+                .check_chunk_count(self.chunk_count)
+                .map_err(|e| e.in_chunk(&chunk.chunkref))?;
+            if self.check_crc {
+                chunk.check_crc()?;
+            }
+            Ok(chunk)
+        });
+
+        match &chunk {
+            Ok(chunk) => {
+                self.position += 4 + 4 + chunk.chunkref.length as u64 + 4;
+                if chunk.chunkref.chunktype == *b"IEND" {
+                    self.finished = true;
+                }
+            }
+
+            // Don't keep trying after an error
+            Err(_) => self.finished = true,
+        }
+
+        Some(chunk)
+    }
+}
+
+/// A chunk borrowed from a [PngSlice]
+#[derive(Clone, Copy, Debug)]
+pub struct PngChunkSlice<'a> {
+    /// Reference to the chunk
+    pub chunkref: PngChunkRef,
+
+    /// Chunk data
+    pub data: &'a [u8],
+
+    /// CRC value stored in the file
+    pub crc: u32,
+
+    limits: Limits,
+}
+
+impl<'a> PngChunkSlice<'a> {
+    /// Convert the chunk type bytes to a string
+    #[inline]
+    pub fn type_str(&self) -> &str {
+        self.chunkref.type_str()
+    }
+
+    /// Compute the CRC of the chunk type and data
+    pub fn computed_crc(&self) -> u32 {
+        let mut data_crc = CRC::new();
+        data_crc.consume(&self.chunkref.chunktype);
+        data_crc.consume(self.data);
+        data_crc.value()
+    }
+
+    /// Check that the stored CRC matches the computed one
+    pub fn check_crc(&self) -> Result<(), std::io::Error> {
+        let computed = self.computed_crc();
+        if self.crc != computed {
+            return Err(Error::CrcMismatch {
+                chunktype: self.chunkref.chunktype,
+                offset: self.chunkref.position,
+                expected: self.crc,
+                actual: computed,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Parse the chunk into an owned PngChunkData enum
+    ///
+    /// `ihdr`: The IHDR chunk, only used for tRNS, sBIT, and bKGD chunks for the colour_type value.
+    ///
+    /// Chunks longer than the [max_chunk_length](Limits::max_chunk_length) of the [PngSlice]
+    /// are rejected before anything is copied.
+    pub fn read_chunk(
+        &self,
+        ihdr: Option<&Ihdr>,
+        registry: &ChunkRegistry,
+    ) -> Result<PngChunkData, std::io::Error> {
+        self.limits
+            .check_chunk_length(self.chunkref.length)
+            .map_err(|e| e.in_chunk(&self.chunkref))?;
+        let crc_bytes = self.crc.to_be_bytes();
+        let mut stream = self.data.chain(&crc_bytes[..]);
+        self.chunkref.read_contents(&mut stream, ihdr, registry)
+    }
+
+    /// Borrow the data of an IDAT chunk
+    pub fn idat(&self) -> Option<IdatRef<'a>> {
+        (self.chunkref.chunktype == Idat::TYPE).then_some(IdatRef(self.data))
+    }
+
+    /// Borrow the data of an fdAT chunk
+    pub fn fdat(&self) -> Option<FdatRef<'a>> {
+        if self.chunkref.chunktype != Fdat::TYPE || self.data.len() < 4 {
+            return None;
+        }
+
+        Some(FdatRef {
+            sequence_number: u32::from_be_bytes([
+                self.data[0],
+                self.data[1],
+                self.data[2],
+                self.data[3],
+            ]),
+            frame_data: &self.data[4..],
+        })
+    }
+
+    /// Borrow the data of a JDAT chunk
+    pub fn jdat(&self) -> Option<JdatRef<'a>> {
+        (self.chunkref.chunktype == Jdat::TYPE).then_some(JdatRef(self.data))
+    }
+
+    /// Borrow the data of an eXIf chunk
+    pub fn exif(&self) -> Option<ExifRef<'a>> {
+        (self.chunkref.chunktype == Exif::TYPE).then_some(ExifRef(self.data))
+    }
+
+    /// Borrow the data of an iCCP chunk
+    pub fn iccp(&self) -> Option<IccpRef<'a>> {
+        if self.chunkref.chunktype != Iccp::TYPE {
+            return None;
+        }
+
+        let name_end = self.data.iter().position(|b| *b == 0)?;
+        Some(IccpRef {
+            name: &self.data[..name_end],
+            compression_method: (*self.data.get(name_end + 1)?).try_into().ok()?,
+            compressed_profile: &self.data[name_end + 2..],
+        })
+    }
+}
+
+/// Image data borrowed from an IDAT chunk
+#[derive(Clone, Copy, Debug)]
+pub struct IdatRef<'a>(pub &'a [u8]);
+
+impl IdatRef<'_> {
+    /// Copy into an owned IDAT chunk
+    pub fn to_chunk(&self) -> Idat {
+        Idat(self.0.to_vec())
+    }
+}
+
+/// Frame data borrowed from an fdAT chunk
+#[derive(Clone, Copy, Debug)]
+pub struct FdatRef<'a> {
+    pub sequence_number: u32,
+    pub frame_data: &'a [u8],
+}
+
+impl FdatRef<'_> {
+    /// Copy into an owned fdAT chunk
+    pub fn to_chunk(&self) -> Fdat {
+        Fdat {
+            sequence_number: self.sequence_number,
+            frame_data: self.frame_data.to_vec(),
+        }
+    }
+}
+
+/// JNG image data borrowed from a JDAT chunk
+#[derive(Clone, Copy, Debug)]
+pub struct JdatRef<'a>(pub &'a [u8]);
+
+impl JdatRef<'_> {
+    /// Copy into an owned JDAT chunk
+    pub fn to_chunk(&self) -> Jdat {
+        Jdat(self.0.to_vec())
+    }
+}
+
+/// Exif profile borrowed from an eXIf chunk
+#[derive(Clone, Copy, Debug)]
+pub struct ExifRef<'a>(pub &'a [u8]);
+
+impl ExifRef<'_> {
+    /// Copy into an owned eXIf chunk
+    pub fn to_chunk(&self) -> Exif {
+        Exif(self.0.to_vec())
+    }
+}
+
+/// Embedded ICC profile borrowed from an iCCP chunk
+#[derive(Clone, Copy, Debug)]
+pub struct IccpRef<'a> {
+    /// Profile name, in Latin-1
+    pub name: &'a [u8],
+    pub compression_method: PngCompressionMethod,
+    pub compressed_profile: &'a [u8],
+}
+
+impl IccpRef<'_> {
+    /// Profile name
+    pub fn name(&self) -> String {
+        self.name.iter().map(|b| *b as char).collect()
+    }
+
+    /// Copy into an owned iCCP chunk
+    pub fn to_chunk(&self) -> Iccp {
+        Iccp {
+            name: self.name(),
+            compression_method: self.compression_method,
+            compressed_profile: self.compressed_profile.to_vec(),
+        }
+    }
+
+    /// Uncompressed profile, decompressed straight from the borrowed data
+    ///
+    /// Returns None if it can't be decompressed. The size isn't limited, see
+    /// [profile_with_limit()](Self::profile_with_limit) for untrusted files.
+    pub fn profile(&self) -> Option<Vec<u8>> {
+        self.profile_with_limit(u64::MAX).ok()
+    }
+
+    /// Uncompressed profile, up to a maximum size in bytes
+    pub fn profile_with_limit(&self, max_size: u64) -> std::io::Result<Vec<u8>> {
+        inflate_limited(
+            self.compressed_profile,
+            max_size,
+            LimitKind::IccProfileSize,
+            Iccp::TYPE,
+        )
+    }
+}
@@ -0,0 +1,296 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Forward-only PNG/APNG reader
+ *
+ * Unlike [PngReader](crate::reader::PngReader), this never seeks, so it can read from pipes,
+ * stdin, sockets, or decompressing readers.
+ */
+
+use std::io::Read;
+
+use crate::chunks::*;
+use crate::error::Error;
+use crate::limits::*;
+use crate::registry::ChunkRegistry;
+use crate::types::*;
+
+/// A forward-only PNG/APNG reader
+///
+/// Chunks are read and parsed in file order.
+#[derive(Clone, Debug)]
+pub struct PngStreamReader<R> {
+    /// Image file type
+    ///
+    /// PNG or APNG. This is only known once an acTL, fcTL, or fdAT chunk has been read.
+    pub filetype: PngFileType,
+
+    /// Image width in pixels
+    pub width: u32,
+
+    /// Image height in pixels
+    pub height: u32,
+
+    /// Image bit depth per pixel component
+    pub bit_depth: u8,
+
+    /// Image colour type
+    pub colour_type: PngColourType,
+
+    /// Stream we're reading from
+    pub stream: R,
+
+    /// The IHDR chunk data
+    pub ihdr: Option<Ihdr>,
+
+    /// User-defined chunk types to consult when reading chunks
+    pub registry: ChunkRegistry,
+
+    /// Resource limits for reading untrusted files
+    ///
+    /// Every chunk, including IDAT and fdAT, is read into memory, so all of them are held to
+    /// [max_chunk_length](Limits::max_chunk_length).
+    pub limits: Limits,
+
+    position: u64,
+    finished: bool,
+    chunk_count: u64,
+    text_size: u64,
+
+    next_sequence_number: u32,
+    next_fctl: Option<Fctl>,
+}
+
+impl<R> PngStreamReader<R>
+where
+    R: Read,
+{
+    /// Constructor from a Read-able type
+    ///
+    /// This just checks the file signature. Use next_chunk() or next_frame() to read chunks.
+    pub fn from_stream(mut stream: R) -> Result<Self, std::io::Error> {
+        // First check the signature
+        {
+            let mut signature = [0; 8];
+            stream.read_exact(&mut signature)?;
+            if signature != [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a] {
+                return Err(Error::BadSignature { offset: 0 }.into());
+            }
+        }
+
+        Ok(PngStreamReader {
+            filetype: PngFileType::Png,
+            width: 0,
+            height: 0,
+            bit_depth: 0,
+            colour_type: PngColourType::Greyscale,
+            stream,
+            ihdr: None,
+            registry: ChunkRegistry::default(),
+            limits: Limits::default(),
+            position: 8,
+            finished: false,
+            chunk_count: 0,
+            text_size: 0,
+            next_sequence_number: 0,
+            next_fctl: None,
+        })
+    }
+
+    /// Position in the stream of the next chunk
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Read and parse the next chunk
+    ///
+    /// Returns None once the IEND chunk has been read.
+    pub fn next_chunk(&mut self) -> Result<Option<(PngChunkRef, PngChunkData)>, std::io::Error> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let chunkref = PngChunkRef::from_stream_at(&mut self.stream, self.position)?;
+
+        // Invalid chunk types for PNG/APNG files
+        if matches!(&chunkref.chunktype, b"JHDR" | b"JDAT" | b"JDAA" | b"JSEP") {
+            return Err(Error::DisallowedChunk {
+                chunktype: chunkref.chunktype,
+                offset: chunkref.position,
+                filetype: PngFileType::Png,
+            }
+            .into());
+        }
+
+        self.chunk_count += 1;
+        self.limits
+            .check_chunk_count(self.chunk_count)
+            .map_err(|e| e.in_chunk(&chunkref))?;
+        self.limits
+            .check_chunk_length(chunkref.length)
+            .map_err(|e| e.in_chunk(&chunkref))?;
+
+        let chunk = chunkref.read_contents(&mut self.stream, self.ihdr.as_ref(), &self.registry)?;
+        self.position += 4 + 4 + chunkref.length as u64 + 4;
+
+        match &chunk {
+            PngChunkData::Ihdr(ihdr) => {
+                self.limits
+                    .check_image_size(ihdr.width, ihdr.height, ihdr.pixel_bits())
+                    .map_err(|e| e.in_chunk(&chunkref))?;
+
+                // Fill in image metadata
+                self.ihdr = Some(*ihdr);
+                self.width = ihdr.width;
+                self.height = ihdr.height;
+                self.bit_depth = ihdr.bit_depth;
+                self.colour_type = ihdr.colour_type;
+            }
+
+            PngChunkData::Actl(_) | PngChunkData::Fctl(_) | PngChunkData::Fdat(_) => {
+                self.filetype = PngFileType::Apng;
+            }
+
+            PngChunkData::Iend => {
+                self.finished = true;
+            }
+
+            _ => (),
+        }
+
+        Ok(Some((chunkref, chunk)))
+    }
+
+    /// Get the string of a tEXt, zTXt, or iTXt chunk
+    ///
+    /// Decompressed text counts towards the [max_text_size](Limits::max_text_size) limit, which
+    /// is shared by all of the chunks in the file. Returns None for other chunk types.
+    pub fn text_string(&mut self, chunk: &PngChunkData) -> Result<Option<String>, std::io::Error> {
+        text_string(&self.limits, &mut self.text_size, chunk)
+    }
+
+    /// Get the decompressed profile of an iCCP chunk
+    ///
+    /// Returns None for other chunk types.
+    pub fn iccp_profile(&self, chunk: &PngChunkData) -> Result<Option<Vec<u8>>, std::io::Error> {
+        iccp_profile(&self.limits, chunk)
+    }
+
+    /// Check that an fcTL or fdAT sequence number is the next one expected
+    fn check_sequence_number(
+        &mut self,
+        chunkref: &PngChunkRef,
+        sequence_number: u32,
+    ) -> Result<(), std::io::Error> {
+        if sequence_number != self.next_sequence_number {
+            return Err(Error::OrderingViolation {
+                chunktype: chunkref.chunktype,
+                offset: chunkref.position,
+                message: format!(
+                    "APNG: Sequence number {} is out of order (expected {}), frames can't be grouped without seeking",
+                    sequence_number, self.next_sequence_number
+                ),
+            }
+            .into());
+        }
+
+        self.next_sequence_number += 1;
+        Ok(())
+    }
+
+    /// Read chunks until the next complete APNG frame
+    ///
+    /// Chunks must be in sequence number order. Returns None once the IEND chunk has been read.
+    /// A default image (IDAT chunks without a preceding fcTL) is not returned as a frame.
+    pub fn next_frame(&mut self) -> Result<Option<ApngStreamFrame>, std::io::Error> {
+        let mut frame = self.next_fctl.take().map(|fctl| ApngStreamFrame {
+            fctl,
+            dats: Vec::new(),
+        });
+
+        while let Some((chunkref, chunk)) = self.next_chunk()? {
+            match chunk {
+                PngChunkData::Fctl(fctl) => {
+                    self.check_sequence_number(&chunkref, fctl.sequence_number)?;
+                    if frame.is_some() {
+                        self.next_fctl = Some(*fctl);
+                        return Ok(frame);
+                    }
+
+                    frame = Some(ApngStreamFrame {
+                        fctl: *fctl,
+                        dats: Vec::new(),
+                    });
+                }
+
+                PngChunkData::Idat(_) => {
+                    if let Some(frame) = &mut frame {
+                        frame.dats.push(chunk);
+                    }
+                }
+
+                PngChunkData::Fdat(ref fdat) => {
+                    self.check_sequence_number(&chunkref, fdat.sequence_number)?;
+                    if let Some(frame) = &mut frame {
+                        frame.dats.push(chunk);
+                    } else {
+                        return Err(Error::OrderingViolation {
+                            chunktype: chunkref.chunktype,
+                            offset: chunkref.position,
+                            message: "At least one fcTL chunk must go before fdAT chunks"
+                                .to_string(),
+                        }
+                        .into());
+                    }
+                }
+
+                _ => (),
+            }
+        }
+
+        Ok(frame)
+    }
+}
+
+impl<R> Iterator for PngStreamReader<R>
+where
+    R: Read,
+{
+    type Item = Result<(PngChunkRef, PngChunkData), std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_chunk() {
+            Ok(Some(chunk)) => Some(Ok(chunk)),
+            Ok(None) => None,
+            Err(e) => {
+                // Don't keep trying after an error
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// An APNG frame read from a forward-only stream
+#[derive(Clone, Debug)]
+pub struct ApngStreamFrame {
+    pub fctl: Fctl,
+
+    /// The IDAT or fdAT chunks of this frame
+    pub dats: Vec<PngChunkData>,
+}
@@ -0,0 +1,91 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Stripping metadata from files
+ *
+ * See [PngReader::strip()](crate::reader::PngReader::strip) and
+ * [JngReader::strip()](crate::jngreader::JngReader::strip). Stripping is a
+ * [rewrite](crate::rewrite), so the chunks that are kept are copied byte-for-byte.
+ */
+
+use crate::chunks::PngChunkRef;
+
+/// Colour management chunk types, kept by [StripPolicy::AllExceptColour]
+pub const COLOUR_CHUNK_TYPES: &[[u8; 4]] = &[
+    *b"gAMA", *b"cHRM", *b"iCCP", *b"sRGB", *b"cICP", *b"mDCV", *b"cLLI",
+];
+
+/// Metadata chunk types, removed by [StripPolicy::Metadata]
+pub const METADATA_CHUNK_TYPES: &[[u8; 4]] = &[*b"tEXt", *b"zTXt", *b"iTXt", *b"tIME", *b"eXIf"];
+
+/// Ancillary chunk types that are needed to display the image, and are never stripped
+const DISPLAY_CHUNK_TYPES: &[[u8; 4]] = &[*b"tRNS", *b"acTL", *b"fcTL", *b"fdAT"];
+
+/// Which ancillary chunks to remove
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StripPolicy {
+    /// All ancillary chunks
+    ///
+    /// Transparency (tRNS) and animation (acTL, fcTL, fdAT) chunks are part of the image, so
+    /// they are kept by every policy.
+    All,
+
+    /// All ancillary chunks except for colour management, see [COLOUR_CHUNK_TYPES]
+    AllExceptColour,
+
+    /// Only text, time, and Exif chunks, see [METADATA_CHUNK_TYPES]
+    Metadata,
+}
+
+impl StripPolicy {
+    /// Does this policy remove a chunk?
+    ///
+    /// Critical chunks are never removed.
+    pub fn strips(&self, chunkref: &PngChunkRef) -> bool {
+        if !chunkref.is_ancillary() || DISPLAY_CHUNK_TYPES.contains(&chunkref.chunktype) {
+            return false;
+        }
+
+        match self {
+            Self::All => true,
+            Self::AllExceptColour => !COLOUR_CHUNK_TYPES.contains(&chunkref.chunktype),
+            Self::Metadata => METADATA_CHUNK_TYPES.contains(&chunkref.chunktype),
+        }
+    }
+}
+
+/// What was removed by stripping a file
+#[derive(Clone, Debug, Default)]
+pub struct StripReport {
+    /// Chunks of the source file that were removed, in file order
+    pub removed: Vec<PngChunkRef>,
+
+    /// Total size of the removed chunks, including their length, type, and CRC
+    pub bytes_saved: u64,
+}
+
+impl StripReport {
+    pub(crate) fn new(removed: Vec<PngChunkRef>) -> Self {
+        let bytes_saved = removed.iter().map(|c| 4 + 4 + c.length as u64 + 4).sum();
+
+        Self {
+            removed,
+            bytes_saved,
+        }
+    }
+}
@@ -23,6 +23,7 @@ use num_enum::{FromPrimitive, IntoPrimitive, TryFromPrimitive};
 
 /// All of the different file types based on PNG
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PngFileType {
     /// Portable Network Graphics
     Png,
@@ -39,6 +40,7 @@ pub enum PngFileType {
 
 /// Colour type of image
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum PngColourType {
     /// Greyscale image - allowed depths of 1, 2, 4, 8, or 16 bits per pixel
@@ -68,10 +70,20 @@ impl PngColourType {
             PngColourType::TrueColourAlpha => 4,
         }
     }
+
+    /// Bit depths allowed for this colour type
+    pub fn allowed_bit_depths(&self) -> &'static [u8] {
+        match self {
+            PngColourType::Greyscale => &[1, 2, 4, 8, 16],
+            PngColourType::IndexedColour => &[1, 2, 4, 8],
+            _ => &[8, 16],
+        }
+    }
 }
 
 /// Compression method(s)
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum PngCompressionMethod {
     /// DEFLATE
@@ -81,6 +93,7 @@ pub enum PngCompressionMethod {
 
 /// Filter methods
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum PngFilterMethod {
     /// Adaptive filtering with five basic filter types
@@ -90,6 +103,7 @@ pub enum PngFilterMethod {
 
 /// Filter types
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum PngFilterType {
     None = 0,
@@ -101,6 +115,7 @@ pub enum PngFilterType {
 
 /// Interlacing methods
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum PngInterlaceMethod {
     /// No interlacing
@@ -112,6 +127,7 @@ pub enum PngInterlaceMethod {
 
 /// Palette entry for for PLTE chunk
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PngPaletteEntry {
     pub red: u8,
     pub green: u8,
@@ -120,6 +136,7 @@ pub struct PngPaletteEntry {
 
 /// ICC rendering intent
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum PngRenderingIntent {
     Perceptual = 0,
@@ -130,6 +147,7 @@ pub enum PngRenderingIntent {
 
 /// Unit type used in several chunks
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum PngUnitType {
     Unknown = 0,
@@ -141,6 +159,7 @@ pub enum PngUnitType {
 ///
 /// When depth=8, the red, green, blue, and alpha fields will actually be unscaled u8 values.
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PngSuggestedPaletteEntry {
     pub red: u16,
     pub green: u16,
@@ -151,6 +170,7 @@ pub struct PngSuggestedPaletteEntry {
 
 /// H.273 colour primaries
 #[derive(Clone, Copy, Debug, IntoPrimitive, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum ColourPrimaries {
     /// Rec. ITU-R BT.709-6\
@@ -288,11 +308,11 @@ impl ColourPrimaries {
             ColourPrimaries::Unspecified | ColourPrimaries::Reserved(_) => (0.0, 0.0),
         }
     }
-
 }
 
 /// H.273 transfer functions
 #[derive(Clone, Copy, Debug, IntoPrimitive, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum TransferFunction {
     /// Rec. ITU-R BT.709-6\
@@ -368,6 +388,7 @@ pub enum TransferFunction {
 
 /// H.273 matrix coefficients
 #[derive(Clone, Copy, Debug, IntoPrimitive, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum MatrixCoefficients {
     /// The identity matrix.\
@@ -443,6 +464,7 @@ pub enum MatrixCoefficients {
 
 /// Equation types used in the pCAL chunk
 #[derive(Clone, Copy, Debug, PartialEq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum CalibrationEquationType {
     /// Linear mapping
@@ -460,6 +482,7 @@ pub enum CalibrationEquationType {
 
 /// GIF Disposal methods for gIFg chunk
 #[derive(Clone, Copy, Debug, PartialEq, IntoPrimitive, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum GifDisposalMethod {
     /// No disposal specified
@@ -481,6 +504,7 @@ pub enum GifDisposalMethod {
 
 /// Stereo modes for the sTER chunk
 #[derive(Clone, Copy, Debug, PartialEq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum StereoMode {
     /// The right-eye image appears at the left and the left-eye image appears
@@ -494,6 +518,7 @@ pub enum StereoMode {
 
 /// Disposal operators in the "fcTL" chunk
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum ApngDisposalOperator {
     None,
@@ -503,6 +528,7 @@ pub enum ApngDisposalOperator {
 
 /// Blend operators in the "fcTL" chunk
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum ApngBlendOperator {
     Source,
@@ -511,6 +537,7 @@ pub enum ApngBlendOperator {
 
 /// Colour type of JNG image
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum JngColourType {
     Greyscale = 8,
@@ -526,6 +553,7 @@ pub enum JngColourType {
 
 /// JNG image sample depth
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum JngImageSampleDepth {
     Depth8 = 8,
@@ -537,6 +565,7 @@ pub enum JngImageSampleDepth {
 
 /// JNG image and alpha compression type
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum JngCompressionType {
     /// PNG greyscale
@@ -548,6 +577,7 @@ pub enum JngCompressionType {
 
 /// JNG alpha sample depth
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum JngAlphaSampleDepth {
     Depth0 = 0,
@@ -560,6 +590,7 @@ pub enum JngAlphaSampleDepth {
 
 /// JNG image and alpha interlace type
 #[derive(Copy, Clone, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum JngInterlaceMethod {
     SequentialJPEG = 0,
@@ -0,0 +1,676 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! Checking whole PNG and APNG files, in the style of pngcheck
+ *
+ * [validate()] scans every chunk of a file, parses the ones that aren't image data, and checks
+ * them against each other and the IHDR chunk. Everything found is returned as a [Finding]
+ * with a [Severity] and a [Code].
+ */
+
+use std::io::{Read, Seek};
+
+use crate::chunks::{Fctl, Hist, PngChunkData, PngChunkRef, Sbit, Trns};
+use crate::ordering::OrderValidator;
+use crate::reader::PngReader;
+use crate::recovery;
+use crate::types::PngColourType;
+
+/// Largest width or height allowed in IHDR, 2^31-1
+const MAX_DIMENSION: u32 = 0x7fff_ffff;
+
+/// How serious a problem is
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Not strictly an error, but some decoders may not accept it
+    Warning,
+
+    /// Breaks the PNG or APNG specification
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// What kind of problem was found
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Code {
+    /// The CRC of a chunk doesn't match its contents
+    CrcMismatch,
+
+    /// The file is damaged, e.g. it has garbage between chunks or is truncated
+    Damaged,
+
+    /// A chunk couldn't be parsed
+    InvalidChunk,
+
+    /// A chunk is out of order, or there are too many of it
+    ChunkOrder,
+
+    /// The width or height in IHDR is out of range
+    ImageSize,
+
+    /// The bit depth isn't allowed for the colour type
+    BitDepth,
+
+    /// An indexed colour image has no PLTE chunk
+    MissingPalette,
+
+    /// A greyscale image has a PLTE chunk
+    UnexpectedPalette,
+
+    /// The number of PLTE entries is out of range for the bit depth
+    PaletteSize,
+
+    /// A tRNS chunk has more entries than the palette
+    TrnsLength,
+
+    /// A hIST chunk doesn't have one entry per palette entry
+    HistLength,
+
+    /// An sBIT value is zero or greater than the sample depth
+    SbitValue,
+
+    /// A text chunk keyword is empty, too long, or has illegal characters or spacing
+    Keyword,
+
+    /// The number of frames in acTL doesn't match the number of fcTL chunks
+    FrameCount,
+
+    /// A frame isn't inside the image
+    FrameBounds,
+
+    /// fcTL and fdAT sequence numbers aren't contiguous from zero
+    SequenceNumber,
+
+    /// More than one fcTL chunk is before IDAT, or an fcTL chunk after IDAT has no fdAT chunks
+    FrameOrder,
+
+    /// An iDOT segment doesn't point at an IDAT chunk
+    IdotPosition,
+}
+
+impl Code {
+    /// Short name, for filtering and display
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CrcMismatch => "crc-mismatch",
+            Self::Damaged => "damaged",
+            Self::InvalidChunk => "invalid-chunk",
+            Self::ChunkOrder => "chunk-order",
+            Self::ImageSize => "image-size",
+            Self::BitDepth => "bit-depth",
+            Self::MissingPalette => "missing-palette",
+            Self::UnexpectedPalette => "unexpected-palette",
+            Self::PaletteSize => "palette-size",
+            Self::TrnsLength => "trns-length",
+            Self::HistLength => "hist-length",
+            Self::SbitValue => "sbit-value",
+            Self::Keyword => "keyword",
+            Self::FrameCount => "frame-count",
+            Self::FrameBounds => "frame-bounds",
+            Self::SequenceNumber => "sequence-number",
+            Self::FrameOrder => "frame-order",
+            Self::IdotPosition => "idot-position",
+        }
+    }
+
+    /// Severity of problems with this code
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::Keyword | Self::IdotPosition => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl std::fmt::Display for Code {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A problem found in a file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub code: Code,
+
+    /// Type of the chunk the problem is in, if any
+    pub chunktype: Option<[u8; 4]>,
+
+    /// Offset of the start of the chunk, or of the problem in the file
+    pub offset: Option<u64>,
+
+    pub message: String,
+}
+
+impl Finding {
+    fn new(code: Code, chunkref: Option<&PngChunkRef>, message: String) -> Self {
+        Self {
+            severity: code.severity(),
+            code,
+            chunktype: chunkref.map(|c| c.chunktype),
+            offset: chunkref.map(|c| c.position),
+            message,
+        }
+    }
+
+    /// Finding for an error with a chunk type and offset
+    fn from_error(code: Code, e: &crate::error::Error) -> Self {
+        Self {
+            severity: code.severity(),
+            code,
+            chunktype: e.chunktype(),
+            offset: e.offset(),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}]", self.severity, self.code)?;
+        if let Some(chunktype) = self.chunktype {
+            write!(f, " {}", String::from_utf8_lossy(&chunktype))?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " at {}", offset)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Is this a legal keyword for a tEXt, zTXt, or iTXt chunk?
+///
+/// Keywords are 1-79 printable Latin-1 characters, with no leading, trailing, or consecutive
+/// spaces.
+pub fn is_valid_keyword(keyword: &str) -> bool {
+    let len = keyword.chars().count();
+    (1..=79).contains(&len)
+        && keyword
+            .chars()
+            .all(|c| matches!(c as u32, 32..=126 | 161..=255))
+        && !keyword.starts_with(' ')
+        && !keyword.ends_with(' ')
+        && !keyword.contains("  ")
+}
+
+/// Chunks that the checks need, collected while reading
+#[derive(Default)]
+struct Collected {
+    plte: Option<(PngChunkRef, usize)>,
+    trns: Option<(PngChunkRef, Trns)>,
+    hist: Option<(PngChunkRef, Hist)>,
+    sbit: Option<(PngChunkRef, Sbit)>,
+    actl: Option<(PngChunkRef, u32)>,
+    fctls: Vec<(PngChunkRef, Fctl)>,
+    idot: Option<(PngChunkRef, Vec<u32>)>,
+
+    /// fcTL and fdAT chunks and their sequence numbers, in file order
+    sequence: Vec<(PngChunkRef, u32)>,
+}
+
+/// Check a whole PNG or APNG file
+///
+/// Chunks are found with [recover_chunks()](PngReader::recover_chunks), so damaged files are
+/// checked as far as possible. Image data isn't decoded. Findings are returned in the order
+/// of the checks, not necessarily in file order.
+///
+/// I/O errors and exceeded [limits](PngReader::limits) end the check.
+pub fn validate<R>(reader: &mut PngReader<R>) -> Result<Vec<Finding>, std::io::Error>
+where
+    R: Read + Seek,
+{
+    let recovered = reader.recover_chunks()?;
+    let mut findings = Vec::new();
+
+    let mut damaged = Vec::new();
+    for diagnostic in &recovered.diagnostics {
+        match diagnostic {
+            recovery::Diagnostic::CrcMismatch {
+                chunktype, offset, ..
+            } => {
+                damaged.push(*offset);
+                findings.push(Finding {
+                    severity: Severity::Error,
+                    code: Code::CrcMismatch,
+                    chunktype: Some(*chunktype),
+                    offset: Some(*offset),
+                    message: diagnostic.to_string(),
+                });
+            }
+
+            recovery::Diagnostic::InvalidChunk(e) => {
+                findings.push(Finding::from_error(Code::InvalidChunk, e))
+            }
+
+            recovery::Diagnostic::SkippedBytes { offset, .. }
+            | recovery::Diagnostic::MissingIend { offset } => findings.push(Finding {
+                severity: Severity::Error,
+                code: Code::Damaged,
+                chunktype: None,
+                offset: Some(*offset),
+                message: diagnostic.to_string(),
+            }),
+
+            recovery::Diagnostic::TruncatedChunk {
+                chunktype, offset, ..
+            } => findings.push(Finding {
+                severity: Severity::Error,
+                code: Code::Damaged,
+                chunktype: Some(*chunktype),
+                offset: Some(*offset),
+                message: diagnostic.to_string(),
+            }),
+        }
+    }
+
+    // A missing IEND has already been reported, so OrderValidator::finish() isn't needed
+    let mut order = OrderValidator::new();
+    for chunkref in &recovered.chunks {
+        for e in order.check(chunkref) {
+            findings.push(Finding::from_error(Code::ChunkOrder, &e));
+        }
+    }
+
+    let mut collected = Collected::default();
+    for chunkref in &recovered.chunks {
+        if damaged.contains(&chunkref.position) {
+            continue;
+        }
+
+        match &chunkref.chunktype {
+            // Don't read image data into memory
+            b"IDAT" => continue,
+
+            b"fdAT" => {
+                match chunkref.read_fctl_fdat_sequence_number(&mut reader.stream) {
+                    Ok(sequence_number) => collected.sequence.push((*chunkref, sequence_number)),
+                    Err(e) => findings.push(io_finding(e, chunkref)?),
+                }
+                continue;
+            }
+
+            _ => (),
+        }
+
+        let chunk = match reader.read_chunk(chunkref) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                findings.push(io_finding(e, chunkref)?);
+                continue;
+            }
+        };
+
+        match chunk {
+            PngChunkData::Plte(plte) => collected.plte = Some((*chunkref, plte.0.len())),
+            PngChunkData::Trns(trns) => collected.trns = Some((*chunkref, *trns)),
+            PngChunkData::Hist(hist) => collected.hist = Some((*chunkref, *hist)),
+            PngChunkData::Sbit(sbit) => collected.sbit = Some((*chunkref, sbit)),
+            PngChunkData::Actl(actl) => collected.actl = Some((*chunkref, actl.num_frames)),
+
+            PngChunkData::Fctl(fctl) => {
+                collected.sequence.push((*chunkref, fctl.sequence_number));
+                collected.fctls.push((*chunkref, *fctl));
+            }
+
+            PngChunkData::Idot(idot) => {
+                collected.idot = Some((
+                    *chunkref,
+                    idot.0.iter().map(|segment| segment.idat_position).collect(),
+                ))
+            }
+
+            PngChunkData::Text(text) => check_keyword(&text.keyword, chunkref, &mut findings),
+            PngChunkData::Ztxt(ztxt) => check_keyword(&ztxt.keyword, chunkref, &mut findings),
+            PngChunkData::Itxt(itxt) => check_keyword(&itxt.keyword, chunkref, &mut findings),
+
+            _ => (),
+        }
+    }
+
+    let Some(ihdr) = reader.ihdr else {
+        // Already reported as an ordering violation
+        return Ok(findings);
+    };
+    let ihdr_ref = recovered
+        .chunks
+        .iter()
+        .find(|c| c.chunktype == *b"IHDR")
+        .copied();
+    let ihdr_ref = ihdr_ref.as_ref();
+
+    if (ihdr.width == 0) || (ihdr.height == 0) {
+        findings.push(Finding::new(
+            Code::ImageSize,
+            ihdr_ref,
+            format!("PNG: Image size is {}x{}", ihdr.width, ihdr.height),
+        ));
+    } else if (ihdr.width > MAX_DIMENSION) || (ihdr.height > MAX_DIMENSION) {
+        findings.push(Finding::new(
+            Code::ImageSize,
+            ihdr_ref,
+            format!(
+                "PNG: Image size ({}x{}) is larger than 2^31-1",
+                ihdr.width, ihdr.height
+            ),
+        ));
+    }
+
+    if !ihdr
+        .colour_type
+        .allowed_bit_depths()
+        .contains(&ihdr.bit_depth)
+    {
+        findings.push(Finding::new(
+            Code::BitDepth,
+            ihdr_ref,
+            format!(
+                "PNG: Invalid bit depth ({}) for colour type ({:?})",
+                ihdr.bit_depth, ihdr.colour_type
+            ),
+        ));
+    }
+
+    check_palette(&ihdr, ihdr_ref, &collected, &mut findings);
+    check_sbit(&ihdr, &collected, &mut findings);
+    check_animation(&ihdr, &recovered.chunks, &collected, &mut findings);
+    check_idot(&recovered.chunks, &collected, &mut findings);
+
+    Ok(findings)
+}
+
+/// Finding for an error from reading a chunk
+///
+/// Errors without an [Error](crate::error::Error) payload are plain I/O errors, and are
+/// returned.
+fn io_finding(e: std::io::Error, chunkref: &PngChunkRef) -> Result<Finding, std::io::Error> {
+    let e = crate::error::Error::in_chunk(e, chunkref);
+    match crate::error::Error::from_io_error(&e) {
+        Some(crate::error::Error::LimitExceeded { .. }) | None => Err(e),
+        Some(error) => Ok(Finding::from_error(Code::InvalidChunk, error)),
+    }
+}
+
+fn check_keyword(keyword: &str, chunkref: &PngChunkRef, findings: &mut Vec<Finding>) {
+    if !is_valid_keyword(keyword) {
+        findings.push(Finding::new(
+            Code::Keyword,
+            Some(chunkref),
+            format!("PNG: Invalid keyword {:?}", keyword),
+        ));
+    }
+}
+
+/// Check PLTE, and the tRNS and hIST chunks that depend on it
+fn check_palette(
+    ihdr: &crate::chunks::Ihdr,
+    ihdr_ref: Option<&PngChunkRef>,
+    collected: &Collected,
+    findings: &mut Vec<Finding>,
+) {
+    let palette_size = collected.plte.map(|(_, size)| size);
+
+    match (ihdr.colour_type, &collected.plte) {
+        (PngColourType::IndexedColour, None) => findings.push(Finding::new(
+            Code::MissingPalette,
+            ihdr_ref,
+            "PNG: Indexed colour image has no PLTE chunk".to_string(),
+        )),
+
+        (PngColourType::Greyscale | PngColourType::GreyscaleAlpha, Some((plte_ref, _))) => findings
+            .push(Finding::new(
+                Code::UnexpectedPalette,
+                Some(plte_ref),
+                format!(
+                    "PNG: PLTE chunk isn't allowed for colour type ({:?})",
+                    ihdr.colour_type
+                ),
+            )),
+
+        (colour_type, Some((plte_ref, size))) => {
+            let max = if colour_type == PngColourType::IndexedColour {
+                1_usize << ihdr.bit_depth.min(8)
+            } else {
+                256
+            };
+            if (*size == 0) || (*size > max) {
+                findings.push(Finding::new(
+                    Code::PaletteSize,
+                    Some(plte_ref),
+                    format!(
+                        "PNG: PLTE has {} entries, must be 1-{} for bit depth {}",
+                        size, max, ihdr.bit_depth
+                    ),
+                ));
+            }
+        }
+
+        _ => (),
+    }
+
+    if let Some((trns_ref, Trns::IndexedColour { values })) = &collected.trns
+        && let Some(palette_size) = palette_size
+        && (values.len() > palette_size)
+    {
+        findings.push(Finding::new(
+            Code::TrnsLength,
+            Some(trns_ref),
+            format!(
+                "PNG: tRNS has {} entries, more than the {} in PLTE",
+                values.len(),
+                palette_size
+            ),
+        ));
+    }
+
+    if let Some((hist_ref, hist)) = &collected.hist
+        && (Some(hist.0.len()) != palette_size)
+    {
+        findings.push(Finding::new(
+            Code::HistLength,
+            Some(hist_ref),
+            format!(
+                "PNG: hIST has {} entries, but PLTE has {}",
+                hist.0.len(),
+                palette_size.unwrap_or(0)
+            ),
+        ));
+    }
+}
+
+/// Check that sBIT values are between 1 and the sample depth
+fn check_sbit(ihdr: &crate::chunks::Ihdr, collected: &Collected, findings: &mut Vec<Finding>) {
+    let Some((sbit_ref, sbit)) = &collected.sbit else {
+        return;
+    };
+
+    let sample_depth = if ihdr.colour_type == PngColourType::IndexedColour {
+        8
+    } else {
+        ihdr.bit_depth
+    };
+    let values: &[u8] = match sbit {
+        Sbit::Greyscale { grey_bits } => &[*grey_bits],
+        Sbit::Colour {
+            red_bits,
+            green_bits,
+            blue_bits,
+        } => &[*red_bits, *green_bits, *blue_bits],
+        Sbit::GreyscaleAlpha {
+            grey_bits,
+            alpha_bits,
+        } => &[*grey_bits, *alpha_bits],
+        Sbit::TrueColourAlpha {
+            red_bits,
+            green_bits,
+            blue_bits,
+            alpha_bits,
+        } => &[*red_bits, *green_bits, *blue_bits, *alpha_bits],
+    };
+
+    if values.iter().any(|&v| (v == 0) || (v > sample_depth)) {
+        findings.push(Finding::new(
+            Code::SbitValue,
+            Some(sbit_ref),
+            format!("PNG: sBIT values ({:?}) must be 1-{}", values, sample_depth),
+        ));
+    }
+}
+
+/// Check acTL, fcTL, and fdAT chunks
+fn check_animation(
+    ihdr: &crate::chunks::Ihdr,
+    chunks: &[PngChunkRef],
+    collected: &Collected,
+    findings: &mut Vec<Finding>,
+) {
+    match collected.actl {
+        Some((actl_ref, num_frames)) if num_frames as usize != collected.fctls.len() => findings
+            .push(Finding::new(
+                Code::FrameCount,
+                Some(&actl_ref),
+                format!(
+                    "APNG: acTL has {} frames, but there are {} fcTL chunks",
+                    num_frames,
+                    collected.fctls.len()
+                ),
+            )),
+
+        None if !collected.fctls.is_empty() => findings.push(Finding::new(
+            Code::FrameCount,
+            Some(&collected.fctls[0].0),
+            "APNG: fcTL chunks without an acTL chunk".to_string(),
+        )),
+
+        _ => (),
+    }
+
+    let first_idat = chunks.iter().find(|c| c.chunktype == *b"IDAT");
+    for (fctl_ref, fctl) in &collected.fctls {
+        if (fctl.width == 0) || (fctl.height == 0) {
+            findings.push(Finding::new(
+                Code::FrameBounds,
+                Some(fctl_ref),
+                format!("APNG: Frame size is {}x{}", fctl.width, fctl.height),
+            ));
+        } else if (fctl.x_offset as u64 + fctl.width as u64 > ihdr.width as u64)
+            || (fctl.y_offset as u64 + fctl.height as u64 > ihdr.height as u64)
+        {
+            findings.push(Finding::new(
+                Code::FrameBounds,
+                Some(fctl_ref),
+                format!(
+                    "APNG: Frame ({}x{} at {},{}) is outside the image ({}x{})",
+                    fctl.width, fctl.height, fctl.x_offset, fctl.y_offset, ihdr.width, ihdr.height
+                ),
+            ));
+        }
+
+        // The fcTL before IDAT makes the default image the first frame, which must fill the image
+        if first_idat.is_some_and(|idat| fctl_ref.position < idat.position)
+            && ((fctl.x_offset, fctl.y_offset, fctl.width, fctl.height)
+                != (0, 0, ihdr.width, ihdr.height))
+        {
+            findings.push(Finding::new(
+                Code::FrameBounds,
+                Some(fctl_ref),
+                "APNG: First frame must be the size of the image".to_string(),
+            ));
+        }
+    }
+
+    // Only the default image's fcTL can be before IDAT, and each other frame has fdAT data
+    if let Some(first_idat) = first_idat {
+        let mut before_idat = 0;
+        for (i, chunkref) in chunks.iter().enumerate() {
+            if chunkref.chunktype != *b"fcTL" {
+                continue;
+            }
+
+            if chunkref.position < first_idat.position {
+                before_idat += 1;
+                if before_idat > 1 {
+                    findings.push(Finding::new(
+                        Code::FrameOrder,
+                        Some(chunkref),
+                        "APNG: Only one fcTL chunk is allowed before IDAT".to_string(),
+                    ));
+                }
+            } else if chunks[i + 1..]
+                .iter()
+                .find(|c| matches!(&c.chunktype, b"fcTL" | b"fdAT" | b"IEND"))
+                .is_none_or(|c| c.chunktype != *b"fdAT")
+            {
+                findings.push(Finding::new(
+                    Code::FrameOrder,
+                    Some(chunkref),
+                    "APNG: fcTL chunk isn't followed by fdAT chunks".to_string(),
+                ));
+            }
+        }
+    }
+
+    let mut expected = 0_u32;
+    for (chunkref, sequence_number) in &collected.sequence {
+        if *sequence_number != expected {
+            findings.push(Finding::new(
+                Code::SequenceNumber,
+                Some(chunkref),
+                format!(
+                    "APNG: Sequence number is {}, expected {}",
+                    sequence_number, expected
+                ),
+            ));
+        }
+        expected = sequence_number.wrapping_add(1);
+    }
+}
+
+/// Check that iDOT segments point at IDAT chunks
+///
+/// Positions in iDOT are relative to the start of the iDOT chunk.
+fn check_idot(chunks: &[PngChunkRef], collected: &Collected, findings: &mut Vec<Finding>) {
+    let Some((idot_ref, positions)) = &collected.idot else {
+        return;
+    };
+
+    for &position in positions {
+        let absolute = idot_ref.position + position as u64;
+        if !chunks
+            .iter()
+            .any(|c| (c.chunktype == *b"IDAT") && (c.position == absolute))
+        {
+            findings.push(Finding::new(
+                Code::IdotPosition,
+                Some(idot_ref),
+                format!(
+                    "PNG: iDOT segment points at {}, which isn't the start of an IDAT chunk",
+                    absolute
+                ),
+            ));
+        }
+    }
+}
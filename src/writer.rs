@@ -0,0 +1,357 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+/*! PNG/APNG writer
+ *
+ * Chunks can be added in any order. Each one is held back until it can be written in the zone
+ * the [ordering rules](crate::ordering::RULES) require, and the image data is split into IDAT
+ * chunks as it is written.
+ */
+
+use std::io::Write;
+
+use crate::chunks::*;
+use crate::countingwriter::CountingWriter;
+use crate::ordering::{OrderValidator, Placement, find_rule};
+use crate::recovery::MAX_CHUNK_LENGTH;
+
+/// Default maximum length of IDAT chunks, the same as libpng's
+pub const DEFAULT_IDAT_SIZE: u32 = 8192;
+
+/// A PNG/APNG file writer
+///
+/// Nothing is written until the image data starts, or [finish()](Self::finish) is called. Until
+/// then, chunks are sorted into zones:
+/// - Chunks that must be before PLTE.
+/// - PLTE.
+/// - Chunks that must be after PLTE or before the first IDAT, and chunks that can be anywhere.
+///
+/// Once the image data has started, only chunks that can be after IDAT may be added. They are
+/// written by finish(), followed by IEND.
+///
+/// The stream doesn't need to be seekable. Positions in [chunk references](Self::chunks) are
+/// counted from the start of the signature.
+#[derive(Debug)]
+pub struct PngWriter<W> {
+    stream: CountingWriter<W>,
+
+    /// Maximum length of each IDAT chunk
+    pub idat_size: u32,
+
+    ihdr: Ihdr,
+    before_plte: Vec<PngChunkData>,
+    plte: Option<PngChunkData>,
+    before_idat: Vec<PngChunkData>,
+    after_idat: Vec<PngChunkData>,
+    image_data: Vec<u8>,
+    started: bool,
+    order: OrderValidator,
+    chunks: Vec<PngChunkRef>,
+}
+
+impl<W> PngWriter<W>
+where
+    W: Write,
+{
+    /// Constructor
+    ///
+    /// Returns an error if the bit depth isn't allowed for the colour type.
+    pub fn new(stream: W, ihdr: Ihdr) -> Result<Self, std::io::Error> {
+        check_ihdr(&ihdr)?;
+
+        Ok(Self {
+            stream: CountingWriter::new(stream),
+            idat_size: DEFAULT_IDAT_SIZE,
+            ihdr,
+            before_plte: Vec::new(),
+            plte: None,
+            before_idat: Vec::new(),
+            after_idat: Vec::new(),
+            image_data: Vec::new(),
+            started: false,
+            order: OrderValidator::new(),
+            chunks: Vec::new(),
+        })
+    }
+
+    /// Get a reference to the stream
+    pub fn get_ref(&self) -> &W {
+        self.stream.get_ref()
+    }
+
+    /// The IHDR chunk that will be, or has been, written
+    pub fn ihdr(&self) -> &Ihdr {
+        &self.ihdr
+    }
+
+    /// References to the chunks written so far
+    pub fn chunks(&self) -> &[PngChunkRef] {
+        &self.chunks
+    }
+
+    /// Add a chunk, to be written in the right place
+    ///
+    /// - An IHDR chunk replaces the one passed to [new()](Self::new).
+    /// - The data of IDAT chunks is passed to [write_image_data()](Self::write_image_data), to be
+    ///   split again.
+    /// - IEND chunks are ignored, since finish() writes one.
+    ///
+    /// Chunk types without an [ordering rule](crate::ordering::RULES), and ones that may be
+    /// anywhere, stay on the same side of the image data as they were added. The exception is
+    /// fcTL: only the first frame's, with sequence number 0, can go before the image data. Any
+    /// other fcTL chunk is written after it, next to the fdAT chunks added after it.
+    ///
+    /// Adding a chunk that must be before IDAT once the image data has started is an error, as is
+    /// adding one that would break the [ordering rules](crate::ordering::RULES) some other way,
+    /// e.g. a second gAMA chunk. Such chunks are rejected without anything being written, so the
+    /// writer can still be used.
+    pub fn add_chunk(&mut self, chunk: PngChunkData) -> Result<(), std::io::Error> {
+        let chunktype = chunk.chunktype();
+        let placement = match &chunk {
+            PngChunkData::Fctl(fctl)
+                if (fctl.sequence_number > 0)
+                    || self.before_idat.iter().any(|c| c.chunktype() == *b"fcTL") =>
+            {
+                Placement::AfterIdat
+            }
+
+            _ => find_rule(chunktype).map_or(Placement::Anywhere, |rule| rule.placement),
+        };
+
+        match chunk {
+            PngChunkData::Ihdr(ihdr) => {
+                if self.started {
+                    return Err(too_late(chunktype));
+                }
+                check_ihdr(&ihdr)?;
+                self.ihdr = ihdr;
+            }
+
+            PngChunkData::Idat(idat) => self.write_image_data(&idat.0)?,
+
+            PngChunkData::Iend => (),
+
+            PngChunkData::Plte(_) => {
+                if self.started {
+                    return Err(too_late(chunktype));
+                }
+                if self.plte.is_some() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "PNG: Only one PLTE chunk is allowed",
+                    ));
+                }
+                self.plte = Some(chunk);
+            }
+
+            _ => {
+                self.zone(chunktype, placement)?.push(chunk);
+                if let Err(e) = self.check_order(true, false) {
+                    self.zone(chunktype, placement)?.pop();
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write image data, i.e. the zlib stream of filtered scanlines
+    ///
+    /// The first call writes the signature and all of the chunks that go before IDAT. Data is
+    /// written in IDAT chunks of [idat_size](Self::idat_size) bytes as it fills them.
+    pub fn write_image_data(&mut self, data: &[u8]) -> Result<(), std::io::Error> {
+        if (self.idat_size == 0) || (self.idat_size > MAX_CHUNK_LENGTH) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("PNG: Invalid IDAT size ({})", self.idat_size),
+            ));
+        }
+
+        self.start()?;
+        self.image_data.extend_from_slice(data);
+
+        let idat_size = self.idat_size as usize;
+        if self.image_data.len() >= idat_size {
+            let remainder = self.image_data.len() % idat_size;
+            let full = self
+                .image_data
+                .drain(..self.image_data.len() - remainder)
+                .collect::<Vec<u8>>();
+            for idat in full.chunks(idat_size) {
+                self.write_chunk(&PngChunkData::Idat(Box::new(Idat(idat.to_vec()))))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the rest of the image data and chunks, and IEND
+    ///
+    /// Returns the stream.
+    ///
+    /// Nothing is written if the chunks would be out of order, e.g. without any image data.
+    pub fn finish(mut self) -> Result<W, std::io::Error> {
+        self.check_order(!self.image_data.is_empty(), true)?;
+        self.start()?;
+
+        if !self.image_data.is_empty() {
+            let idat = std::mem::take(&mut self.image_data);
+            self.write_chunk(&PngChunkData::Idat(Box::new(Idat(idat))))?;
+        }
+
+        for chunk in std::mem::take(&mut self.after_idat) {
+            self.write_chunk(&chunk)?;
+        }
+        self.write_chunk(&PngChunkData::Iend)?;
+
+        self.stream.flush()?;
+        Ok(self.stream.into_inner())
+    }
+
+    /// Write the signature and the chunks that go before IDAT, if they haven't been yet
+    fn start(&mut self) -> Result<(), std::io::Error> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+
+        self.stream
+            .write_all(&[0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a])?;
+        self.write_chunk(&PngChunkData::Ihdr(self.ihdr))?;
+        for chunk in std::mem::take(&mut self.before_plte) {
+            self.write_chunk(&chunk)?;
+        }
+        if let Some(plte) = self.plte.take() {
+            self.write_chunk(&plte)?;
+        }
+        for chunk in std::mem::take(&mut self.before_idat) {
+            self.write_chunk(&chunk)?;
+        }
+
+        Ok(())
+    }
+
+    /// The zone a chunk is held in until it's written
+    fn zone(
+        &mut self,
+        chunktype: [u8; 4],
+        placement: Placement,
+    ) -> Result<&mut Vec<PngChunkData>, std::io::Error> {
+        match placement {
+            Placement::BeforePlte if !self.started => Ok(&mut self.before_plte),
+
+            Placement::AfterPlte | Placement::BeforeIdat | Placement::Anywhere if !self.started => {
+                Ok(&mut self.before_idat)
+            }
+
+            Placement::AfterIdat | Placement::Anywhere => Ok(&mut self.after_idat),
+
+            _ => Err(too_late(chunktype)),
+        }
+    }
+
+    /// Check the order of the chunks that are still to be written, without writing any
+    ///
+    /// `image_data`: Whether an IDAT chunk is to be written before the after-IDAT chunks.
+    /// `iend`: Whether to check IEND as well.
+    ///
+    /// Positions in errors assume the image data is in a single IDAT chunk.
+    fn check_order(&self, image_data: bool, iend: bool) -> Result<(), std::io::Error> {
+        let ihdr = PngChunkData::Ihdr(self.ihdr);
+        let idat = PngChunkData::Idat(Box::new(Idat(Vec::new())));
+        let before_idat = (!self.started).then(|| {
+            std::iter::once(&ihdr)
+                .chain(&self.before_plte)
+                .chain(&self.plte)
+                .chain(&self.before_idat)
+        });
+        let chunks = before_idat
+            .into_iter()
+            .flatten()
+            .chain(image_data.then_some(&idat))
+            .chain(&self.after_idat)
+            .chain(iend.then_some(&PngChunkData::Iend));
+
+        let mut order = self.order.clone();
+        let mut position = self.stream.position() + if self.started { 0 } else { 8 };
+        for chunk in chunks {
+            let length = if chunk.chunktype() == *b"IDAT" {
+                self.image_data.len() as u32
+            } else {
+                chunk.length()
+            };
+            let chunkref = PngChunkRef {
+                position,
+                length,
+                chunktype: chunk.chunktype(),
+            };
+            if let Some(e) = order.check(&chunkref).into_iter().next() {
+                return Err(e.into());
+            }
+            position += 4 + 4 + length as u64 + 4;
+        }
+
+        Ok(())
+    }
+
+    /// Write a chunk, if it's allowed where it is
+    fn write_chunk(&mut self, chunk: &PngChunkData) -> Result<(), std::io::Error> {
+        let chunkref = PngChunkRef {
+            position: self.stream.position(),
+            length: chunk.length(),
+            chunktype: chunk.chunktype(),
+        };
+        if let Some(e) = self.order.check(&chunkref).into_iter().next() {
+            return Err(e.into());
+        }
+
+        self.chunks.push(self.stream.write_chunk(chunk)?);
+
+        Ok(())
+    }
+}
+
+/// Check that the bit depth is allowed for the colour type
+fn check_ihdr(ihdr: &Ihdr) -> Result<(), std::io::Error> {
+    if !ihdr
+        .colour_type
+        .allowed_bit_depths()
+        .contains(&ihdr.bit_depth)
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "PNG: Invalid bit depth ({}) for colour type ({:?})",
+                ihdr.bit_depth, ihdr.colour_type
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+fn too_late(chunktype: [u8; 4]) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!(
+            "PNG: {} chunk must be added before the image data",
+            String::from_utf8_lossy(&chunktype)
+        ),
+    )
+}
@@ -0,0 +1,335 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Adam7 pass geometry and deinterlacing with the decode feature
+
+#![cfg(feature = "decode")]
+
+mod common;
+
+use std::io::{Cursor, ErrorKind};
+
+use png_container::adam7::*;
+use png_container::chunks::*;
+use png_container::decode::*;
+use png_container::types::*;
+
+fn ihdr(
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    colour_type: PngColourType,
+    interlace_method: PngInterlaceMethod,
+) -> Ihdr {
+    Ihdr::new(width, height, bit_depth, colour_type, interlace_method)
+}
+
+/// Width and height of each pass
+fn pass_sizes(adam7: &Adam7) -> Vec<(u32, u32)> {
+    adam7.passes.iter().map(|p| (p.width, p.height)).collect()
+}
+
+/// Bits of a pixel in a packed row, as an integer
+fn get_pixel(row: &[u8], x: usize, bits: usize) -> u64 {
+    (0..bits).fold(0, |value, i| {
+        let bit = x * bits + i;
+        (value << 1) | ((row[bit / 8] >> (7 - bit % 8)) & 1) as u64
+    })
+}
+
+fn set_pixel(row: &mut [u8], x: usize, bits: usize, value: u64) {
+    for i in 0..bits {
+        let bit = x * bits + i;
+        if (value >> (bits - 1 - i)) & 1 != 0 {
+            row[bit / 8] |= 0x80 >> (bit % 8);
+        }
+    }
+}
+
+/// An image with a different value in each pixel, in packed rows
+fn test_image(adam7: &Adam7) -> Vec<Vec<u8>> {
+    let bits = adam7.pixel_bits as usize;
+    (0..adam7.height as usize)
+        .map(|y| {
+            let mut row = vec![0; adam7.line_size()];
+            for x in 0..adam7.width as usize {
+                let value = ((y * 31 + x * 7 + 3) as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+                let mask = if bits == 64 { !0 } else { (1 << bits) - 1 };
+                set_pixel(&mut row, x, bits, value & mask);
+            }
+            row
+        })
+        .collect()
+}
+
+/// Split an image into the rows of each pass
+fn interlace(adam7: &Adam7, image: &[Vec<u8>]) -> Vec<Vec<Vec<u8>>> {
+    let bits = adam7.pixel_bits as usize;
+    adam7
+        .passes
+        .iter()
+        .map(|pass| {
+            (0..pass.height)
+                .map(|py| {
+                    let source = &image[(pass.y_start + py * pass.y_step) as usize];
+                    let mut row = vec![0; pass.line_size];
+                    for px in 0..pass.width {
+                        let x = (pass.x_start + px * pass.x_step) as usize;
+                        set_pixel(&mut row, px as usize, bits, get_pixel(source, x, bits));
+                    }
+                    row
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn pass_geometry() {
+    let grey = |width, height| {
+        Adam7::new(&ihdr(
+            width,
+            height,
+            8,
+            PngColourType::Greyscale,
+            PngInterlaceMethod::Adam7,
+        ))
+    };
+
+    let one = grey(1, 1);
+    assert_eq!(
+        pass_sizes(&one),
+        [(1, 1), (0, 1), (1, 0), (0, 1), (1, 0), (0, 1), (1, 0)]
+    );
+    assert_eq!(
+        one.passes.iter().map(|p| p.is_empty()).collect::<Vec<_>>(),
+        [false, true, true, true, true, true, true]
+    );
+    assert_eq!(one.data_size(), 1);
+
+    let three = grey(3, 3);
+    assert_eq!(
+        pass_sizes(&three),
+        [(1, 1), (0, 1), (1, 0), (1, 1), (2, 1), (1, 2), (3, 1)]
+    );
+    assert_eq!(three.data_size(), 9);
+
+    let eight = grey(8, 8);
+    assert_eq!(
+        pass_sizes(&eight),
+        [(1, 1), (1, 1), (2, 1), (2, 2), (4, 2), (4, 4), (8, 4)]
+    );
+    assert_eq!(eight.data_size(), 64);
+
+    // Widths less than 8 leave the later columns of each pass empty
+    for width in 1..8 {
+        let adam7 = grey(width, 1);
+        let pixels: u32 = adam7.passes.iter().map(|p| p.width * p.height).sum();
+        assert_eq!(pixels, width);
+    }
+    assert_eq!(
+        pass_sizes(&grey(5, 1)),
+        [(1, 1), (1, 1), (2, 0), (1, 1), (3, 0), (2, 1), (5, 0)]
+    );
+
+    // Sub-byte pixels are packed into each pass's rows
+    let packed = Adam7::new(&ihdr(
+        13,
+        2,
+        1,
+        PngColourType::Greyscale,
+        PngInterlaceMethod::Adam7,
+    ));
+    assert_eq!(
+        packed
+            .passes
+            .iter()
+            .map(|p| p.line_size)
+            .collect::<Vec<_>>(),
+        [1, 1, 1, 1, 1, 1, 2]
+    );
+    assert_eq!(packed.line_size(), 2);
+    assert_eq!(packed.image_size(), 4);
+}
+
+#[test]
+fn deinterlace_all_passes() {
+    for (bit_depth, colour_type) in [
+        (1, PngColourType::Greyscale),
+        (2, PngColourType::Greyscale),
+        (4, PngColourType::IndexedColour),
+        (8, PngColourType::Greyscale),
+        (8, PngColourType::TrueColour),
+        (16, PngColourType::TrueColourAlpha),
+    ] {
+        for (width, height) in [(1, 1), (3, 3), (5, 2), (8, 8), (13, 11)] {
+            let adam7 = Adam7::new(&ihdr(
+                width,
+                height,
+                bit_depth,
+                colour_type,
+                PngInterlaceMethod::Adam7,
+            ));
+            let image = test_image(&adam7);
+            let passes = interlace(&adam7, &image);
+
+            let data = passes
+                .iter()
+                .flatten()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>();
+            assert_eq!(data.len(), adam7.data_size());
+            assert_eq!(adam7.deinterlace(&data).unwrap(), image.concat());
+
+            // Row by row gives the same result
+            let mut rows = vec![0; adam7.image_size()];
+            for (pass, pass_rows) in adam7.passes.iter().zip(&passes) {
+                for (y, row) in pass_rows.iter().enumerate() {
+                    adam7
+                        .deinterlace_row(pass, y as u32, row, &mut rows)
+                        .unwrap();
+                }
+            }
+            assert_eq!(rows, image.concat());
+        }
+    }
+}
+
+#[test]
+fn preview() {
+    let adam7 = Adam7::new(&ihdr(
+        9,
+        9,
+        8,
+        PngColourType::Greyscale,
+        PngInterlaceMethod::Adam7,
+    ));
+    let image = test_image(&adam7);
+    let passes = interlace(&adam7, &image);
+
+    // After the first pass, each 8x8 block is filled with its top-left pixel
+    let mut preview = vec![0; adam7.image_size()];
+    adam7
+        .deinterlace_pass(&adam7.passes[0], &passes[0].concat(), &mut preview)
+        .unwrap();
+    adam7.fill_preview(&adam7.passes[0], &mut preview).unwrap();
+    for y in 0..9 {
+        for x in 0..9 {
+            assert_eq!(preview[y * 9 + x], image[y - y % 8][x - x % 8]);
+        }
+    }
+
+    // After the last pass, the preview is the image
+    for (pass, pass_rows) in adam7.passes.iter().zip(&passes).skip(1) {
+        adam7
+            .deinterlace_pass(pass, &pass_rows.concat(), &mut preview)
+            .unwrap();
+        adam7.fill_preview(pass, &mut preview).unwrap();
+    }
+    assert_eq!(preview, image.concat());
+}
+
+#[test]
+fn short_buffers() {
+    let adam7 = Adam7::new(&ihdr(
+        8,
+        8,
+        8,
+        PngColourType::TrueColour,
+        PngInterlaceMethod::Adam7,
+    ));
+    let pass = adam7.passes[6];
+    let row = vec![0; pass.line_size];
+    let mut image = vec![0; adam7.image_size()];
+
+    let invalid_input = |result: Result<(), std::io::Error>| {
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidInput);
+    };
+    invalid_input(adam7.deinterlace_row(&pass, 0, &row[1..], &mut image));
+    invalid_input(adam7.deinterlace_row(&pass, pass.height, &row, &mut image));
+    invalid_input(adam7.deinterlace_row(&pass, 0, &row, &mut image[1..]));
+    invalid_input(adam7.fill_preview(&pass, &mut image[..10]));
+
+    // A pass of a different image
+    let other = Adam7::new(&ihdr(
+        9,
+        8,
+        8,
+        PngColourType::TrueColour,
+        PngInterlaceMethod::Adam7,
+    ));
+    invalid_input(adam7.deinterlace_row(&other.passes[6], 0, &row, &mut image));
+
+    assert!(adam7.deinterlace_row(&pass, 0, &row, &mut image).is_ok());
+    assert_eq!(
+        adam7.deinterlace(&[0; 10]).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+/// Interlaced and non-interlaced encodings of the same image decode to the same pixels
+#[test]
+fn interlaced_matches_non_interlaced() {
+    for (bit_depth, colour_type) in [
+        (1, PngColourType::Greyscale),
+        (4, PngColourType::Greyscale),
+        (8, PngColourType::IndexedColour),
+        (8, PngColourType::TrueColour),
+        (16, PngColourType::GreyscaleAlpha),
+        (16, PngColourType::TrueColourAlpha),
+    ] {
+        for (width, height) in [(1, 1), (3, 3), (7, 5), (16, 9)] {
+            let plain = ihdr(
+                width,
+                height,
+                bit_depth,
+                colour_type,
+                PngInterlaceMethod::None,
+            );
+            let interlaced = ihdr(
+                width,
+                height,
+                bit_depth,
+                colour_type,
+                PngInterlaceMethod::Adam7,
+            );
+            let bpp = filter_bytes_per_pixel(&plain);
+            let adam7 = Adam7::new(&interlaced);
+            let image = test_image(&adam7);
+
+            let data = common::zlib(&common::filter_rows(&image, bpp, 0));
+            let mut decoder = ScanlineDecoder::new(Cursor::new(data), &plain).unwrap();
+            let expected = decoder.read_all().unwrap();
+            assert_eq!(expected, image.concat());
+
+            // Empty passes are left out, and each pass is filtered separately
+            let data = adam7
+                .passes
+                .iter()
+                .zip(interlace(&adam7, &image))
+                .filter(|(pass, _)| !pass.is_empty())
+                .enumerate()
+                .flat_map(|(i, (_, rows))| common::filter_rows(&rows, bpp, i as u8))
+                .collect::<Vec<_>>();
+            let mut decoder =
+                ScanlineDecoder::new(Cursor::new(common::zlib(&data)), &interlaced).unwrap();
+            assert_eq!(decoder.read_all().unwrap(), expected);
+        }
+    }
+}
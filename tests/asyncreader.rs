@@ -0,0 +1,268 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Async readers with the tokio feature
+
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use std::io::{Cursor, ErrorKind};
+
+use png_container::asyncreader::{AsyncJngReader, AsyncPngReader};
+use png_container::chunks::*;
+use png_container::error::Error;
+use png_container::jngreader::JngReader;
+use png_container::limits::*;
+use png_container::reader::PngReader;
+use png_container::types::*;
+
+#[tokio::test]
+async fn png_chunks_match_reader() {
+    let bytes = common::apng();
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let expected = reader.scan_all_chunks().unwrap();
+
+    let mut async_reader = AsyncPngReader::from_stream(Cursor::new(&bytes))
+        .await
+        .unwrap();
+    let header = async_reader.scan_header_chunks().await.unwrap();
+    assert_eq!(
+        header.iter().map(|c| c.chunktype).collect::<Vec<_>>(),
+        [*b"IHDR", *b"acTL", *b"fcTL"]
+    );
+    assert_eq!((async_reader.width, async_reader.height), (2, 2));
+    assert_eq!(async_reader.bit_depth, 8);
+    assert_eq!(async_reader.colour_type, PngColourType::Greyscale);
+    assert_eq!(async_reader.filetype, PngFileType::Apng);
+
+    async_reader.reset_next_chunk_position();
+    let chunks = async_reader.scan_all_chunks().await.unwrap();
+    assert_eq!(chunks.len(), expected.len());
+    for (chunkref, expected) in chunks.iter().zip(&expected) {
+        assert_eq!(
+            (chunkref.position, chunkref.length, chunkref.chunktype),
+            (expected.position, expected.length, expected.chunktype)
+        );
+        assert_eq!(
+            format!("{:?}", async_reader.read_chunk(chunkref).await.unwrap()),
+            format!("{:?}", reader.read_chunk(expected).unwrap())
+        );
+    }
+}
+
+#[tokio::test]
+async fn apng_frames_match_reader() {
+    let bytes = common::apng();
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    reader.scan_header_chunks().unwrap();
+    reader.reset_next_chunk_position();
+    let expected = reader.apng_scan_frames().unwrap();
+
+    let mut async_reader = AsyncPngReader::from_stream(Cursor::new(&bytes))
+        .await
+        .unwrap();
+    async_reader.scan_header_chunks().await.unwrap();
+    async_reader.reset_next_chunk_position();
+    let frames = async_reader.apng_scan_frames().await.unwrap();
+
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames.len(), expected.len());
+    for (frame, expected) in frames.iter().zip(&expected) {
+        assert_eq!(frame.fctl.sequence_number, expected.fctl.sequence_number);
+        assert_eq!(
+            frame.dats.iter().map(|c| c.position).collect::<Vec<_>>(),
+            expected.dats.iter().map(|c| c.position).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[tokio::test]
+async fn short_sequence_number() {
+    // An fdAT chunk too short for its sequence number, followed by another chunk
+    let bytes = common::png(
+        1,
+        1,
+        0,
+        8,
+        &[
+            common::fctl(0, 1, 1),
+            common::chunk(b"IDAT", &common::zlib(&[0, 0])),
+            common::chunk(b"fdAT", &[]),
+            common::chunk(b"tEXt", b"Title\0Short"),
+        ],
+    );
+
+    let mut async_reader = AsyncPngReader::from_stream(Cursor::new(&bytes))
+        .await
+        .unwrap();
+    let e = async_reader.apng_scan_frames().await.unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn bad_signature() {
+    let bytes = common::jng();
+    let e = AsyncPngReader::from_stream(Cursor::new(&bytes))
+        .await
+        .unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn jng_chunks_match_reader() {
+    let bytes = common::jng();
+    let mut reader = JngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let expected = reader.scan_all_chunks().unwrap();
+
+    let mut async_reader = AsyncJngReader::from_stream(Cursor::new(&bytes))
+        .await
+        .unwrap();
+    let header = async_reader.scan_header_chunks().await.unwrap();
+    assert_eq!(
+        header.iter().map(|c| c.chunktype).collect::<Vec<_>>(),
+        [*b"JHDR", *b"gAMA", *b"tEXt"]
+    );
+    assert_eq!((async_reader.width, async_reader.height), (3, 2));
+    assert_eq!(async_reader.colour_type, JngColourType::Colour);
+
+    async_reader.reset_next_chunk_position();
+    let chunks = async_reader.scan_all_chunks().await.unwrap();
+    assert_eq!(
+        async_reader.iend.position,
+        expected.last().unwrap().position
+    );
+    assert_eq!(chunks.len(), expected.len());
+    for (chunkref, expected) in chunks.iter().zip(&expected) {
+        assert_eq!(chunkref.chunktype, expected.chunktype);
+        let chunk = async_reader.read_chunk(chunkref).await.unwrap();
+        assert_eq!(
+            format!("{:?}", chunk),
+            format!("{:?}", reader.read_chunk(expected).unwrap())
+        );
+        if let PngChunkData::Jdat(jdat) = chunk {
+            assert!(!jdat.0.is_empty());
+        }
+    }
+}
+
+fn limit_kind(e: &std::io::Error) -> Option<(LimitKind, Option<[u8; 4]>)> {
+    assert_eq!(e.kind(), ErrorKind::QuotaExceeded, "{}", e);
+    match Error::from_io_error(e)? {
+        Error::LimitExceeded {
+            chunktype, limit, ..
+        } => Some((limit.kind, *chunktype)),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn limits() {
+    let bytes = common::apng();
+    let reader = || AsyncPngReader::from_stream(Cursor::new(&bytes));
+
+    // Header chunks are buffered like any other, and IHDR is 13 bytes long
+    let mut async_reader = reader().await.unwrap();
+    async_reader.limits.max_chunk_length = 12;
+    let e = async_reader.scan_header_chunks().await.unwrap_err();
+    assert_eq!(
+        limit_kind(&e),
+        Some((LimitKind::ChunkLength, Some(*b"IHDR")))
+    );
+
+    let mut async_reader = reader().await.unwrap();
+    async_reader.limits.max_chunks = 9;
+    let e = async_reader.scan_all_chunks().await.unwrap_err();
+    assert_eq!(
+        limit_kind(&e),
+        Some((LimitKind::ChunkCount, Some(*b"IEND")))
+    );
+
+    // Scanning again doesn't count the same chunks twice
+    let mut async_reader = reader().await.unwrap();
+    async_reader.limits.max_chunks = 10;
+    async_reader.scan_all_chunks().await.unwrap();
+    async_reader.reset_next_chunk_position();
+    async_reader.scan_all_chunks().await.unwrap();
+
+    let mut async_reader = reader().await.unwrap();
+    async_reader.limits.max_image_size = 3;
+    let e = async_reader.scan_all_chunks().await.unwrap_err();
+    assert_eq!(limit_kind(&e), Some((LimitKind::ImageSize, Some(*b"IHDR"))));
+
+    let mut async_reader = reader().await.unwrap();
+    let chunks = async_reader.scan_all_chunks().await.unwrap();
+    let text = chunks.iter().find(|c| c.chunktype == *b"tEXt").unwrap();
+    let chunk = async_reader.read_chunk(text).await.unwrap();
+    assert_eq!(
+        async_reader.text_string(&chunk).unwrap().as_deref(),
+        Some("Frames")
+    );
+    async_reader.limits.max_chunk_length = text.length - 1;
+    let e = async_reader.read_chunk(text).await.unwrap_err();
+    assert_eq!(
+        limit_kind(&e),
+        Some((LimitKind::ChunkLength, Some(*b"tEXt")))
+    );
+
+    let jng = common::jng();
+    let jng_reader = || AsyncJngReader::from_stream(Cursor::new(&jng));
+
+    let mut async_reader = jng_reader().await.unwrap();
+    async_reader.limits.max_chunks = 4;
+    let e = async_reader.scan_all_chunks().await.unwrap_err();
+    assert_eq!(
+        limit_kind(&e),
+        Some((LimitKind::ChunkCount, Some(*b"JDAT")))
+    );
+
+    let mut async_reader = jng_reader().await.unwrap();
+    async_reader.limits.max_image_size = 17;
+    let e = async_reader.scan_all_chunks().await.unwrap_err();
+    assert_eq!(limit_kind(&e), Some((LimitKind::ImageSize, Some(*b"JHDR"))));
+
+    let mut async_reader = jng_reader().await.unwrap();
+    let chunks = async_reader.scan_all_chunks().await.unwrap();
+    async_reader.limits.max_chunk_length = 4;
+    let jdat = chunks.iter().find(|c| c.chunktype == *b"JDAT").unwrap();
+    let e = async_reader.read_chunk(jdat).await.unwrap_err();
+    assert_eq!(
+        limit_kind(&e),
+        Some((LimitKind::ChunkLength, Some(*b"JDAT")))
+    );
+}
+
+/// A chunk claiming to be huge is rejected before its buffer is allocated
+#[tokio::test]
+async fn huge_chunk_length() {
+    let mut bytes = common::png(1, 1, 0, 8, &[]);
+    bytes.truncate(bytes.len() - 12);
+    bytes.extend_from_slice(&0x7fff_ffff_u32.to_be_bytes());
+    bytes.extend_from_slice(b"prIv");
+
+    let mut async_reader = AsyncPngReader::from_stream(Cursor::new(&bytes))
+        .await
+        .unwrap();
+    async_reader.scan_next_chunk().await.unwrap();
+    let huge = async_reader.scan_next_chunk().await.unwrap();
+    let e = async_reader.read_chunk(&huge).await.unwrap_err();
+    assert_eq!(
+        limit_kind(&e),
+        Some((LimitKind::ChunkLength, Some(*b"prIv")))
+    );
+}
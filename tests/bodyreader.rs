@@ -0,0 +1,227 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Streaming chunk data
+
+mod common;
+
+use std::io::{Cursor, ErrorKind, Read};
+
+use png_container::bodyreader::{ChunkBodyReader, ImageDataReader};
+use png_container::chunks::*;
+use png_container::error::Error;
+use png_container::reader::PngReader;
+
+use common::chunk;
+
+/// A file with a tEXt chunk of the given data, followed by a gAMA chunk
+fn text_file(text: &[u8]) -> Vec<u8> {
+    common::png(
+        1,
+        1,
+        0,
+        8,
+        &[
+            chunk(b"tEXt", text),
+            chunk(b"gAMA", &45455_u32.to_be_bytes()),
+        ],
+    )
+}
+
+#[test]
+fn clean_read() {
+    let text = b"Comment\0A fairly long comment, read a few bytes at a time";
+    let bytes = text_file(text);
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let chunks = reader.scan_all_chunks().unwrap();
+
+    let mut body = reader.chunk_body_reader(&chunks[1]).unwrap();
+    assert_eq!(body.chunkref().chunktype, *b"tEXt");
+    assert_eq!(body.remaining(), text.len() as u32);
+
+    let mut data = Vec::new();
+    let mut buf = [0_u8; 5];
+    loop {
+        let n = body.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+    assert_eq!(data, text);
+    assert_eq!(body.remaining(), 0);
+
+    // Further reads keep returning EOF
+    assert_eq!(body.read(&mut buf).unwrap(), 0);
+
+    // Once the CRC has been checked, the stream is at the next chunk
+    let mut stream = body.into_inner();
+    let next = PngChunkRef::from_stream(&mut stream).unwrap();
+    assert_eq!(next.position, chunks[2].position);
+    assert_eq!(next.chunktype, *b"gAMA");
+}
+
+#[test]
+fn crc_mismatch_at_eof() {
+    let text = b"Comment\0Corrupted";
+    let mut bytes = text_file(text);
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let chunks = reader.scan_all_chunks().unwrap();
+
+    // Flip a bit in the last byte of the text
+    let last = (chunks[1].position + 4 + 4) as usize + text.len() - 1;
+    bytes[last] ^= 0x01;
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let mut body = reader.chunk_body_reader(&chunks[1]).unwrap();
+
+    // All of the data can be read before the mismatch is noticed
+    let mut data = vec![0_u8; text.len()];
+    body.read_exact(&mut data).unwrap();
+    assert_eq!(data[..text.len() - 1], text[..text.len() - 1]);
+
+    let e = body.read(&mut [0_u8; 4]).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+    match Error::from_io_error(&e) {
+        Some(Error::CrcMismatch {
+            chunktype, offset, ..
+        }) => {
+            assert_eq!(*chunktype, *b"tEXt");
+            assert_eq!(*offset, chunks[1].position);
+        }
+        e => panic!("{:?}", e),
+    }
+
+    // read_to_end() surfaces it too
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let mut body = reader.chunk_body_reader(&chunks[1]).unwrap();
+    let e = body.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn partial_read_then_drop() {
+    let bytes = text_file(b"Comment\0Only partly read");
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let chunks = reader.scan_all_chunks().unwrap();
+
+    {
+        let mut body = reader.chunk_body_reader(&chunks[1]).unwrap();
+        let mut keyword = [0_u8; 7];
+        body.read_exact(&mut keyword).unwrap();
+        assert_eq!(&keyword, b"Comment");
+        assert_eq!(body.remaining(), 17);
+    }
+
+    // The reader seeks to each chunk, so it doesn't matter where the stream was left
+    assert_eq!(
+        reader.read_chunk(&chunks[2]).unwrap().gama_gamma(),
+        Some(0.45455)
+    );
+    let mut body = reader.chunk_body_reader(&chunks[1]).unwrap();
+    let mut data = Vec::new();
+    body.read_to_end(&mut data).unwrap();
+    assert_eq!(data, b"Comment\0Only partly read");
+}
+
+#[test]
+fn truncated_stream() {
+    let bytes = text_file(b"Comment\0Truncated");
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let chunks = reader.scan_all_chunks().unwrap();
+
+    let start = (chunks[1].position + 4 + 4) as usize;
+    let mut body = ChunkBodyReader::new(&bytes[start..start + 10], chunks[1]);
+    let e = body.read_to_end(&mut Vec::new()).unwrap_err();
+    assert!(matches!(
+        Error::from_io_error(&e),
+        Some(Error::TruncatedData { .. })
+    ));
+}
+
+#[test]
+fn concatenated_idats() {
+    let bytes = common::apng();
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let mut data = Vec::new();
+    reader
+        .image_data_reader()
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, common::zlib(&common::APNG_FRAMES[0]));
+
+    // The result is a single zlib stream
+    let mut decoder = flate2::read::ZlibDecoder::new(reader.image_data_reader().unwrap());
+    let mut pixels = Vec::new();
+    decoder.read_to_end(&mut pixels).unwrap();
+    assert_eq!(pixels, common::APNG_FRAMES[0]);
+}
+
+#[test]
+fn fdat_sequence_numbers_are_skipped() {
+    let bytes = common::apng();
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    reader.scan_header_chunks().unwrap();
+    reader.reset_next_chunk_position();
+    let frames = reader.apng_scan_frames().unwrap();
+    assert_eq!(frames[1].dats.len(), 2);
+
+    for (frame, pixels) in frames.iter().zip(common::APNG_FRAMES) {
+        let mut data = Vec::new();
+        frame
+            .data_reader(Cursor::new(&bytes))
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, common::zlib(&pixels));
+    }
+
+    // Reading one byte at a time makes no difference
+    let mut data_reader = frames[1].data_reader(Cursor::new(&bytes));
+    let mut data = Vec::new();
+    let mut buf = [0_u8; 1];
+    while data_reader.read(&mut buf).unwrap() > 0 {
+        data.push(buf[0]);
+    }
+    assert_eq!(data, common::zlib(&common::APNG_FRAMES[1]));
+}
+
+#[test]
+fn image_data_crc_mismatch() {
+    let mut bytes = common::apng();
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let idats = reader.scan_chunks_filtered(|ct| ct == *b"IDAT").unwrap();
+    bytes[(idats[1].position + 4 + 4) as usize] ^= 0xff;
+
+    let mut data_reader = ImageDataReader::new(Cursor::new(&bytes), idats.clone());
+    let e = data_reader.read_to_end(&mut Vec::new()).unwrap_err();
+    match Error::from_io_error(&e) {
+        Some(Error::CrcMismatch { offset, .. }) => assert_eq!(*offset, idats[1].position),
+        e => panic!("{:?}", e),
+    }
+}
+
+#[test]
+fn not_image_data() {
+    let bytes = common::apng();
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let chunks = reader.scan_all_chunks().unwrap();
+
+    let mut data_reader = ImageDataReader::new(Cursor::new(&bytes), vec![chunks[8]]);
+    assert_eq!(chunks[8].chunktype, *b"tEXt");
+    assert!(data_reader.read_to_end(&mut Vec::new()).is_err());
+}
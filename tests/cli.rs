@@ -0,0 +1,245 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! The pngc command-line tool
+
+mod common;
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use png_container::chunks::*;
+use png_container::document::PngDocument;
+use png_container::types::*;
+
+const IMAGE_DATA: &[u8] = &[0x78, 0x9c, 1, 2, 3, 4];
+
+/// Write a test file, returning its path
+fn source(name: &str) -> PathBuf {
+    let mut doc = PngDocument::new(Ihdr::new(
+        2,
+        2,
+        8,
+        PngColourType::TrueColour,
+        PngInterlaceMethod::None,
+    ));
+    doc.set_image_data(IMAGE_DATA.to_vec());
+    doc.set_gama(Gama::new(0.45455));
+    doc.add_text(Text::new("Author", "Someone").into());
+    doc.set_time(Time::new(2025, 1, 2, 3, 4, 5));
+
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, doc.write_to(Vec::new()).unwrap()).unwrap();
+    path
+}
+
+/// Write a fixture from the common module, returning its path
+fn fixture(name: &str, bytes: &[u8]) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn pngc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pngc"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn list() {
+    let path = source("cli-list.png");
+    let output = pngc(&["list", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = stdout(&output);
+    for chunktype in ["IHDR", "gAMA", "IDAT", "tEXt", "tIME", "IEND"] {
+        assert!(stdout.contains(chunktype), "{} not listed", chunktype);
+    }
+    assert!(!stdout.contains("BAD"));
+}
+
+#[test]
+fn list_bad_crc() {
+    let path = source("cli-bad-crc.png");
+    let mut bytes = std::fs::read(&path).unwrap();
+    // First byte of the tEXt keyword
+    let text = bytes.windows(4).position(|w| w == b"tEXt").unwrap();
+    bytes[text + 4] ^= 0xff;
+    std::fs::write(&path, bytes).unwrap();
+
+    let output = pngc(&["list", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("BAD"));
+
+    let output = pngc(&["validate", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn dump() {
+    let path = source("cli-dump.png");
+    let output = pngc(&["dump", path.to_str().unwrap(), "gAMA", "tEXt"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = stdout(&output);
+    assert!(stdout.contains("gAMA: gamma 0.45455 (45455)"));
+    assert!(stdout.contains("tEXt: \"Author\": \"Someone\""));
+    assert!(!stdout.contains("IHDR"));
+}
+
+#[test]
+fn text() {
+    let path = source("cli-text.png");
+    let out = path.with_extension("out.png");
+    let (path, out) = (path.to_str().unwrap(), out.to_str().unwrap());
+
+    let output = pngc(&["text", "set", path, "Author", "Somebody else", "-o", out]);
+    assert_eq!(output.status.code(), Some(0));
+    let output = pngc(&["text", "get", out, "Author"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "Somebody else\n");
+
+    // Non-Latin-1 strings need iTXt
+    let output = pngc(&["text", "set", path, "Title", "Ωμέγα"]);
+    assert_eq!(output.status.code(), Some(0));
+    let output = pngc(&["text", "get", path]);
+    assert_eq!(stdout(&output), "Author: Someone\nTitle: Ωμέγα\n");
+    let output = pngc(&["dump", path, "iTXt"]);
+    assert!(stdout(&output).contains("iTXt"));
+
+    let output = pngc(&["text", "get", path, "Comment"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = pngc(&["text", "set", path, " Bad", "keyword"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn strip() {
+    let path = source("cli-strip.png");
+    let output = pngc(&["strip", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("Removed tEXt"));
+
+    let output = pngc(&["list", path.to_str().unwrap()]);
+    let stdout = stdout(&output);
+    assert!(stdout.contains("gAMA"));
+    assert!(!stdout.contains("tEXt"));
+    assert!(!stdout.contains("tIME"));
+
+    let output = pngc(&["strip", path.to_str().unwrap(), "--policy", "none"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn extract() {
+    let path = source("cli-extract.png");
+    let output = pngc(&["extract", path.to_str().unwrap(), "idat", "-"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, IMAGE_DATA);
+
+    let output = pngc(&["extract", path.to_str().unwrap(), "exif", "-"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn frames() {
+    let path = fixture("cli-frames.apng", &common::apng());
+    let output = pngc(&["frames", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = stdout(&output);
+    assert!(stdout.starts_with("2 frames, infinite plays\n"));
+    let frames = stdout
+        .lines()
+        .skip(2)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        frames,
+        [
+            ["0", "0", "2x2", "0,0", "0.1s", "None", "Source", "14"],
+            ["1", "1", "2x2", "0,0", "0.1s", "None", "Source", "14"],
+        ]
+    );
+
+    let path = source("cli-frames.png");
+    let output = pngc(&["frames", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn jng() {
+    let path = fixture("cli-jng.jng", &common::jng());
+    let path = path.to_str().unwrap();
+
+    let output = pngc(&["list", path]);
+    assert_eq!(output.status.code(), Some(0));
+    let listed = stdout(&output);
+    for chunktype in ["JHDR", "gAMA", "tEXt", "JDAT", "tIME", "IEND"] {
+        assert!(listed.contains(chunktype), "{} not listed", chunktype);
+    }
+
+    let output = pngc(&["dump", path, "JHDR"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("JHDR: 3×2, Colour"));
+
+    let output = pngc(&["extract", path, "jdat", "-"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(output.stdout, [0xff, 0xd8, 1, 2, 3, 4, 5, 0xff, 0xd9]);
+
+    let output = pngc(&["text", "get", path, "Title"]);
+    assert_eq!(stdout(&output), "Test\n");
+
+    let output = pngc(&["validate", path]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("0 errors, 0 warnings"));
+
+    let output = pngc(&["frames", path]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = pngc(&["strip", path]);
+    assert_eq!(output.status.code(), Some(0));
+    let output = pngc(&["list", path]);
+    let stdout = stdout(&output);
+    assert!(stdout.contains("gAMA"));
+    assert!(!stdout.contains("tEXt"));
+    assert!(stdout.contains("JDAT"));
+}
+
+#[test]
+fn validate() {
+    let path = source("cli-validate.png");
+    let output = pngc(&["validate", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("0 errors, 0 warnings"));
+}
+
+#[test]
+fn errors() {
+    assert_eq!(pngc(&[]).status.code(), Some(2));
+    assert_eq!(pngc(&["list"]).status.code(), Some(2));
+    assert_eq!(pngc(&["frobnicate", "x.png"]).status.code(), Some(2));
+
+    let missing = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cli-missing.png");
+    let output = pngc(&["list", missing.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(3));
+}
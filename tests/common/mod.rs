@@ -0,0 +1,219 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Byte-level builders for test files
+//!
+//! Each test binary only uses some of these.
+
+#![allow(dead_code)]
+
+use std::io::Cursor;
+
+use png_container::chunks::*;
+use png_container::crc::CRC;
+use png_container::types::*;
+
+pub const SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// Encode a chunk with a correct CRC
+pub fn chunk(chunktype: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut crc = CRC::new();
+    crc.consume(chunktype);
+    crc.consume(data);
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(chunktype);
+    bytes.extend_from_slice(data);
+    bytes.extend_from_slice(&crc.value().to_be_bytes());
+    bytes
+}
+
+/// Encode a PNG file from chunks, with an IHDR for an image of the given size and colour type
+pub fn png(width: u32, height: u32, colour_type: u8, bit_depth: u8, chunks: &[Vec<u8>]) -> Vec<u8> {
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[bit_depth, colour_type, 0, 0, 0]);
+
+    let mut bytes = SIGNATURE.to_vec();
+    bytes.extend(chunk(b"IHDR", &ihdr));
+    for c in chunks {
+        bytes.extend_from_slice(c);
+    }
+    bytes.extend(chunk(b"IEND", &[]));
+    bytes
+}
+
+/// Compress data into a zlib stream
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Encode fcTL chunk data for a frame at the top left with a 1/10 second delay
+pub fn fctl(sequence_number: u32, width: u32, height: u32) -> Vec<u8> {
+    fctl_at(sequence_number, width, height, 0, 0)
+}
+
+/// Encode fcTL chunk data for a frame at an offset with a 1/10 second delay
+pub fn fctl_at(
+    sequence_number: u32,
+    width: u32,
+    height: u32,
+    x_offset: u32,
+    y_offset: u32,
+) -> Vec<u8> {
+    let mut data = Vec::new();
+    for v in [sequence_number, width, height, x_offset, y_offset] {
+        data.extend_from_slice(&v.to_be_bytes());
+    }
+    data.extend_from_slice(&[0, 1, 0, 10, 0, 0]);
+    chunk(b"fcTL", &data)
+}
+
+/// Encode fdAT chunk data
+pub fn fdat(sequence_number: u32, data: &[u8]) -> Vec<u8> {
+    let mut contents = sequence_number.to_be_bytes().to_vec();
+    contents.extend_from_slice(data);
+    chunk(b"fdAT", &contents)
+}
+
+/// The image data of the frames of [apng()], with a filter type byte at the start of each row
+pub const APNG_FRAMES: [[u8; 6]; 2] = [[0, 10, 20, 0, 30, 40], [0, 50, 60, 0, 70, 80]];
+
+/// A 2x2 greyscale APNG file with two frames
+///
+/// The first frame is also the default image, and its data is split across two IDAT chunks.
+/// The data of the second frame is split across two fdAT chunks.
+pub fn apng() -> Vec<u8> {
+    let first = zlib(&APNG_FRAMES[0]);
+    let second = zlib(&APNG_FRAMES[1]);
+    let (first_a, first_b) = first.split_at(first.len() / 2);
+    let (second_a, second_b) = second.split_at(second.len() / 2);
+
+    let mut actl = 2_u32.to_be_bytes().to_vec();
+    actl.extend_from_slice(&0_u32.to_be_bytes());
+
+    png(
+        2,
+        2,
+        0,
+        8,
+        &[
+            chunk(b"acTL", &actl),
+            fctl(0, 2, 2),
+            chunk(b"IDAT", first_a),
+            chunk(b"IDAT", first_b),
+            fctl(1, 2, 2),
+            fdat(2, second_a),
+            fdat(3, second_b),
+            chunk(b"tEXt", b"Title\0Frames"),
+        ],
+    )
+}
+
+pub const JNG_SIGNATURE: [u8; 8] = [0x8b, 0x4a, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// A colour JNG file with some metadata
+pub fn jng() -> Vec<u8> {
+    let jhdr = Jhdr::new(
+        3,
+        2,
+        JngColourType::Colour,
+        JngImageSampleDepth::Depth8,
+        JngCompressionType::HuffmanBaseline,
+        JngInterlaceMethod::SequentialJPEG,
+        JngAlphaSampleDepth::Depth0,
+        JngCompressionType::HuffmanBaseline,
+        PngFilterMethod::Adaptive,
+        JngInterlaceMethod::SequentialJPEG,
+    );
+
+    let mut stream = Cursor::new(JNG_SIGNATURE.to_vec());
+    stream.set_position(8);
+    for chunk in [
+        PngChunkData::Jhdr(Box::new(jhdr)),
+        Gama::new(0.45455).into(),
+        Text::new("Title", "Test").into(),
+        Jdat(vec![0xff, 0xd8, 1, 2, 3]).into(),
+        Jdat(vec![4, 5, 0xff, 0xd9]).into(),
+        Time::new(2025, 1, 2, 3, 4, 5).into(),
+        PngChunkData::Iend,
+    ] {
+        chunk.to_stream(&mut stream).unwrap();
+    }
+
+    stream.into_inner()
+}
+
+/// Paeth predictor, as in the PNG specification
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if (pa <= pb) && (pa <= pc) {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Filter a line with a filter type, returning it with the filter type byte at the start
+///
+/// `prev`: The previous unfiltered line, or all zeroes for the first line.
+pub fn filter_line(filter_type: u8, bpp: usize, prev: &[u8], line: &[u8]) -> Vec<u8> {
+    let mut filtered = vec![filter_type];
+    for i in 0..line.len() {
+        let a = if i >= bpp { line[i - bpp] } else { 0 };
+        let b = prev[i];
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        let predictor = match filter_type {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) >> 1) as u8,
+            4 => paeth(a, b, c),
+            _ => panic!("Invalid filter type {}", filter_type),
+        };
+        filtered.push(line[i].wrapping_sub(predictor));
+    }
+
+    filtered
+}
+
+/// Filter the rows of an image or interlace pass, cycling through the filter types
+///
+/// `first_filter`: Filter type of the first row.
+pub fn filter_rows(rows: &[Vec<u8>], bpp: usize, first_filter: u8) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut prev = vec![0_u8; rows.first().map_or(0, |row| row.len())];
+    for (y, row) in rows.iter().enumerate() {
+        data.extend(filter_line((first_filter + y as u8) % 5, bpp, &prev, row));
+        prev = row.clone();
+    }
+
+    data
+}
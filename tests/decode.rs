@@ -0,0 +1,219 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Unfiltering scanlines with the decode feature
+
+#![cfg(feature = "decode")]
+
+mod common;
+
+use std::io::{Cursor, ErrorKind};
+
+use png_container::chunks::*;
+use png_container::decode::*;
+use png_container::types::*;
+
+/// Unfilter a copy of a line
+fn unfilter(filter_type: PngFilterType, bpp: usize, prev: &[u8], line: &[u8]) -> Vec<u8> {
+    let mut line = line.to_vec();
+    unfilter_line(filter_type, bpp, prev, &mut line).unwrap();
+    line
+}
+
+#[test]
+fn short_previous_line() {
+    for filter_type in [
+        PngFilterType::None,
+        PngFilterType::Sub,
+        PngFilterType::Up,
+        PngFilterType::Average,
+        PngFilterType::Paeth,
+    ] {
+        let mut line = [1, 2, 3, 4];
+        let e = unfilter_line(filter_type, 1, &[0; 3], &mut line).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert_eq!(line, [1, 2, 3, 4]);
+    }
+}
+
+#[test]
+fn filters_one_byte_per_pixel() {
+    let prev = [10, 20, 30, 40];
+    let line = [1, 2, 3, 4];
+    assert_eq!(unfilter(PngFilterType::None, 1, &prev, &line), [1, 2, 3, 4]);
+    assert_eq!(unfilter(PngFilterType::Sub, 1, &prev, &line), [1, 3, 6, 10]);
+    assert_eq!(
+        unfilter(PngFilterType::Up, 1, &prev, &line),
+        [11, 22, 33, 44]
+    );
+    assert_eq!(
+        unfilter(PngFilterType::Average, 1, &prev, &line),
+        [6, 15, 25, 36]
+    );
+    assert_eq!(
+        unfilter(PngFilterType::Paeth, 1, &prev, &line),
+        [11, 22, 33, 44]
+    );
+
+    // Additions wrap around
+    assert_eq!(
+        unfilter(PngFilterType::Sub, 1, &prev, &[200, 100]),
+        [200, 44]
+    );
+    assert_eq!(unfilter(PngFilterType::Up, 1, &[250], &[10]), [4]);
+    assert_eq!(unfilter(PngFilterType::Average, 1, &[255], &[200]), [71]);
+}
+
+#[test]
+fn paeth_predictor_choices() {
+    // Predicts from above, then from the left
+    assert_eq!(
+        unfilter(PngFilterType::Paeth, 1, &[100, 100], &[5, 7]),
+        [105, 112]
+    );
+
+    // Predicts from above, then from the upper left
+    assert_eq!(
+        unfilter(PngFilterType::Paeth, 1, &[50, 10], &[50, 3]),
+        [100, 53]
+    );
+}
+
+#[test]
+fn filters_eight_bytes_per_pixel() {
+    // e.g. 16-bit RGBA
+    let line = (1..=16).collect::<Vec<u8>>();
+    assert_eq!(
+        unfilter(PngFilterType::Sub, 8, &[0; 16], &line),
+        [1, 2, 3, 4, 5, 6, 7, 8, 10, 12, 14, 16, 18, 20, 22, 24]
+    );
+    assert_eq!(unfilter(PngFilterType::Up, 8, &[2; 16], &[1; 16]), [3; 16]);
+    assert_eq!(
+        unfilter(PngFilterType::Average, 8, &[2; 16], &[1; 16]),
+        [2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 3]
+    );
+    assert_eq!(
+        unfilter(PngFilterType::Paeth, 8, &[2; 16], &[1; 16]),
+        [3, 3, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 4, 4, 4]
+    );
+}
+
+#[test]
+fn first_row() {
+    // With a previous line of all zeroes, Up is the same as None, and Paeth as Sub
+    let zero = [0; 6];
+    let line = [1, 2, 3, 4, 5, 6];
+    assert_eq!(unfilter(PngFilterType::Up, 2, &zero, &line), line);
+    assert_eq!(
+        unfilter(PngFilterType::Paeth, 2, &zero, &line),
+        unfilter(PngFilterType::Sub, 2, &zero, &line)
+    );
+    assert_eq!(
+        unfilter(PngFilterType::Average, 2, &zero, &line),
+        [1, 2, 3, 5, 6, 8]
+    );
+}
+
+/// Decode all rows of an image with ScanlineDecoder
+fn decode_rows(ihdr: &Ihdr, data: &[u8]) -> Result<Vec<Vec<u8>>, std::io::Error> {
+    let mut decoder = ScanlineDecoder::new(Cursor::new(common::zlib(data)), ihdr)?;
+    let mut rows = Vec::new();
+    while let Some(row) = decoder.next_row()? {
+        rows.push(row.to_vec());
+    }
+    assert_eq!(decoder.rows_left(), 0);
+
+    Ok(rows)
+}
+
+#[test]
+fn decoder_every_filter_type() {
+    let ihdr = Ihdr::new(3, 5, 8, PngColourType::TrueColour, PngInterlaceMethod::None);
+    assert_eq!(filter_bytes_per_pixel(&ihdr), 3);
+    let rows = (0..5)
+        .map(|y| (0..9).map(|x| (x * 37 + y * 101) as u8).collect())
+        .collect::<Vec<Vec<u8>>>();
+
+    // Each filter type gets a turn at the first row
+    for first_filter in 0..5 {
+        let data = common::filter_rows(&rows, 3, first_filter);
+        assert_eq!(decode_rows(&ihdr, &data).unwrap(), rows);
+    }
+}
+
+#[test]
+fn decoder_sub_byte_depths() {
+    // 1-bit greyscale, 10 pixels wide, so 2 bytes per row with 6 bits of padding
+    let ihdr = Ihdr::new(10, 3, 1, PngColourType::Greyscale, PngInterlaceMethod::None);
+    assert_eq!(filter_bytes_per_pixel(&ihdr), 1);
+    let rows = vec![
+        vec![0b1010_1010, 0b1100_0000],
+        vec![0b0101_0101, 0b0100_0000],
+        vec![0b1111_0000, 0b1000_0000],
+    ];
+    for first_filter in 0..5 {
+        let data = common::filter_rows(&rows, 1, first_filter);
+        assert_eq!(decode_rows(&ihdr, &data).unwrap(), rows);
+    }
+
+    // 4-bit indexed colour
+    let ihdr = Ihdr::new(
+        3,
+        2,
+        4,
+        PngColourType::IndexedColour,
+        PngInterlaceMethod::None,
+    );
+    let rows = vec![vec![0x12, 0x30], vec![0xfe, 0xd0]];
+    let data = common::filter_rows(&rows, 1, 3);
+    assert_eq!(decode_rows(&ihdr, &data).unwrap(), rows);
+}
+
+#[test]
+fn decoder_read_all() {
+    let ihdr = Ihdr::new(2, 2, 16, PngColourType::Greyscale, PngInterlaceMethod::None);
+    let rows = vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]];
+    let data = common::zlib(&common::filter_rows(&rows, 2, 4));
+    let mut decoder = ScanlineDecoder::new(Cursor::new(data), &ihdr).unwrap();
+    assert_eq!(decoder.line_size(), 4);
+    assert_eq!(decoder.rows_left(), 2);
+    assert_eq!(decoder.read_all().unwrap(), [1, 2, 3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+fn decoder_invalid_filter_type() {
+    let ihdr = Ihdr::new(2, 1, 8, PngColourType::Greyscale, PngInterlaceMethod::None);
+    let e = decode_rows(&ihdr, &[5, 1, 2]).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn decoder_truncated_data() {
+    let ihdr = Ihdr::new(2, 2, 8, PngColourType::Greyscale, PngInterlaceMethod::None);
+    let e = decode_rows(&ihdr, &[0, 1, 2, 0]).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn decoder_invalid_bit_depth() {
+    let ihdr = Ihdr::new(2, 2, 4, PngColourType::TrueColour, PngInterlaceMethod::None);
+    let e = ScanlineDecoder::new(Cursor::new(Vec::new()), &ihdr)
+        .err()
+        .unwrap();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+}
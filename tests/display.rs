@@ -0,0 +1,187 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Human-readable summaries of chunks
+
+use png_container::chunks::*;
+use png_container::types::*;
+
+#[test]
+fn header() {
+    let ihdr: PngChunkData = Ihdr::new(
+        640,
+        480,
+        8,
+        PngColourType::TrueColourAlpha,
+        PngInterlaceMethod::Adam7,
+    )
+    .into();
+    assert_eq!(
+        ihdr.to_string(),
+        "IHDR: 640×480, 8-bit TrueColourAlpha, interlaced"
+    );
+    assert_eq!(
+        format!("{:#}", ihdr),
+        "IHDR: 640×480, 8-bit TrueColourAlpha, interlaced (compression method Zlib, filter method Adaptive, interlace method Adam7)"
+    );
+    assert_eq!(PngChunkData::Iend.to_string(), "IEND");
+}
+
+#[test]
+fn decoded_values() {
+    let gama: PngChunkData = Gama::new(0.45455).into();
+    assert_eq!(gama.to_string(), "gAMA: gamma 0.45455");
+    assert_eq!(format!("{:#}", gama), "gAMA: gamma 0.45455 (45455)");
+
+    let chrm = Chrm {
+        white_x: 31270,
+        white_y: 32900,
+        red_x: 64000,
+        red_y: 33000,
+        green_x: 30000,
+        green_y: 60000,
+        blue_x: 15000,
+        blue_y: 6000,
+    };
+    assert_eq!(
+        PngChunkData::from(chrm).to_string(),
+        "cHRM: white (0.3127, 0.329), red (0.64, 0.33), green (0.3, 0.6), blue (0.15, 0.06)"
+    );
+
+    let phys = Phys {
+        x_pixels_per_unit: 11811,
+        y_pixels_per_unit: 11811,
+        unit: PngUnitType::Metre,
+    };
+    assert_eq!(phys.to_string(), "300×300 DPI");
+    assert_eq!(
+        format!("{:#}", phys),
+        "300×300 DPI (11811×11811 pixels per metre)"
+    );
+    let phys = Phys {
+        x_pixels_per_unit: 2,
+        y_pixels_per_unit: 1,
+        unit: PngUnitType::Unknown,
+    };
+    assert_eq!(phys.to_string(), "aspect ratio 2:1");
+
+    let time: PngChunkData = Time::new(2025, 1, 2, 3, 4, 5).into();
+    assert_eq!(time.to_string(), "tIME: 2025-01-02T03:04:05Z");
+    assert_eq!(
+        Time::new(2025, 13, 2, 3, 4, 5).to_string(),
+        "2025-13-02T03:04:05Z (invalid)"
+    );
+
+    let fctl = Fctl {
+        sequence_number: 3,
+        width: 16,
+        height: 8,
+        x_offset: 1,
+        y_offset: 2,
+        delay_num: 1,
+        delay_den: 4,
+        dispose_op: ApngDisposalOperator::Background,
+        blend_op: ApngBlendOperator::Over,
+    };
+    assert_eq!(
+        fctl.to_string(),
+        "sequence 3, 16×8 at (1, 2), delay 0.25 s, dispose Background, blend Over"
+    );
+    assert_eq!(
+        format!("{:#}", fctl),
+        "sequence 3, 16×8 at (1, 2), delay 0.25 s (1/4), dispose Background, blend Over"
+    );
+
+    // A denominator of 0 means hundredths of a second
+    for (delay_num, delay) in [(5, "0.05"), (0, "0")] {
+        let fctl = Fctl {
+            delay_num,
+            delay_den: 0,
+            ..fctl
+        };
+        assert_eq!(
+            format!("{:#}", fctl),
+            format!(
+                "sequence 3, 16×8 at (1, 2), delay {} s ({}/0), dispose Background, blend Over",
+                delay, delay_num
+            )
+        );
+    }
+
+    let actl = Actl {
+        num_frames: 4,
+        num_plays: 0,
+    };
+    assert_eq!(actl.to_string(), "4 frames, looping forever");
+}
+
+#[test]
+fn text() {
+    let ztxt: PngChunkData = Ztxt::new("Comment", PngCompressionMethod::Zlib, "Hello").into();
+    assert_eq!(ztxt.to_string(), "zTXt: \"Comment\": \"Hello\"");
+
+    let itxt = Itxt::new("Title", None, "el", "Τίτλος", "Ωμέγα");
+    assert_eq!(
+        itxt.to_string(),
+        "\"Title\" (\"el\", \"Τίτλος\"): \"Ωμέγα\""
+    );
+
+    // Long strings are truncated, except in the alternate form
+    let long = "a".repeat(100);
+    let text = Text::new("Description", &long);
+    assert_eq!(
+        text.to_string(),
+        format!("\"Description\": \"{}\"…", "a".repeat(60))
+    );
+    assert_eq!(
+        format!("{:#}", text),
+        format!("\"Description\": \"{}\"", long)
+    );
+}
+
+#[test]
+fn binary_data() {
+    let idat: PngChunkData = PngChunkData::Idat(Box::new(Idat((0..20).collect())));
+    assert_eq!(idat.to_string(), "IDAT: 20 bytes");
+    assert_eq!(
+        format!("{:#}", idat),
+        "IDAT: 20 bytes: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f …"
+    );
+
+    let unknown = PngChunkData::Unknown {
+        chunktype: *b"prIv",
+        data: vec![0xca, 0xfe],
+    };
+    assert_eq!(unknown.to_string(), "prIv: 2 bytes");
+    assert_eq!(format!("{:#}", unknown), "prIv: 2 bytes: ca fe");
+
+    let plte = Plte(vec![
+        PngPaletteEntry {
+            red: 255,
+            green: 0,
+            blue: 0,
+        },
+        PngPaletteEntry {
+            red: 0,
+            green: 128,
+            blue: 255,
+        },
+    ]);
+    assert_eq!(plte.to_string(), "2 entries");
+    assert_eq!(format!("{:#}", plte), "2 entries: #ff0000 #0080ff");
+}
@@ -0,0 +1,206 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Editing files with PngDocument
+
+use std::io::Cursor;
+
+use png_container::chunks::*;
+use png_container::document::PngDocument;
+use png_container::reader::PngReader;
+use png_container::types::*;
+use png_container::validate::validate;
+
+fn indexed_document() -> PngDocument {
+    let mut doc = PngDocument::new(Ihdr::new(
+        2,
+        2,
+        1,
+        PngColourType::IndexedColour,
+        PngInterlaceMethod::None,
+    ));
+    doc.set_image_data(vec![1, 2, 3, 4, 5]);
+    doc
+}
+
+fn chunk_types(doc: &PngDocument) -> Vec<[u8; 4]> {
+    doc.chunks.iter().map(|c| c.chunktype()).collect()
+}
+
+fn reload(doc: &PngDocument) -> PngDocument {
+    let bytes = doc.write_to(Vec::new()).unwrap();
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    assert!(validate(&mut reader).unwrap().is_empty());
+    PngDocument::from_reader(&mut reader).unwrap()
+}
+
+#[test]
+fn insert_in_zones() {
+    let mut doc = indexed_document();
+    doc.add_text(Text::new("Title", "Test").into());
+    doc.set_trns(Trns::IndexedColour { values: vec![0] });
+    doc.set_plte(Plte::new(
+        &[PngPaletteEntry {
+            red: 0,
+            green: 0,
+            blue: 0,
+        }; 2],
+    ));
+    doc.set_gama(Gama::new(0.45455));
+    doc.set_time(Time::new(2025, 6, 7, 8, 9, 10));
+
+    assert_eq!(
+        chunk_types(&doc),
+        [
+            *b"IHDR", *b"gAMA", *b"PLTE", *b"tRNS", *b"IDAT", *b"tEXt", *b"tIME", *b"IEND"
+        ]
+    );
+    assert_eq!(chunk_types(&reload(&doc)), chunk_types(&doc));
+}
+
+/// Chunks edited into an illegal order are written in a legal one
+#[test]
+fn write_out_of_order() {
+    let mut doc = indexed_document();
+    doc.set_plte(Plte::new(&[PngPaletteEntry {
+        red: 1,
+        green: 2,
+        blue: 3,
+    }]));
+    doc.add_text(Text::new("Title", "Test").into());
+    doc.set_phys(Phys {
+        x_pixels_per_unit: 2835,
+        y_pixels_per_unit: 2835,
+        unit: PngUnitType::Metre,
+    });
+
+    // Move pHYs after IDAT, and gAMA after PLTE
+    let phys = doc.remove(*b"pHYs").pop().unwrap();
+    let idat = doc
+        .chunks
+        .iter()
+        .position(|c| c.chunktype() == *b"IDAT")
+        .unwrap();
+    doc.chunks.insert(idat + 1, phys);
+    let plte = doc
+        .chunks
+        .iter()
+        .position(|c| c.chunktype() == *b"PLTE")
+        .unwrap();
+    doc.chunks.insert(plte + 1, Gama::new(0.45455).into());
+    assert_eq!(
+        chunk_types(&doc),
+        [
+            *b"IHDR", *b"PLTE", *b"gAMA", *b"IDAT", *b"pHYs", *b"tEXt", *b"IEND"
+        ]
+    );
+
+    let written = reload(&doc);
+    assert_eq!(
+        chunk_types(&written),
+        [
+            *b"IHDR", *b"gAMA", *b"PLTE", *b"pHYs", *b"IDAT", *b"tEXt", *b"IEND"
+        ]
+    );
+    assert_eq!(written.image_data(), [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn singletons() {
+    let mut doc = indexed_document();
+    assert!(doc.phys().is_none());
+
+    doc.set_phys(Phys {
+        x_pixels_per_unit: 2835,
+        y_pixels_per_unit: 2835,
+        unit: PngUnitType::Metre,
+    });
+    doc.set_phys(Phys {
+        x_pixels_per_unit: 3780,
+        y_pixels_per_unit: 3780,
+        unit: PngUnitType::Metre,
+    });
+    assert_eq!(doc.get_all(*b"pHYs").count(), 1);
+    assert_eq!(doc.phys().unwrap().x_pixels_per_unit, 3780);
+
+    // Extra chunks of a singleton type are removed by set()
+    doc.chunks.insert(1, Gama::new(1.0).into());
+    doc.chunks.insert(1, Gama::new(1.0).into());
+    doc.set_gama(Gama::new(0.5));
+    assert_eq!(doc.get_all(*b"gAMA").count(), 1);
+    assert_eq!(doc.gama().unwrap().gamma(), 0.5);
+
+    doc.set_ihdr(Ihdr::new(
+        4,
+        4,
+        2,
+        PngColourType::IndexedColour,
+        PngInterlaceMethod::None,
+    ));
+    assert_eq!(doc.chunks[0].chunktype(), *b"IHDR");
+    assert_eq!(doc.ihdr().unwrap().bit_depth, 2);
+}
+
+#[test]
+fn text_chunks() {
+    let mut doc = indexed_document();
+    doc.set_plte(Plte::new(&[PngPaletteEntry {
+        red: 1,
+        green: 2,
+        blue: 3,
+    }]));
+    doc.add_text(Text::new("Title", "One").into());
+    doc.add_text(Ztxt::new("Comment", PngCompressionMethod::Zlib, "Two").into());
+    doc.add_text(Text::new("Title", "Three").into());
+
+    let doc = reload(&doc);
+    let keywords = doc
+        .text_chunks()
+        .filter_map(|c| c.text_keyword())
+        .collect::<Vec<&str>>();
+    assert_eq!(keywords, ["Title", "Comment", "Title"]);
+
+    let mut doc = doc;
+    assert_eq!(doc.remove_text("Title").len(), 2);
+    assert_eq!(doc.text_chunks().count(), 1);
+    assert_eq!(doc.remove(*b"zTXt").len(), 1);
+    assert_eq!(doc.text_chunks().count(), 0);
+}
+
+#[test]
+fn image_data() {
+    let mut doc = indexed_document();
+    doc.set_plte(Plte::new(&[PngPaletteEntry {
+        red: 1,
+        green: 2,
+        blue: 3,
+    }]));
+    doc.idat_size = 2;
+    doc.add_text(Text::new("Title", "Test").into());
+
+    let mut doc = reload(&doc);
+    assert_eq!(doc.get_all(*b"IDAT").count(), 3);
+    assert_eq!(doc.image_data(), [1, 2, 3, 4, 5]);
+
+    doc.set_image_data(vec![6, 7, 8]);
+    assert_eq!(
+        chunk_types(&doc),
+        [*b"IHDR", *b"PLTE", *b"IDAT", *b"tEXt", *b"IEND"]
+    );
+    assert_eq!(reload(&doc).image_data(), [6, 7, 8]);
+}
@@ -0,0 +1,69 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Writing and reading back header chunks
+
+use std::io::{Cursor, Seek, SeekFrom};
+
+use png_container::chunks::*;
+use png_container::types::*;
+
+/// Write a chunk and read it back again
+fn round_trip(chunk: PngChunkData) -> PngChunkData {
+    let mut stream = Cursor::new(Vec::new());
+    chunk.to_stream(&mut stream).unwrap();
+
+    stream.seek(SeekFrom::Start(0)).unwrap();
+    let chunkref = PngChunkRef::from_stream(&mut stream).unwrap();
+    chunkref.read_chunk(&mut stream, None).unwrap()
+}
+
+#[test]
+fn ihdr_dimensions() {
+    let ihdr = Ihdr::new(
+        640,
+        480,
+        8,
+        PngColourType::TrueColour,
+        PngInterlaceMethod::None,
+    );
+    let PngChunkData::Ihdr(ihdr) = round_trip(PngChunkData::Ihdr(ihdr)) else {
+        panic!("Not an IHDR chunk");
+    };
+    assert_eq!((ihdr.width, ihdr.height), (640, 480));
+}
+
+#[test]
+fn jhdr_dimensions() {
+    let jhdr = Jhdr::new(
+        640,
+        480,
+        JngColourType::Colour,
+        JngImageSampleDepth::Depth8,
+        JngCompressionType::HuffmanBaseline,
+        JngInterlaceMethod::SequentialJPEG,
+        JngAlphaSampleDepth::Depth0,
+        JngCompressionType::HuffmanBaseline,
+        PngFilterMethod::Adaptive,
+        JngInterlaceMethod::SequentialJPEG,
+    );
+    let PngChunkData::Jhdr(jhdr) = round_trip(PngChunkData::Jhdr(Box::new(jhdr))) else {
+        panic!("Not a JHDR chunk");
+    };
+    assert_eq!((jhdr.width, jhdr.height), (640, 480));
+}
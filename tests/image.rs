@@ -0,0 +1,402 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Decoding images into pixel buffers with the decode feature
+
+#![cfg(feature = "decode")]
+
+mod common;
+
+use std::io::{Cursor, ErrorKind};
+
+use png_container::chunks::*;
+use png_container::decode::filter_bytes_per_pixel;
+use png_container::document::PngDocument;
+use png_container::image::*;
+use png_container::reader::PngReader;
+use png_container::types::*;
+
+const WIDTH: u32 = 5;
+const HEIGHT: u32 = 5;
+
+/// Every colour type, with each of its bit depths
+const FORMATS: [(PngColourType, &[u8]); 5] = [
+    (PngColourType::Greyscale, &[1, 2, 4, 8, 16]),
+    (PngColourType::TrueColour, &[8, 16]),
+    (PngColourType::IndexedColour, &[1, 2, 4, 8]),
+    (PngColourType::GreyscaleAlpha, &[8, 16]),
+    (PngColourType::TrueColourAlpha, &[8, 16]),
+];
+
+fn ihdr(width: u32, height: u32, bit_depth: u8, colour_type: PngColourType) -> Ihdr {
+    Ihdr::new(
+        width,
+        height,
+        bit_depth,
+        colour_type,
+        PngInterlaceMethod::None,
+    )
+}
+
+/// Pack samples into a row
+fn pack(samples: &[u16], bit_depth: u8) -> Vec<u8> {
+    match bit_depth {
+        16 => samples.iter().flat_map(|s| s.to_be_bytes()).collect(),
+        8 => samples.iter().map(|s| *s as u8).collect(),
+        _ => {
+            let bits = bit_depth as usize;
+            let mut row = vec![0; (samples.len() * bits).div_ceil(8)];
+            for (i, sample) in samples.iter().enumerate() {
+                let bit = i * bits;
+                row[bit / 8] |= (*sample as u8) << (8 - bits - bit % 8);
+            }
+            row
+        }
+    }
+}
+
+/// Samples of a test image, in rows
+fn samples(ihdr: &Ihdr) -> Vec<Vec<u16>> {
+    let components = ihdr.colour_type.num_components() as usize;
+    let max = ((1_u32 << ihdr.bit_depth) - 1) as u16;
+    (0..ihdr.height as usize)
+        .map(|y| {
+            (0..ihdr.width as usize * components)
+                .map(|i| ((y * 97 + i * 31 + 5) as u16).wrapping_mul(0x9e37) & max)
+                .collect()
+        })
+        .collect()
+}
+
+/// A palette with an entry for every possible index
+fn palette(bit_depth: u8) -> Plte {
+    Plte(
+        (0..1_usize << bit_depth)
+            .map(|i| PngPaletteEntry {
+                red: (i * 3) as u8,
+                green: (255 - i) as u8,
+                blue: (i * 7) as u8,
+            })
+            .collect(),
+    )
+}
+
+/// Encode a file, with each row filtered by a different filter type
+fn encode(ihdr: &Ihdr, chunks: &[PngChunkData], rows: &[Vec<u16>], first_filter: u8) -> Vec<u8> {
+    let packed = rows
+        .iter()
+        .map(|row| pack(row, ihdr.bit_depth))
+        .collect::<Vec<_>>();
+    let data = common::filter_rows(&packed, filter_bytes_per_pixel(ihdr), first_filter);
+
+    let mut doc = PngDocument::new(*ihdr);
+    for chunk in chunks {
+        doc.insert(chunk.clone());
+    }
+    doc.set_image_data(common::zlib(&data));
+    doc.write_to(Vec::new()).unwrap()
+}
+
+fn decode(bytes: &[u8], options: &DecodeOptions) -> Result<DecodedImage, std::io::Error> {
+    PngReader::from_stream(Cursor::new(bytes))?.decode_image(options)
+}
+
+fn decode_u8(bytes: &[u8], format: PixelFormat) -> Vec<u8> {
+    let image = decode(bytes, &DecodeOptions::new(format)).unwrap();
+    assert_eq!(image.format, format);
+    match image.data {
+        ImageBuffer::U8(data) => data,
+        data => panic!("Decoded {:?}", data),
+    }
+}
+
+fn decode_u16(bytes: &[u8], format: PixelFormat) -> Vec<u16> {
+    let image = decode(bytes, &DecodeOptions::new(format)).unwrap();
+    match image.data {
+        ImageBuffer::U16(data) => data,
+        data => panic!("Decoded {:?}", data),
+    }
+}
+
+/// Decoding to the lossless format gives back the samples, scaled to the full range
+#[test]
+fn every_colour_type_and_bit_depth() {
+    for (colour_type, bit_depths) in FORMATS {
+        for &bit_depth in bit_depths {
+            let ihdr = ihdr(WIDTH, HEIGHT, bit_depth, colour_type);
+            let rows = samples(&ihdr);
+            let indexed = colour_type == PngColourType::IndexedColour;
+            let plte = palette(if indexed { bit_depth } else { 0 });
+            let chunks = if indexed {
+                vec![plte.clone().into()]
+            } else {
+                vec![]
+            };
+
+            let format = PixelFormat::for_ihdr(&ihdr, false);
+            let samples = rows.concat();
+            let expected = ImageBuffer::U8(if indexed {
+                samples
+                    .iter()
+                    .flat_map(|i| {
+                        let entry = plte.0[*i as usize];
+                        [entry.red, entry.green, entry.blue]
+                    })
+                    .collect()
+            } else {
+                let scale = 255 / ((1 << bit_depth.min(8)) - 1);
+                samples.iter().map(|s| (*s * scale) as u8).collect()
+            });
+            let expected = if bit_depth == 16 {
+                ImageBuffer::U16(samples)
+            } else {
+                expected
+            };
+
+            for first_filter in 0..5 {
+                let bytes = encode(&ihdr, &chunks, &rows, first_filter);
+                let image = decode(&bytes, &DecodeOptions::new(format)).unwrap();
+                assert_eq!((image.width, image.height), (WIDTH, HEIGHT));
+                assert_eq!(image.format, format);
+                assert_eq!(
+                    image.data, expected,
+                    "{:?} {} bits, first filter {}",
+                    colour_type, bit_depth, first_filter
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn palette_with_trns() {
+    for bit_depth in [1, 2, 4, 8] {
+        let ihdr = ihdr(WIDTH, HEIGHT, bit_depth, PngColourType::IndexedColour);
+        let rows = samples(&ihdr);
+        let plte = palette(bit_depth);
+
+        // Entries past the end of tRNS are opaque
+        let alphas = (0..plte.0.len().div_ceil(2))
+            .map(|i| (i * 11) as u8)
+            .collect::<Vec<_>>();
+        let trns = Trns::IndexedColour {
+            values: alphas.clone(),
+        };
+        let bytes = encode(&ihdr, &[plte.clone().into(), trns.into()], &rows, 0);
+
+        let expected = rows
+            .concat()
+            .iter()
+            .flat_map(|i| {
+                let entry = plte.0[*i as usize];
+                let alpha = alphas.get(*i as usize).copied().unwrap_or(0xff);
+                [entry.red, entry.green, entry.blue, alpha]
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            PixelFormat::for_ihdr(&ihdr, true),
+            PixelFormat::Rgba8,
+            "{} bits",
+            bit_depth
+        );
+        assert_eq!(decode_u8(&bytes, PixelFormat::Rgba8), expected);
+
+        // The palette is expanded without tRNS when the format has no alpha
+        let rgb = expected
+            .chunks_exact(4)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect::<Vec<_>>();
+        assert_eq!(decode_u8(&bytes, PixelFormat::Rgb8), rgb);
+    }
+
+    // Indexed colour needs a palette, with every index in range
+    let ihdr = ihdr(2, 1, 8, PngColourType::IndexedColour);
+    let bytes = encode(&ihdr, &[], &[vec![0, 1]], 0);
+    let e = decode(&bytes, &DecodeOptions::new(PixelFormat::Rgb8)).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+
+    let bytes = encode(&ihdr, &[palette(1).into()], &[vec![0, 2]], 0);
+    let e = decode(&bytes, &DecodeOptions::new(PixelFormat::Rgb8)).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn trns_keying() {
+    // Greyscale: matching samples become transparent
+    let grey = ihdr(4, 1, 4, PngColourType::Greyscale);
+    let bytes = encode(
+        &grey,
+        &[Trns::Greyscale { value: 3 }.into()],
+        &[vec![0, 3, 15, 3]],
+        0,
+    );
+    assert_eq!(
+        decode_u8(&bytes, PixelFormat::GrayAlpha8),
+        [0, 255, 51, 0, 255, 255, 51, 0]
+    );
+    assert_eq!(
+        decode_u8(&bytes, PixelFormat::Rgba8),
+        [
+            0, 0, 0, 255, 51, 51, 51, 0, 255, 255, 255, 255, 51, 51, 51, 0
+        ]
+    );
+
+    // Unless tRNS isn't applied, or there's no alpha channel
+    let mut options = DecodeOptions::new(PixelFormat::GrayAlpha8);
+    options.apply_trns = false;
+    assert_eq!(
+        decode(&bytes, &options).unwrap().data,
+        ImageBuffer::U8(vec![0, 255, 51, 255, 255, 255, 51, 255])
+    );
+    assert_eq!(decode_u8(&bytes, PixelFormat::Gray8), [0, 51, 255, 51]);
+
+    // True colour: all three samples must match
+    let rgb = ihdr(3, 1, 16, PngColourType::TrueColour);
+    let bytes = encode(
+        &rgb,
+        &[Trns::TrueColour {
+            red: 0x1234,
+            green: 0x5678,
+            blue: 0x9abc,
+        }
+        .into()],
+        &[vec![
+            0x1234, 0x5678, 0x9abc, 0x1234, 0x5678, 0x9abd, 0xffff, 0, 0,
+        ]],
+        0,
+    );
+    assert_eq!(
+        decode_u16(&bytes, PixelFormat::Rgba16),
+        [
+            0x1234, 0x5678, 0x9abc, 0, 0x1234, 0x5678, 0x9abd, 0xffff, 0xffff, 0, 0, 0xffff
+        ]
+    );
+}
+
+#[test]
+fn sbit_scaling() {
+    // Five significant bits, stored in the high bits of each sample
+    let grey = ihdr(4, 1, 8, PngColourType::Greyscale);
+    let sbit = Sbit::Greyscale { grey_bits: 5 };
+    let bytes = encode(&grey, &[sbit.into()], &[vec![0x00, 0x80, 0xf8, 0xff]], 0);
+
+    assert_eq!(decode_u8(&bytes, PixelFormat::Gray8), [0, 128, 248, 255]);
+
+    let mut options = DecodeOptions::new(PixelFormat::Gray8);
+    options.scale_sbit = true;
+    assert_eq!(
+        decode(&bytes, &options).unwrap().data,
+        ImageBuffer::U8(vec![0, 132, 255, 255])
+    );
+
+    let mut options = DecodeOptions::new(PixelFormat::Gray16);
+    options.scale_sbit = true;
+    assert_eq!(
+        decode(&bytes, &options).unwrap().data,
+        ImageBuffer::U16(vec![0, 0x8421, 0xffff, 0xffff])
+    );
+
+    // Each channel is scaled separately, and alpha keeps its full range
+    let rgba = ihdr(1, 1, 8, PngColourType::TrueColourAlpha);
+    let sbit = Sbit::TrueColourAlpha {
+        red_bits: 1,
+        green_bits: 4,
+        blue_bits: 8,
+        alpha_bits: 2,
+    };
+    let bytes = encode(&rgba, &[sbit.into()], &[vec![0x80, 0x70, 0x70, 0x40]], 0);
+    let mut options = DecodeOptions::new(PixelFormat::Rgba8);
+    options.scale_sbit = true;
+    assert_eq!(
+        decode(&bytes, &options).unwrap().data,
+        ImageBuffer::U8(vec![255, 119, 0x70, 85])
+    );
+}
+
+#[test]
+fn sixteen_to_eight_bits() {
+    let grey = ihdr(4, 1, 16, PngColourType::Greyscale);
+    let bytes = encode(&grey, &[], &[vec![0x0000, 0x1234, 0x80ff, 0xffff]], 0);
+
+    assert_eq!(decode_u8(&bytes, PixelFormat::Gray8), [0, 18, 128, 255]);
+    assert_eq!(
+        decode_u16(&bytes, PixelFormat::Gray16),
+        [0x0000, 0x1234, 0x80ff, 0xffff]
+    );
+
+    // 8-bit samples are widened exactly
+    let grey = ihdr(2, 1, 8, PngColourType::Greyscale);
+    let bytes = encode(&grey, &[], &[vec![0x12, 0xff]], 0);
+    assert_eq!(decode_u16(&bytes, PixelFormat::Gray16), [0x1212, 0xffff]);
+}
+
+#[test]
+fn luma() {
+    let rgba = ihdr(5, 1, 8, PngColourType::TrueColourAlpha);
+    let rows = [vec![
+        255, 0, 0, 10, 0, 255, 0, 20, 0, 0, 255, 30, 255, 255, 255, 40, 0, 0, 0, 50,
+    ]];
+    let bytes = encode(&rgba, &[], &rows, 0);
+
+    assert_eq!(decode_u8(&bytes, PixelFormat::Gray8), [54, 182, 18, 255, 0]);
+    assert_eq!(
+        decode_u8(&bytes, PixelFormat::GrayAlpha8),
+        [54, 10, 182, 20, 18, 30, 255, 40, 0, 50]
+    );
+
+    // Alpha is dropped, not composited
+    assert_eq!(
+        decode_u8(&bytes, PixelFormat::Rgb8),
+        [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 0, 0, 0]
+    );
+
+    // Grey is copied into each colour channel, with opaque alpha
+    let grey = ihdr(2, 1, 2, PngColourType::Greyscale);
+    let bytes = encode(&grey, &[], &[vec![1, 2]], 0);
+    assert_eq!(
+        decode_u8(&bytes, PixelFormat::Rgba8),
+        [85, 85, 85, 255, 170, 170, 170, 255]
+    );
+}
+
+/// PixelConverter can be used directly, e.g. on APNG frames smaller than the image
+#[test]
+fn converter() {
+    let ihdr = ihdr(8, 8, 4, PngColourType::IndexedColour);
+    let mut converter = PixelConverter::new(&ihdr);
+    converter.add_chunk(&palette(4).into());
+    converter.add_chunk(&Text::new("Title", "Ignored").into());
+
+    let data = [0x01, 0x23, 0xfe, 0xd0];
+    let image = converter
+        .convert(&data, 3, 2, &DecodeOptions::new(PixelFormat::Rgb8))
+        .unwrap();
+    assert_eq!((image.width, image.height), (3, 2));
+    assert_eq!(
+        image.data,
+        ImageBuffer::U8(vec![
+            0, 255, 0, 3, 254, 7, 6, 253, 14, 45, 240, 105, 42, 241, 98, 39, 242, 91
+        ])
+    );
+
+    let e = converter
+        .convert(&data, 3, 3, &DecodeOptions::new(PixelFormat::Rgb8))
+        .unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+}
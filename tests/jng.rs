@@ -0,0 +1,137 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Reading, rewriting, and stripping JNG files
+
+mod common;
+
+use std::io::Cursor;
+
+use png_container::chunks::*;
+use png_container::jngreader::JngReader;
+use png_container::rewrite::{ChunkSelector, RewriteOp};
+use png_container::strip::StripPolicy;
+use png_container::types::*;
+
+fn chunk_types(bytes: &[u8]) -> Vec<[u8; 4]> {
+    let mut reader = JngReader::from_stream(Cursor::new(bytes)).unwrap();
+    reader
+        .scan_all_chunks()
+        .unwrap()
+        .iter()
+        .map(|c| c.chunktype)
+        .collect()
+}
+
+#[test]
+fn scan() {
+    let bytes = common::jng();
+    let mut reader = JngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let header = reader.scan_header_chunks().unwrap();
+    assert_eq!(
+        header.iter().map(|c| c.chunktype).collect::<Vec<_>>(),
+        [*b"JHDR", *b"gAMA", *b"tEXt"]
+    );
+    assert_eq!((reader.width, reader.height), (3, 2));
+    assert_eq!(reader.colour_type, JngColourType::Colour);
+    assert!(reader.jhdr.is_some());
+
+    assert_eq!(
+        chunk_types(&bytes),
+        [
+            *b"JHDR", *b"gAMA", *b"tEXt", *b"JDAT", *b"JDAT", *b"tIME", *b"IEND"
+        ]
+    );
+
+    let mut reader = JngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let chunks = reader.scan_all_chunks().unwrap();
+    match reader.read_chunk(&chunks[3]).unwrap() {
+        PngChunkData::Jdat(jdat) => assert_eq!(jdat.0, [0xff, 0xd8, 1, 2, 3]),
+        chunk => panic!("Read {:?}", chunk),
+    }
+}
+
+#[test]
+fn rewrite() {
+    let bytes = common::jng();
+    let mut reader = JngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let mut out = Vec::new();
+    let written = reader
+        .rewrite(
+            &[
+                RewriteOp::Replace(
+                    ChunkSelector::Keyword("Title".to_string()),
+                    Text::new("Title", "New").into(),
+                ),
+                RewriteOp::Delete(ChunkSelector::Type(*b"gAMA")),
+                RewriteOp::Insert(
+                    Phys {
+                        x_pixels_per_unit: 2835,
+                        y_pixels_per_unit: 2835,
+                        unit: PngUnitType::Metre,
+                    }
+                    .into(),
+                ),
+            ],
+            &mut out,
+        )
+        .unwrap();
+
+    let types = chunk_types(&out);
+    assert_eq!(
+        written.iter().map(|c| c.chunktype).collect::<Vec<_>>(),
+        types
+    );
+    assert_eq!(
+        types,
+        [
+            *b"JHDR", *b"tEXt", *b"pHYs", *b"JDAT", *b"JDAT", *b"tIME", *b"IEND"
+        ]
+    );
+
+    // The image data is copied unchanged
+    let jdat_start = bytes.windows(4).position(|w| w == b"JDAT").unwrap() - 4;
+    let out_jdat_start = out.windows(4).position(|w| w == b"JDAT").unwrap() - 4;
+    let jdat_len = 2 * (4 + 4 + 4) + 5 + 4;
+    assert_eq!(
+        bytes[jdat_start..jdat_start + jdat_len],
+        out[out_jdat_start..out_jdat_start + jdat_len]
+    );
+}
+
+#[test]
+fn strip() {
+    let bytes = common::jng();
+    let mut reader = JngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let mut out = Vec::new();
+    let report = reader.strip(StripPolicy::Metadata, &mut out).unwrap();
+
+    assert_eq!(
+        report
+            .removed
+            .iter()
+            .map(|c| c.chunktype)
+            .collect::<Vec<_>>(),
+        [*b"tEXt", *b"tIME"]
+    );
+    assert_eq!(report.bytes_saved, (bytes.len() - out.len()) as u64);
+    assert_eq!(
+        chunk_types(&out),
+        [*b"JHDR", *b"gAMA", *b"JDAT", *b"JDAT", *b"IEND"]
+    );
+}
@@ -0,0 +1,376 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Resource limits in every reader
+
+mod common;
+
+use std::io::{Cursor, ErrorKind};
+
+use common::chunk;
+use png_container::chunks::*;
+use png_container::error::Error;
+use png_container::jngreader::JngReader;
+use png_container::limits::*;
+use png_container::pushparser::PngPushParser;
+use png_container::reader::PngReader;
+use png_container::slice::PngSlice;
+use png_container::streamreader::PngStreamReader;
+use png_container::types::*;
+
+/// Check that an error is for an exceeded limit
+fn assert_limit(e: &std::io::Error, kind: LimitKind, limit: u64, chunktype: Option<&[u8; 4]>) {
+    assert_eq!(e.kind(), ErrorKind::QuotaExceeded, "{}", e);
+    match Error::from_io_error(e) {
+        Some(Error::LimitExceeded {
+            chunktype: ct,
+            limit: exceeded,
+            ..
+        }) => {
+            assert_eq!(*exceeded, LimitExceeded { kind, limit });
+            assert_eq!(ct.as_ref(), chunktype);
+        }
+        other => panic!("Got {:?}", other),
+    }
+}
+
+fn ztxt(keyword: &str, text: &str) -> Vec<u8> {
+    let mut data = keyword.as_bytes().to_vec();
+    data.extend_from_slice(&[0, 0]);
+    data.extend(common::zlib(text.as_bytes()));
+    chunk(b"zTXt", &data)
+}
+
+fn iccp(profile: &[u8]) -> Vec<u8> {
+    let mut data = b"Profile\0\0".to_vec();
+    data.extend(common::zlib(profile));
+    chunk(b"iCCP", &data)
+}
+
+/// A file with a 100 byte private chunk, and 5 chunks in all
+fn file() -> Vec<u8> {
+    common::png(
+        2,
+        2,
+        0,
+        8,
+        &[
+            chunk(b"prIv", &[7; 100]),
+            chunk(b"IDAT", &common::zlib(&[0, 1, 2, 0, 3, 4])),
+            chunk(b"tEXt", b"Title\0Limits"),
+        ],
+    )
+}
+
+/// The default limits with some changes
+fn with_limits(change: impl FnOnce(&mut Limits)) -> Limits {
+    let mut limits = Limits::default();
+    change(&mut limits);
+    limits
+}
+
+#[test]
+fn defaults() {
+    let limits = Limits::default();
+    assert_eq!(limits.max_chunk_length, 64 << 20);
+    assert_eq!(limits.max_chunks, 1 << 20);
+    assert!(limits.check_chunk_length(64 << 20).is_ok());
+    assert_eq!(
+        limits.check_chunk_length((64 << 20) + 1),
+        Err(LimitExceeded {
+            kind: LimitKind::ChunkLength,
+            limit: 64 << 20
+        })
+    );
+
+    // 16384 × 16384 RGBA is exactly 1 GiB
+    assert!(limits.check_image_size(16384, 16384, 32).is_ok());
+    assert!(limits.check_image_size(16384, 16385, 32).is_err());
+    assert!(limits.check_image_size(u32::MAX, u32::MAX, 64).is_err());
+
+    let unlimited = Limits::unlimited();
+    assert!(unlimited.check_chunk_length(u32::MAX).is_ok());
+    assert!(unlimited.check_chunk_count(u64::MAX).is_ok());
+    assert!(unlimited.check_image_size(u32::MAX, u32::MAX, 64).is_ok());
+
+    let e = std::io::Error::from(LimitExceeded {
+        kind: LimitKind::ChunkCount,
+        limit: 3,
+    });
+    assert_eq!(e.to_string(), "PNG: Number of chunks exceeds the limit (3)");
+    assert_limit(&e, LimitKind::ChunkCount, 3, None);
+}
+
+#[test]
+fn chunk_length() {
+    let bytes = file();
+    let limits = with_limits(|l| l.max_chunk_length = 99);
+
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    reader.limits = limits;
+    let chunks = reader.scan_all_chunks().unwrap();
+    let e = reader.read_chunk(&chunks[1]).unwrap_err();
+    assert_limit(&e, LimitKind::ChunkLength, 99, Some(b"prIv"));
+    match Error::from_io_error(&e) {
+        Some(Error::LimitExceeded { offset, .. }) => assert_eq!(*offset, Some(chunks[1].position)),
+        other => panic!("Got {:?}", other),
+    }
+    assert!(reader.read_chunk(&chunks[2]).is_ok());
+
+    let mut reader = PngStreamReader::from_stream(bytes.as_slice()).unwrap();
+    reader.limits = limits;
+    reader.next_chunk().unwrap();
+    let e = reader.next_chunk().unwrap_err();
+    assert_limit(&e, LimitKind::ChunkLength, 99, Some(b"prIv"));
+
+    let mut parser = PngPushParser::new();
+    parser.limits = limits;
+    let e = parser.push(&bytes).unwrap_err();
+    assert_limit(&e, LimitKind::ChunkLength, 99, Some(b"prIv"));
+
+    // Borrowing is fine, copying isn't
+    let mut png = PngSlice::new(&bytes).unwrap();
+    png.limits = limits;
+    let chunks = png.chunks().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(chunks[1].data.len(), 100);
+    let e = chunks[1].read_chunk(None, &Default::default()).unwrap_err();
+    assert_limit(&e, LimitKind::ChunkLength, 99, Some(b"prIv"));
+    let e = png
+        .chunk_at(&chunks[1].chunkref)
+        .unwrap()
+        .read_chunk(None, &Default::default())
+        .unwrap_err();
+    assert_limit(&e, LimitKind::ChunkLength, 99, Some(b"prIv"));
+
+    let mut reader = JngReader::from_stream(Cursor::new(common::jng())).unwrap();
+    reader.limits = with_limits(|l| l.max_chunk_length = 4);
+    let chunks = reader.scan_all_chunks().unwrap();
+    let jdat = chunks.iter().find(|c| c.chunktype == *b"JDAT").unwrap();
+    let e = reader.read_chunk(jdat).unwrap_err();
+    assert_limit(&e, LimitKind::ChunkLength, 4, Some(b"JDAT"));
+}
+
+#[test]
+fn chunk_count() {
+    let bytes = file();
+    let limits = with_limits(|l| l.max_chunks = 4);
+
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    reader.limits = limits;
+    let e = reader.scan_all_chunks().unwrap_err();
+    assert_limit(&e, LimitKind::ChunkCount, 4, Some(b"IEND"));
+
+    // Scanning the same chunks again doesn't count them twice
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    reader.limits = Limits {
+        max_chunks: 5,
+        ..limits
+    };
+    for _ in 0..3 {
+        reader.reset_next_chunk_position();
+        assert_eq!(reader.scan_all_chunks().unwrap().len(), 5);
+    }
+
+    let mut reader = PngStreamReader::from_stream(bytes.as_slice()).unwrap();
+    reader.limits = limits;
+    let e = reader.find_map(|c| c.err()).unwrap();
+    assert_limit(&e, LimitKind::ChunkCount, 4, Some(b"IEND"));
+
+    let mut parser = PngPushParser::new();
+    parser.limits = limits;
+    let e = parser.push(&bytes).unwrap_err();
+    assert_limit(&e, LimitKind::ChunkCount, 4, Some(b"IEND"));
+
+    let mut png = PngSlice::new(&bytes).unwrap();
+    png.limits = limits;
+    let results = png.chunks().collect::<Vec<_>>();
+    assert_eq!(results.len(), 5);
+    assert!(results[..4].iter().all(|c| c.is_ok()));
+    assert_limit(
+        results[4].as_ref().unwrap_err(),
+        LimitKind::ChunkCount,
+        4,
+        Some(b"IEND"),
+    );
+
+    let mut reader = JngReader::from_stream(Cursor::new(common::jng())).unwrap();
+    reader.limits = limits;
+    let e = reader.scan_all_chunks().unwrap_err();
+    assert_limit(&e, LimitKind::ChunkCount, 4, Some(b"JDAT"));
+}
+
+#[test]
+fn image_size() {
+    // 2 × 2 × 8 bits is 4 bytes
+    let bytes = file();
+    let limits = with_limits(|l| l.max_image_size = 3);
+
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    reader.limits = limits;
+    let e = reader.scan_header_chunks().unwrap_err();
+    assert_limit(&e, LimitKind::ImageSize, 3, Some(b"IHDR"));
+
+    let mut reader = PngStreamReader::from_stream(bytes.as_slice()).unwrap();
+    reader.limits = limits;
+    let e = reader.next_chunk().unwrap_err();
+    assert_limit(&e, LimitKind::ImageSize, 3, Some(b"IHDR"));
+
+    let mut parser = PngPushParser::new();
+    parser.limits = limits;
+    let e = parser.push(&bytes).unwrap_err();
+    assert_limit(&e, LimitKind::ImageSize, 3, Some(b"IHDR"));
+
+    // The default limit rejects huge headers before anything is allocated for them
+    let huge = common::png(100_000, 100_000, 6, 16, &[]);
+    let mut reader = PngReader::from_stream(Cursor::new(&huge)).unwrap();
+    let e = reader.scan_all_chunks().unwrap_err();
+    assert_limit(&e, LimitKind::ImageSize, 1 << 30, Some(b"IHDR"));
+
+    // 3 × 2 × 24 bits is 18 bytes
+    let mut reader = JngReader::from_stream(Cursor::new(common::jng())).unwrap();
+    reader.limits = with_limits(|l| l.max_image_size = 17);
+    let e = reader.scan_all_chunks().unwrap_err();
+    assert_limit(&e, LimitKind::ImageSize, 17, Some(b"JHDR"));
+
+    let mut reader = JngReader::from_stream(Cursor::new(common::jng())).unwrap();
+    reader.limits = with_limits(|l| l.max_image_size = 18);
+    assert!(reader.scan_all_chunks().is_ok());
+}
+
+#[test]
+fn text_size() {
+    let text = "x".repeat(1000);
+    let bytes = common::png(
+        1,
+        1,
+        0,
+        8,
+        &[
+            ztxt("Comment", &text),
+            ztxt("Comment", &text),
+            chunk(b"tEXt", b"Title\0Not compressed"),
+        ],
+    );
+
+    // The limit is shared by all of the chunks in the file
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    reader.limits = with_limits(|l| l.max_text_size = 1500);
+    let chunks = reader.scan_all_chunks().unwrap();
+    let first = reader.read_chunk(&chunks[1]).unwrap();
+    assert_eq!(reader.text_string(&first).unwrap(), Some(text.clone()));
+    let second = reader.read_chunk(&chunks[2]).unwrap();
+    let e = reader.text_string(&second).unwrap_err();
+    assert_limit(&e, LimitKind::TextSize, 1500, Some(b"zTXt"));
+
+    // Uncompressed text doesn't count
+    let title = reader.read_chunk(&chunks[3]).unwrap();
+    assert_eq!(
+        reader.text_string(&title).unwrap().as_deref(),
+        Some("Not compressed")
+    );
+    assert_eq!(reader.text_string(&PngChunkData::Iend).unwrap(), None);
+
+    let mut reader = PngStreamReader::from_stream(bytes.as_slice()).unwrap();
+    reader.limits = with_limits(|l| l.max_text_size = 999);
+    reader.next_chunk().unwrap();
+    let (_, chunk) = reader.next_chunk().unwrap().unwrap();
+    let e = reader.text_string(&chunk).unwrap_err();
+    assert_limit(&e, LimitKind::TextSize, 999, Some(b"zTXt"));
+
+    // The chunks' own methods take a limit, or have none
+    let PngChunkData::Ztxt(ztxt) = first else {
+        panic!("Read {:?}", first);
+    };
+    assert_eq!(ztxt.string().unwrap(), text);
+    assert_eq!(ztxt.string_with_limit(1000).unwrap(), text);
+    let e = ztxt.string_with_limit(999).unwrap_err();
+    assert_limit(&e, LimitKind::TextSize, 999, Some(b"zTXt"));
+
+    let itxt = Itxt::new("Comment", Some(PngCompressionMethod::Zlib), "", "", &text);
+    assert_eq!(itxt.string().unwrap(), text);
+    let e = itxt.string_with_limit(10).unwrap_err();
+    assert_limit(&e, LimitKind::TextSize, 10, Some(b"iTXt"));
+
+    // Corrupt data isn't a limit
+    let broken = Ztxt {
+        compressed_string: vec![0x78, 0x9c, 0xff, 0xff],
+        ..*ztxt
+    };
+    assert_eq!(broken.string(), None);
+    assert_eq!(
+        broken.string_with_limit(100).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn icc_profile_size() {
+    let profile = (0..2000).map(|i| i as u8).collect::<Vec<_>>();
+    let bytes = common::png(1, 1, 0, 8, &[iccp(&profile)]);
+
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    reader.limits = with_limits(|l| l.max_icc_profile_size = 1999);
+    let chunks = reader.scan_all_chunks().unwrap();
+    let chunk = reader.read_chunk(&chunks[1]).unwrap();
+    let e = reader.iccp_profile(&chunk).unwrap_err();
+    assert_limit(&e, LimitKind::IccProfileSize, 1999, Some(b"iCCP"));
+    assert_eq!(reader.iccp_profile(&PngChunkData::Iend).unwrap(), None);
+
+    reader.limits.max_icc_profile_size = 2000;
+    assert_eq!(reader.iccp_profile(&chunk).unwrap(), Some(profile.clone()));
+
+    let mut reader = PngStreamReader::from_stream(bytes.as_slice()).unwrap();
+    reader.limits = with_limits(|l| l.max_icc_profile_size = 1000);
+    reader.next_chunk().unwrap();
+    let (_, chunk) = reader.next_chunk().unwrap().unwrap();
+    let e = reader.iccp_profile(&chunk).unwrap_err();
+    assert_limit(&e, LimitKind::IccProfileSize, 1000, Some(b"iCCP"));
+
+    let PngChunkData::Iccp(iccp) = chunk else {
+        panic!("Read {:?}", chunk);
+    };
+    assert_eq!(iccp.profile().unwrap(), profile);
+    let e = iccp.profile_with_limit(0).unwrap_err();
+    assert_limit(&e, LimitKind::IccProfileSize, 0, Some(b"iCCP"));
+
+    let png = PngSlice::new(&bytes).unwrap();
+    let iccp_ref = png.chunks().nth(1).unwrap().unwrap().iccp().unwrap();
+    assert_eq!(iccp_ref.profile().unwrap(), profile);
+    let e = iccp_ref.profile_with_limit(1500).unwrap_err();
+    assert_limit(&e, LimitKind::IccProfileSize, 1500, Some(b"iCCP"));
+}
+
+/// A small zlib stream that inflates to far more than its size is stopped at the limit
+#[test]
+fn decompression_bomb() {
+    let zeros = vec![0; 4 << 20];
+    let mut data = b"Bomb\0\0".to_vec();
+    data.extend(common::zlib(&zeros));
+    assert!(data.len() < 8192);
+    let bytes = common::png(1, 1, 0, 8, &[chunk(b"zTXt", &data)]);
+
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    reader.limits = with_limits(|l| l.max_text_size = 1 << 20);
+    let chunks = reader.scan_all_chunks().unwrap();
+    let chunk = reader.read_chunk(&chunks[1]).unwrap();
+    let e = reader.text_string(&chunk).unwrap_err();
+    assert_limit(&e, LimitKind::TextSize, 1 << 20, Some(b"zTXt"));
+
+    reader.limits = Limits::unlimited();
+    assert_eq!(reader.text_string(&chunk).unwrap().unwrap().len(), 4 << 20);
+}
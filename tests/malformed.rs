@@ -0,0 +1,434 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Regression corpus of malformed chunk contents and damaged files
+//!
+//! Unless a test is about CRCs, each case has a valid CRC, so the parser itself must reject the
+//! contents with an [InvalidData](std::io::ErrorKind::InvalidData) error rather than panicking.
+
+mod common;
+
+use std::io::{Cursor, ErrorKind};
+
+use png_container::Error;
+use png_container::bodyreader::ImageDataReader;
+use png_container::chunks::*;
+use png_container::reader::PngReader;
+use png_container::recovery::Diagnostic;
+use png_container::registry::ChunkRegistry;
+use png_container::slice::PngSlice;
+use png_container::types::*;
+
+use common::chunk;
+
+/// Encode a PNG file from chunks, with an IHDR for a 1x1 image
+fn png(colour_type: u8, bit_depth: u8, chunks: &[Vec<u8>]) -> Vec<u8> {
+    common::png(1, 1, colour_type, bit_depth, chunks)
+}
+
+/// Parse a single chunk with read_contents() and return the error
+fn parse_err(chunktype: &[u8; 4], data: &[u8], ihdr: Option<&Ihdr>) -> std::io::Error {
+    let bytes = chunk(chunktype, data);
+    let mut stream = Cursor::new(&bytes);
+    let chunkref = PngChunkRef::from_stream(&mut stream).unwrap();
+    match chunkref.read_contents(&mut stream, ihdr, &ChunkRegistry::default()) {
+        Ok(chunk) => panic!(
+            "{} chunk {:?} parsed as {:?}",
+            chunkref.type_str(),
+            data,
+            chunk
+        ),
+        Err(e) => e,
+    }
+}
+
+/// Assert that a chunk is rejected as invalid data
+fn assert_invalid(chunktype: &[u8; 4], data: &[u8], ihdr: Option<&Ihdr>) {
+    let e = parse_err(chunktype, data, ihdr);
+    assert_eq!(
+        e.kind(),
+        ErrorKind::InvalidData,
+        "{:?} {:?}: {}",
+        chunktype,
+        data,
+        e
+    );
+}
+
+fn rgb_ihdr() -> Ihdr {
+    Ihdr::new(1, 1, 8, PngColourType::TrueColour, PngInterlaceMethod::None)
+}
+
+#[test]
+fn plte_partial_entry() {
+    assert_invalid(b"PLTE", &[1, 2, 3, 4], None);
+    assert_invalid(b"PLTE", &[1, 2], None);
+}
+
+#[test]
+fn text_missing_null() {
+    assert_invalid(b"tEXt", b"Comment", None);
+    assert_invalid(b"tEXt", b"", None);
+}
+
+#[test]
+fn ztxt_missing_null() {
+    assert_invalid(b"zTXt", b"Comment", None);
+    assert_invalid(b"zTXt", b"", None);
+}
+
+#[test]
+fn ztxt_missing_compression_method() {
+    assert_invalid(b"zTXt", b"Comment\0", None);
+}
+
+#[test]
+fn itxt_missing_null() {
+    assert_invalid(b"iTXt", b"Comment", None);
+}
+
+#[test]
+fn itxt_truncated_after_keyword() {
+    assert_invalid(b"iTXt", b"Comment\0", None);
+    assert_invalid(b"iTXt", b"Comment\0\0", None);
+    assert_invalid(b"iTXt", b"Comment\0\0\0", None);
+    assert_invalid(b"iTXt", b"Comment\0\0\0en", None);
+}
+
+#[test]
+fn idot_partial_segment() {
+    let mut data = 2_u32.to_be_bytes().to_vec();
+    data.extend_from_slice(&[0; 12]);
+    data.extend_from_slice(&[0; 8]);
+    assert_invalid(b"iDOT", &data, None);
+    assert_invalid(b"iDOT", &[0; 2], None);
+}
+
+#[test]
+fn fdat_short() {
+    assert_invalid(b"fdAT", &[], None);
+    assert_invalid(b"fdAT", &[0, 0, 1], None);
+}
+
+#[test]
+fn trns_short() {
+    let ihdr = rgb_ihdr();
+    assert_invalid(b"tRNS", &[0, 1, 0, 2], Some(&ihdr));
+
+    let grey = Ihdr::new(1, 1, 8, PngColourType::Greyscale, PngInterlaceMethod::None);
+    assert_invalid(b"tRNS", &[0], Some(&grey));
+}
+
+#[test]
+fn sbit_short() {
+    let ihdr = rgb_ihdr();
+    assert_invalid(b"sBIT", &[8, 8], Some(&ihdr));
+
+    let rgba = Ihdr::new(
+        1,
+        1,
+        8,
+        PngColourType::TrueColourAlpha,
+        PngInterlaceMethod::None,
+    );
+    assert_invalid(b"sBIT", &[8, 8, 8], Some(&rgba));
+}
+
+#[test]
+fn iccp_missing_null() {
+    assert_invalid(b"iCCP", b"Profile", None);
+}
+
+#[test]
+fn iccp_missing_compression_method() {
+    assert_invalid(b"iCCP", b"Profile\0", None);
+}
+
+#[test]
+fn splt_malformed() {
+    assert_invalid(b"sPLT", b"Palette", None);
+    assert_invalid(b"sPLT", b"Palette\0", None);
+    assert_invalid(b"sPLT", b"Palette\0\x07", None);
+    assert_invalid(b"sPLT", b"Palette\0\x08\x01\x02\x03", None);
+}
+
+#[test]
+fn pcal_malformed() {
+    assert_invalid(b"pCAL", b"Calibration", None);
+    assert_invalid(b"pCAL", b"Calibration\0\0\0\0\0", None);
+
+    // Two parameters declared, only one present
+    let mut data = b"Calibration\0".to_vec();
+    data.extend_from_slice(&0_i32.to_be_bytes());
+    data.extend_from_slice(&255_i32.to_be_bytes());
+    data.extend_from_slice(&[0, 2]);
+    data.extend_from_slice(b"m\x001.0");
+    assert_invalid(b"pCAL", &data, None);
+}
+
+#[test]
+fn scal_malformed() {
+    assert_invalid(b"sCAL", &[], None);
+    assert_invalid(b"sCAL", b"\x011.0", None);
+}
+
+#[test]
+fn gifx_short() {
+    assert_invalid(b"gIFx", b"NETSCAPE", None);
+    assert_invalid(b"gIFx", &[], None);
+}
+
+#[test]
+fn fixed_length_chunks_short() {
+    assert_invalid(b"IHDR", &[0; 12], None);
+    assert_invalid(b"gAMA", &[0; 3], None);
+    assert_invalid(b"cHRM", &[0; 31], None);
+    assert_invalid(b"sRGB", &[], None);
+    assert_invalid(b"pHYs", &[0; 8], None);
+    assert_invalid(b"tIME", &[0; 6], None);
+    assert_invalid(b"acTL", &[0; 7], None);
+    assert_invalid(b"fcTL", &[0; 25], None);
+    assert_invalid(b"oFFs", &[0; 8], None);
+}
+
+#[test]
+fn fixed_length_chunks_long() {
+    assert_invalid(b"gAMA", &[0; 5], None);
+    assert_invalid(b"sRGB", &[0; 2], None);
+}
+
+#[test]
+fn error_has_chunk_context() {
+    let e = parse_err(b"PLTE", &[1, 2], None);
+    assert!(matches!(
+        Error::from_io_error(&e),
+        Some(Error::InvalidFieldValue {
+            chunktype: [b'P', b'L', b'T', b'E'],
+            offset: 0,
+            ..
+        })
+    ));
+}
+
+/// tRNS, sBIT, and bKGD can't be parsed without the colour type from IHDR
+#[test]
+fn chunks_before_ihdr() {
+    for (chunktype, data) in [
+        (b"tRNS", &[0, 0][..]),
+        (b"sBIT", &[8][..]),
+        (b"bKGD", &[0, 0][..]),
+    ] {
+        let e = parse_err(chunktype, data, None);
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            Error::from_io_error(&e),
+            Some(&Error::OrderingViolation {
+                chunktype: *chunktype,
+                offset: 0,
+                message: "IHDR must go first".to_string(),
+            })
+        );
+    }
+}
+
+#[test]
+fn wrong_chunk_type() {
+    let bytes = png(0, 8, &[chunk(b"IDAT", &[0x78, 0x9c, 1, 2])]);
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let chunks = reader.scan_all_chunks().unwrap();
+
+    let e = chunks[1]
+        .read_fctl_fdat_sequence_number(&mut Cursor::new(&bytes))
+        .unwrap_err();
+    assert!(matches!(
+        Error::from_io_error(&e),
+        Some(Error::InvalidFieldValue {
+            chunktype: [b'I', b'D', b'A', b'T'],
+            offset: 33,
+            ..
+        })
+    ));
+
+    let mut image_data = ImageDataReader::new(Cursor::new(&bytes), vec![chunks[0]]);
+    let e = std::io::Read::read_to_end(&mut image_data, &mut Vec::new()).unwrap_err();
+    assert!(matches!(
+        Error::from_io_error(&e),
+        Some(Error::InvalidFieldValue {
+            chunktype: [b'I', b'H', b'D', b'R'],
+            offset: 8,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn crc_mismatch() {
+    let mut bytes = chunk(b"gAMA", &[0, 0, 0xb1, 0x8f]);
+    *bytes.last_mut().unwrap() ^= 0xff;
+    let mut stream = Cursor::new(&bytes);
+    let chunkref = PngChunkRef::from_stream(&mut stream).unwrap();
+    let e = chunkref
+        .read_contents(&mut stream, None, &ChunkRegistry::default())
+        .unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+    assert!(matches!(
+        Error::from_io_error(&e),
+        Some(Error::CrcMismatch {
+            chunktype: [b'g', b'A', b'M', b'A'],
+            offset: 0,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn reader_malformed_chunk() {
+    let bytes = png(3, 8, &[chunk(b"PLTE", &[1, 2, 3, 4, 5])]);
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    let chunks = reader.scan_all_chunks().unwrap();
+    let e = reader.read_chunk(&chunks[1]).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn slice_malformed_chunk() {
+    let bytes = png(0, 8, &[chunk(b"zTXt", b"Comment")]);
+    let slice = PngSlice::new(&bytes).unwrap();
+    let ztxt = slice.chunks().nth(1).unwrap().unwrap();
+    let e = ztxt
+        .read_chunk(None, &ChunkRegistry::default())
+        .unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn apng_truncated_fdat() {
+    let mut actl = 1_u32.to_be_bytes().to_vec();
+    actl.extend_from_slice(&0_u32.to_be_bytes());
+
+    let mut fctl = 0_u32.to_be_bytes().to_vec();
+    fctl.extend_from_slice(&1_u32.to_be_bytes());
+    fctl.extend_from_slice(&1_u32.to_be_bytes());
+    fctl.extend_from_slice(&[0; 8]);
+    fctl.extend_from_slice(&[0, 1, 0, 1, 0, 0]);
+
+    let bytes = png(
+        0,
+        8,
+        &[
+            chunk(b"acTL", &actl),
+            chunk(b"IDAT", &[]),
+            chunk(b"fcTL", &fctl),
+            chunk(b"fdAT", &[0, 0]),
+        ],
+    );
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    let e = reader.apng_scan_frames().unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn apng_huge_sequence_number() {
+    let mut actl = 1_u32.to_be_bytes().to_vec();
+    actl.extend_from_slice(&0_u32.to_be_bytes());
+
+    let mut fctl = u32::MAX.to_be_bytes().to_vec();
+    fctl.extend_from_slice(&1_u32.to_be_bytes());
+    fctl.extend_from_slice(&1_u32.to_be_bytes());
+    fctl.extend_from_slice(&[0; 8]);
+    fctl.extend_from_slice(&[0, 1, 0, 1, 0, 0]);
+
+    let bytes = png(
+        0,
+        8,
+        &[
+            chunk(b"acTL", &actl),
+            chunk(b"fcTL", &[0; 26]),
+            chunk(b"IDAT", &[]),
+            chunk(b"fcTL", &fctl),
+        ],
+    );
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    let frames = reader.apng_scan_frames().unwrap();
+    assert_eq!(frames.len(), 2);
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn decode_invalid_bit_depth() {
+    let ihdr = Ihdr::new(1, 1, 4, PngColourType::TrueColour, PngInterlaceMethod::None);
+    let e = png_container::decode::ScanlineDecoder::new(Cursor::new(Vec::new()), &ihdr)
+        .err()
+        .unwrap();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn decode_without_ihdr() {
+    let mut bytes = common::SIGNATURE.to_vec();
+    bytes.extend(chunk(b"IDAT", &common::zlib(&[0, 0])));
+    bytes.extend(chunk(b"IEND", &[]));
+
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let options =
+        png_container::image::DecodeOptions::new(png_container::image::PixelFormat::Gray8);
+    let e = reader.decode_image(&options).unwrap_err();
+    assert!(matches!(
+        Error::from_io_error(&e),
+        Some(Error::OrderingViolation {
+            chunktype: [b'I', b'H', b'D', b'R'],
+            offset: 8,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn recover_damaged_file() {
+    let gama = chunk(b"gAMA", &[0, 0, 0xb1, 0x8f]);
+    let mut bad_crc = chunk(b"tEXt", b"Title\0Damaged");
+    *bad_crc.last_mut().unwrap() ^= 0xff;
+
+    let mut bytes = png(0, 8, &[]);
+    bytes.truncate(bytes.len() - 12);
+    bytes.extend_from_slice(b"garbage");
+    bytes.extend(gama);
+    bytes.extend(bad_crc);
+    bytes.extend_from_slice(&chunk(b"IDAT", &[0; 16])[..10]);
+
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    assert!(reader.scan_all_chunks().is_err());
+
+    let recovered = reader.recover_chunks().unwrap();
+    let types: Vec<_> = recovered.chunks.iter().map(|c| c.chunktype).collect();
+    assert_eq!(types, [*b"IHDR", *b"gAMA", *b"tEXt"]);
+    assert!(matches!(
+        recovered.diagnostics.as_slice(),
+        [
+            Diagnostic::SkippedBytes {
+                offset: 33,
+                length: 7
+            },
+            Diagnostic::CrcMismatch { .. },
+            Diagnostic::TruncatedChunk { length: 16, .. },
+            Diagnostic::MissingIend { .. },
+        ]
+    ));
+    assert_eq!(reader.width, 1);
+}
@@ -0,0 +1,130 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Chunk ordering and multiplicity rules
+
+use png_container::chunks::PngChunkRef;
+use png_container::error::Error;
+use png_container::ordering::check_chunk_order;
+
+/// Chunk references for a sequence of chunk types, each with 4 bytes of data
+fn chunks(chunktypes: &[&[u8; 4]]) -> Vec<PngChunkRef> {
+    chunktypes
+        .iter()
+        .enumerate()
+        .map(|(i, chunktype)| PngChunkRef {
+            position: 8 + i as u64 * 16,
+            length: 4,
+            chunktype: **chunktype,
+        })
+        .collect()
+}
+
+/// Chunk types and offsets of the violations
+fn violations(chunktypes: &[&[u8; 4]]) -> Vec<([u8; 4], u64)> {
+    check_chunk_order(&chunks(chunktypes))
+        .iter()
+        .map(|e| (e.chunktype().unwrap(), e.offset().unwrap()))
+        .collect()
+}
+
+#[test]
+fn valid_order() {
+    assert!(
+        violations(&[
+            b"IHDR", b"gAMA", b"PLTE", b"tRNS", b"tEXt", b"IDAT", b"IDAT", b"tIME", b"IEND"
+        ])
+        .is_empty()
+    );
+    assert!(
+        violations(&[
+            b"IHDR", b"acTL", b"fcTL", b"IDAT", b"fcTL", b"fdAT", b"fdAT", b"IEND"
+        ])
+        .is_empty()
+    );
+}
+
+#[test]
+fn ihdr_first() {
+    assert_eq!(
+        violations(&[b"gAMA", b"IHDR", b"IDAT", b"IEND"]),
+        [(*b"gAMA", 8)]
+    );
+    assert_eq!(violations(&[b"IDAT", b"IEND"]), [(*b"IDAT", 8)]);
+}
+
+#[test]
+fn plte_before_dependent_chunks() {
+    assert_eq!(
+        violations(&[b"IHDR", b"tRNS", b"PLTE", b"IDAT", b"IEND"]),
+        [(*b"PLTE", 40)]
+    );
+    assert_eq!(
+        violations(&[b"IHDR", b"PLTE", b"gAMA", b"IDAT", b"IEND"]),
+        [(*b"gAMA", 40)]
+    );
+}
+
+#[test]
+fn before_idat() {
+    assert_eq!(
+        violations(&[b"IHDR", b"IDAT", b"PLTE", b"pHYs", b"IEND"]),
+        [(*b"PLTE", 40), (*b"pHYs", 56)]
+    );
+}
+
+#[test]
+fn consecutive_idats() {
+    assert_eq!(
+        violations(&[b"IHDR", b"IDAT", b"tEXt", b"IDAT", b"IEND"]),
+        [(*b"IDAT", 56)]
+    );
+}
+
+#[test]
+fn single_instance() {
+    assert_eq!(
+        violations(&[
+            b"IHDR", b"pHYs", b"pHYs", b"IDAT", b"tIME", b"tIME", b"IEND"
+        ]),
+        [(*b"pHYs", 40), (*b"tIME", 88)]
+    );
+    assert!(violations(&[b"IHDR", b"sPLT", b"sPLT", b"IDAT", b"IEND"]).is_empty());
+}
+
+#[test]
+fn fdat_after_idat() {
+    assert_eq!(
+        violations(&[b"IHDR", b"acTL", b"fcTL", b"fdAT", b"IDAT", b"IEND"]),
+        [(*b"fdAT", 56)]
+    );
+}
+
+#[test]
+fn iend_last() {
+    assert_eq!(
+        violations(&[b"IHDR", b"IDAT", b"IEND", b"tEXt"]),
+        [(*b"tEXt", 56)]
+    );
+
+    let missing = check_chunk_order(&chunks(&[b"IHDR", b"IDAT"]));
+    assert!(matches!(
+        missing.as_slice(),
+        [Error::OrderingViolation { offset: 40, .. }]
+    ));
+}
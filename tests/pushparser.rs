@@ -0,0 +1,297 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Incremental parsing with PngPushParser
+
+mod common;
+
+use std::io::ErrorKind;
+
+use png_container::error::Error;
+use png_container::limits::{LimitKind, Limits};
+use png_container::pushparser::{PngEvent, PngPushParser};
+
+/// Push a file in pieces of the given sizes, repeating the last size until it's all pushed
+///
+/// Consecutive data events from the same chunk are merged, so that the events don't depend on
+/// how the file was split up. Each event is returned in its Debug form.
+fn events(bytes: &[u8], sizes: &[usize]) -> Vec<String> {
+    let mut parser = PngPushParser::new();
+    let mut events = Vec::new();
+    let mut data = bytes;
+    let mut sizes = sizes.iter().copied();
+    let mut size = 0;
+    while !data.is_empty() {
+        size = sizes.next().unwrap_or(size);
+        let (piece, rest) = data.split_at(size.min(data.len()));
+        events.extend(parser.push(piece).unwrap());
+        data = rest;
+    }
+    assert!(parser.is_finished());
+    assert_eq!(parser.bytes_needed(), 0);
+    assert_eq!(parser.position(), bytes.len() as u64);
+
+    let mut merged: Vec<PngEvent> = Vec::new();
+    for event in events {
+        match (merged.last_mut(), event) {
+            (Some(PngEvent::ImageData(data)), PngEvent::ImageData(more)) => {
+                data.extend(more);
+            }
+            (Some(PngEvent::FrameData(seq, data)), PngEvent::FrameData(more_seq, more))
+                if *seq == more_seq =>
+            {
+                data.extend(more);
+            }
+            (_, event) => merged.push(event),
+        }
+    }
+
+    merged.iter().map(|e| format!("{:?}", e)).collect()
+}
+
+#[test]
+fn whole_file() {
+    let bytes = common::apng();
+    let mut parser = PngPushParser::new();
+    let events = parser.push(&bytes).unwrap();
+    assert!(parser.is_finished());
+
+    let mut image_data = Vec::new();
+    let mut frame_data = Vec::new();
+    let mut kinds = Vec::new();
+    for event in &events {
+        kinds.push(match event {
+            PngEvent::Signature => "Signature".to_string(),
+            PngEvent::Ihdr(_, ihdr) => {
+                assert_eq!((ihdr.width, ihdr.height), (2, 2));
+                "IHDR".to_string()
+            }
+            PngEvent::ImageData(data) => {
+                image_data.extend_from_slice(data);
+                "ImageData".to_string()
+            }
+            PngEvent::ImageDataComplete(chunkref) => {
+                assert_eq!(chunkref.chunktype, *b"IDAT");
+                "ImageDataComplete".to_string()
+            }
+            PngEvent::FrameData(seq, data) => {
+                frame_data.extend_from_slice(data);
+                format!("FrameData {}", seq)
+            }
+            PngEvent::FrameDataComplete(chunkref) => {
+                assert_eq!(chunkref.chunktype, *b"fdAT");
+                "FrameDataComplete".to_string()
+            }
+            PngEvent::Chunk(chunkref, chunk) => {
+                assert_eq!(chunkref.chunktype, chunk.chunktype());
+                chunkref.type_str().to_string()
+            }
+            PngEvent::End(chunkref) => {
+                assert_eq!(chunkref.position + 12, bytes.len() as u64);
+                "End".to_string()
+            }
+        });
+    }
+
+    assert_eq!(
+        kinds,
+        [
+            "Signature",
+            "IHDR",
+            "acTL",
+            "fcTL",
+            "ImageData",
+            "ImageDataComplete",
+            "ImageData",
+            "ImageDataComplete",
+            "fcTL",
+            "FrameData 2",
+            "FrameDataComplete",
+            "FrameData 3",
+            "FrameDataComplete",
+            "tEXt",
+            "End",
+        ]
+    );
+    assert_eq!(image_data, common::zlib(&common::APNG_FRAMES[0]));
+    assert_eq!(frame_data, common::zlib(&common::APNG_FRAMES[1]));
+}
+
+#[test]
+fn split_pushes() {
+    let bytes = common::apng();
+    let whole = events(&bytes, &[bytes.len()]);
+    assert_eq!(events(&bytes, &[1]), whole);
+    assert_eq!(events(&bytes, &[3, 7]), whole);
+    assert_eq!(events(&bytes, &[13]), whole);
+    assert_eq!(events(&bytes, &[8, 4, 4, 1, 29]), whole);
+}
+
+#[test]
+fn bytes_needed() {
+    let bytes = common::apng();
+    let mut parser = PngPushParser::new();
+    assert_eq!(parser.bytes_needed(), 8);
+    parser.push(&bytes[..5]).unwrap();
+    assert_eq!(parser.bytes_needed(), 3);
+    parser.push(&bytes[5..8]).unwrap();
+    assert_eq!(parser.bytes_needed(), 8);
+
+    // IHDR contents and CRC
+    parser.push(&bytes[8..16]).unwrap();
+    assert_eq!(parser.bytes_needed(), 13 + 4);
+}
+
+#[test]
+fn ignores_trailing_data() {
+    let mut bytes = common::apng();
+    let whole = events(&bytes, &[bytes.len()]);
+    bytes.extend_from_slice(b"trailing garbage");
+
+    let mut parser = PngPushParser::new();
+    let events = parser.push(&bytes).unwrap();
+    assert_eq!(events.len(), whole.len());
+    assert!(parser.is_finished());
+}
+
+#[test]
+fn bad_signature() {
+    let mut bytes = common::apng();
+    bytes[1] = b'p';
+    let mut parser = PngPushParser::new();
+    let e = parser.push(&bytes).unwrap_err();
+    assert!(matches!(
+        Error::from_io_error(&e),
+        Some(Error::BadSignature { offset: 0 })
+    ));
+    assert!(parser.is_finished());
+}
+
+#[test]
+fn bad_image_data_crc() {
+    let mut bytes = common::apng();
+    let idat = bytes.windows(4).position(|w| w == b"IDAT").unwrap();
+    bytes[idat + 4] ^= 0xff;
+
+    let mut parser = PngPushParser::new();
+    let e = parser.push(&bytes).unwrap_err();
+    match Error::from_io_error(&e) {
+        Some(Error::CrcMismatch { chunktype, .. }) => assert_eq!(chunktype, b"IDAT"),
+        e => panic!("{:?}", e),
+    }
+}
+
+#[test]
+fn huge_chunk_is_rejected() {
+    let bytes = common::apng();
+    let mut parser = PngPushParser::new();
+    let text = bytes.windows(4).position(|w| w == b"tEXt").unwrap() - 4;
+    parser.push(&bytes[..text]).unwrap();
+
+    // A tEXt chunk claiming to be 2 GiB long is rejected as soon as its header arrives
+    let mut header = 0x8000_0000_u32.to_be_bytes().to_vec();
+    header.extend_from_slice(b"tEXt");
+    let e = parser.push(&header).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::QuotaExceeded);
+    match Error::from_io_error(&e) {
+        Some(Error::LimitExceeded {
+            chunktype, limit, ..
+        }) => {
+            assert_eq!(*chunktype, Some(*b"tEXt"));
+            assert_eq!(limit.kind, LimitKind::ChunkLength);
+        }
+        e => panic!("{:?}", e),
+    }
+}
+
+#[test]
+fn image_data_is_not_limited() {
+    let bytes = common::png(
+        1,
+        1,
+        0,
+        8,
+        &[
+            common::fctl(0, 1, 1),
+            common::chunk(b"IDAT", &[0; 1000]),
+            common::fctl(1, 1, 1),
+            common::fdat(2, &[0; 1000]),
+        ],
+    );
+
+    // Only the IHDR and fcTL chunks are buffered
+    let mut parser = PngPushParser::new();
+    parser.limits.max_chunk_length = 26;
+    let events = parser.push(&bytes).unwrap();
+    assert!(parser.is_finished());
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| matches!(
+                e,
+                PngEvent::ImageDataComplete(_) | PngEvent::FrameDataComplete(_)
+            ))
+            .count(),
+        2
+    );
+
+    let mut parser = PngPushParser::new();
+    parser.limits = Limits {
+        max_chunk_length: 25,
+        ..Limits::default()
+    };
+    let e = parser.push(&bytes).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::QuotaExceeded);
+}
+
+#[test]
+fn short_fdat() {
+    let bytes = common::png(1, 1, 0, 8, &[common::chunk(b"fdAT", &[0, 0])]);
+    let mut parser = PngPushParser::new();
+    let e = parser.push(&bytes).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn ihdr_must_be_first() {
+    for first in [
+        common::chunk(b"gAMA", &45455_u32.to_be_bytes()),
+        common::chunk(b"IDAT", &[0x78, 0x9c]),
+    ] {
+        let mut bytes = common::SIGNATURE.to_vec();
+        bytes.extend(first);
+        let mut parser = PngPushParser::new();
+        let e = parser.push(&bytes).unwrap_err();
+        assert!(matches!(
+            Error::from_io_error(&e),
+            Some(Error::OrderingViolation { offset: 8, .. })
+        ));
+        assert!(parser.is_finished());
+    }
+}
+
+#[test]
+fn jng_chunks_are_rejected() {
+    let bytes = common::png(1, 1, 0, 8, &[common::chunk(b"JDAT", &[0xff, 0xd8])]);
+    let mut parser = PngPushParser::new();
+    let e = parser.push(&bytes).unwrap_err();
+    assert!(matches!(
+        Error::from_io_error(&e),
+        Some(Error::DisallowedChunk { chunktype, offset: 33, .. }) if chunktype == b"JDAT"
+    ));
+}
@@ -0,0 +1,219 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! User-defined chunk types
+
+use std::io::{Cursor, Read, Write};
+
+use png_container::chunks::*;
+use png_container::crc::CRC;
+use png_container::document::PngDocument;
+use png_container::reader::PngReader;
+use png_container::registry::{ChunkCodec, ChunkRegistry};
+use png_container::types::*;
+
+/// A private chunk holding a big-endian counter
+#[derive(Clone, Debug, PartialEq)]
+struct Counter(u32);
+
+impl ChunkCodec for Counter {
+    const TYPE: [u8; 4] = *b"ctRr";
+
+    fn from_contents_stream<R>(
+        stream: &mut R,
+        length: u32,
+        data_crc: Option<&mut CRC>,
+    ) -> std::io::Result<Self>
+    where
+        R: Read,
+    {
+        if length != 4 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Counter chunk is not 4 bytes",
+            ));
+        }
+
+        let mut data = [0_u8; 4];
+        stream.read_exact(&mut data)?;
+        if let Some(data_crc) = data_crc {
+            data_crc.consume(&data);
+        }
+
+        Ok(Self(u32::from_be_bytes(data)))
+    }
+
+    fn length(&self) -> u32 {
+        4
+    }
+
+    fn write_contents<W>(&self, stream: &mut W, data_crc: Option<&mut CRC>) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        let data = self.0.to_be_bytes();
+        stream.write_all(&data)?;
+        if let Some(data_crc) = data_crc {
+            data_crc.consume(&data);
+        }
+
+        Ok(())
+    }
+}
+
+/// Raw gAMA chunk, overriding the built-in type
+#[derive(Clone, Debug, PartialEq)]
+struct RawGamma(Vec<u8>);
+
+impl ChunkCodec for RawGamma {
+    const TYPE: [u8; 4] = *b"gAMA";
+
+    fn from_contents_stream<R>(
+        stream: &mut R,
+        length: u32,
+        data_crc: Option<&mut CRC>,
+    ) -> std::io::Result<Self>
+    where
+        R: Read,
+    {
+        let mut data = vec![0_u8; length as usize];
+        stream.read_exact(&mut data)?;
+        if let Some(data_crc) = data_crc {
+            data_crc.consume(&data);
+        }
+
+        Ok(Self(data))
+    }
+
+    fn length(&self) -> u32 {
+        self.0.len() as u32
+    }
+
+    fn write_contents<W>(&self, stream: &mut W, data_crc: Option<&mut CRC>) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        stream.write_all(&self.0)?;
+        if let Some(data_crc) = data_crc {
+            data_crc.consume(&self.0);
+        }
+
+        Ok(())
+    }
+}
+
+/// A file with a gAMA chunk and a Counter chunk
+fn source() -> Vec<u8> {
+    let mut doc = PngDocument::new(Ihdr::new(
+        1,
+        1,
+        8,
+        PngColourType::Greyscale,
+        PngInterlaceMethod::None,
+    ));
+    doc.set_image_data(vec![0x78, 0x9c, 1, 2, 3, 4]);
+    doc.set_gama(Gama::new(0.45455));
+    doc.insert(PngChunkData::new_custom(Counter(1234)));
+    doc.write_to(Vec::new()).unwrap()
+}
+
+/// Read every chunk of a file of a given type with a registry
+fn read_chunks(bytes: &[u8], registry: &ChunkRegistry, chunktype: [u8; 4]) -> Vec<PngChunkData> {
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    reader.registry = registry.clone();
+    let chunks = reader.scan_chunks_filtered(|ct| ct == chunktype).unwrap();
+    chunks
+        .iter()
+        .map(|c| reader.read_chunk(c).unwrap())
+        .collect()
+}
+
+#[test]
+fn register_and_parse() {
+    let bytes = source();
+
+    // Unregistered chunks are kept as raw bytes
+    let chunks = read_chunks(&bytes, &ChunkRegistry::new(), *b"ctRr");
+    match &chunks[..] {
+        [PngChunkData::Unknown { chunktype, data }] => {
+            assert_eq!(chunktype, b"ctRr");
+            assert_eq!(data, &1234_u32.to_be_bytes());
+        }
+        chunks => panic!("Read {:?}", chunks),
+    }
+
+    let mut registry = ChunkRegistry::new();
+    registry.register::<Counter>();
+    assert!(registry.is_registered(*b"ctRr"));
+    let chunks = read_chunks(&bytes, &registry, *b"ctRr");
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].custom::<Counter>(), Some(&Counter(1234)));
+    assert_eq!(chunks[0].custom::<RawGamma>(), None);
+    assert_eq!(chunks[0].chunktype(), *b"ctRr");
+}
+
+#[test]
+fn override_built_in() {
+    let bytes = source();
+    let mut registry = ChunkRegistry::new();
+    registry.register::<RawGamma>();
+
+    let chunks = read_chunks(&bytes, &registry, *b"gAMA");
+    assert_eq!(
+        chunks[0].custom::<RawGamma>(),
+        Some(&RawGamma(45455_u32.to_be_bytes().to_vec()))
+    );
+    assert_eq!(chunks[0].gama_gamma(), None);
+}
+
+#[test]
+fn unregister() {
+    let bytes = source();
+    let mut registry = ChunkRegistry::new();
+    registry.register::<RawGamma>();
+    registry.register::<Counter>();
+    registry.unregister(*b"gAMA");
+    assert!(!registry.is_registered(*b"gAMA"));
+    assert!(registry.is_registered(*b"ctRr"));
+
+    let chunks = read_chunks(&bytes, &registry, *b"gAMA");
+    assert_eq!(chunks[0].gama_gamma(), Some(0.45455));
+}
+
+#[test]
+fn custom_round_trip() {
+    let chunk = PngChunkData::new_custom(Counter(0xdeadbeef));
+    assert_eq!(chunk.length(), 4);
+
+    let mut stream = Cursor::new(Vec::new());
+    let chunkref = chunk.to_stream(&mut stream).unwrap();
+    assert_eq!(chunkref.chunktype, *b"ctRr");
+    assert_eq!(chunkref.length, 4);
+
+    let mut registry = ChunkRegistry::new();
+    registry.register::<Counter>();
+    stream.set_position(0);
+    let chunkref = PngChunkRef::from_stream(&mut stream).unwrap();
+    let read = chunkref
+        .read_chunk_with_registry(&mut stream, None, &registry)
+        .unwrap();
+    assert_eq!(read.custom::<Counter>(), Some(&Counter(0xdeadbeef)));
+
+    // Cloning keeps the concrete type
+    assert_eq!(read.clone().custom::<Counter>(), Some(&Counter(0xdeadbeef)));
+}
@@ -0,0 +1,293 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Lossless rewriting of chunks
+
+use std::io::{Cursor, ErrorKind};
+
+use png_container::chunks::*;
+use png_container::document::PngDocument;
+use png_container::reader::PngReader;
+use png_container::rewrite::{ChunkSelector, RewriteOp};
+use png_container::types::*;
+
+/// A greyscale file with some metadata, and a private chunk with a bad CRC
+fn source() -> Vec<u8> {
+    let mut doc = PngDocument::new(Ihdr::new(
+        2,
+        2,
+        8,
+        PngColourType::Greyscale,
+        PngInterlaceMethod::None,
+    ));
+    doc.set_image_data(vec![0x78, 0x9c, 1, 2, 3, 4, 5, 6]);
+    doc.set_gama(Gama::new(0.45455));
+    doc.add_text(Text::new("Title", "Old").into());
+    doc.add_text(Text::new("Author", "Someone").into());
+    doc.insert(PngChunkData::Unknown {
+        chunktype: *b"prIv",
+        data: vec![1, 2, 3],
+    });
+
+    let mut bytes = doc.write_to(Vec::new()).unwrap();
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let private = reader.scan_chunks_filtered(|ct| ct == *b"prIv").unwrap()[0];
+    let crc = (private.position + 4 + 4 + 3) as usize;
+    bytes[crc] ^= 0xff;
+
+    bytes
+}
+
+/// Raw bytes of each chunk, with its type
+fn raw_chunks(bytes: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    reader
+        .scan_all_chunks()
+        .unwrap()
+        .iter()
+        .map(|c| {
+            let start = c.position as usize;
+            let end = start + 4 + 4 + c.length as usize + 4;
+            (c.chunktype, bytes[start..end].to_vec())
+        })
+        .collect()
+}
+
+fn rewrite(bytes: &[u8], ops: &[RewriteOp]) -> Result<Vec<u8>, std::io::Error> {
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    let mut out = Vec::new();
+    let written = reader.rewrite(ops, &mut out)?;
+
+    let scanned = raw_chunks(&out);
+    assert_eq!(written.len(), scanned.len());
+    for (w, (chunktype, _)) in written.iter().zip(&scanned) {
+        assert_eq!(w.chunktype, *chunktype);
+    }
+
+    Ok(out)
+}
+
+#[test]
+fn untouched_chunks_are_copied() {
+    let bytes = source();
+    let out = rewrite(
+        &bytes,
+        &[
+            RewriteOp::Replace(
+                ChunkSelector::Keyword("Title".to_string()),
+                Text::new("Title", "New").into(),
+            ),
+            RewriteOp::Replace(
+                ChunkSelector::Type(*b"pHYs"),
+                Phys {
+                    x_pixels_per_unit: 2835,
+                    y_pixels_per_unit: 2835,
+                    unit: PngUnitType::Metre,
+                }
+                .into(),
+            ),
+            RewriteOp::Delete(ChunkSelector::Type(*b"gAMA")),
+        ],
+    )
+    .unwrap();
+
+    let before = raw_chunks(&bytes);
+    let after = raw_chunks(&out);
+    assert_eq!(
+        after.iter().map(|(ct, _)| *ct).collect::<Vec<_>>(),
+        [
+            *b"IHDR", *b"pHYs", *b"IDAT", *b"tEXt", *b"tEXt", *b"prIv", *b"IEND"
+        ]
+    );
+
+    // Everything except the new chunks is identical, including the bad CRC
+    for chunktype in [b"IHDR", b"IDAT", b"prIv", b"IEND"] {
+        let find = |chunks: &[([u8; 4], Vec<u8>)]| {
+            chunks
+                .iter()
+                .find(|(ct, _)| ct == chunktype)
+                .unwrap()
+                .1
+                .clone()
+        };
+        assert_eq!(find(&before), find(&after));
+    }
+    assert_eq!(after[4], before[4]);
+
+    let mut reader = PngReader::from_stream(Cursor::new(&out)).unwrap();
+    let title = reader.scan_chunks_filtered(|ct| ct == *b"tEXt").unwrap()[0];
+    match reader.read_chunk(&title).unwrap() {
+        PngChunkData::Text(text) => assert_eq!((&*text.keyword, &*text.string), ("Title", "New")),
+        chunk => panic!("Read {:?}", chunk),
+    }
+}
+
+#[test]
+fn delete_by_predicate() {
+    let out = rewrite(
+        &source(),
+        &[RewriteOp::Delete(ChunkSelector::Predicate(Box::new(
+            |c: &PngChunkRef| c.is_ancillary(),
+        )))],
+    )
+    .unwrap();
+
+    assert_eq!(
+        raw_chunks(&out)
+            .iter()
+            .map(|(ct, _)| *ct)
+            .collect::<Vec<_>>(),
+        [*b"IHDR", *b"IDAT", *b"IEND"]
+    );
+}
+
+#[test]
+fn insert() {
+    let out = rewrite(
+        &source(),
+        &[
+            RewriteOp::Insert(
+                Srgb {
+                    rendering_intent: PngRenderingIntent::Perceptual,
+                }
+                .into(),
+            ),
+            RewriteOp::Insert(Text::new("Comment", "Added").into()),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(
+        raw_chunks(&out)
+            .iter()
+            .map(|(ct, _)| *ct)
+            .collect::<Vec<_>>(),
+        [
+            *b"IHDR", *b"gAMA", *b"sRGB", *b"IDAT", *b"tEXt", *b"tEXt", *b"prIv", *b"tEXt",
+            *b"IEND"
+        ]
+    );
+
+    let err = rewrite(&source(), &[RewriteOp::Insert(PngChunkData::Iend)]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+/// PLTE goes before the chunks that must follow it
+#[test]
+fn insert_plte() {
+    let mut doc = PngDocument::new(Ihdr::new(
+        2,
+        2,
+        8,
+        PngColourType::TrueColour,
+        PngInterlaceMethod::None,
+    ));
+    doc.set_image_data(vec![0x78, 0x9c, 1, 2, 3, 4]);
+    doc.set_gama(Gama::new(0.45455));
+    doc.set_trns(Trns::TrueColour {
+        red: 1,
+        green: 2,
+        blue: 3,
+    });
+    doc.set_bkgd(Bkgd::TrueColour {
+        red: 4,
+        green: 5,
+        blue: 6,
+    });
+    doc.set_phys(Phys {
+        x_pixels_per_unit: 2835,
+        y_pixels_per_unit: 2835,
+        unit: PngUnitType::Metre,
+    });
+    let bytes = doc.write_to(Vec::new()).unwrap();
+
+    let out = rewrite(
+        &bytes,
+        &[RewriteOp::Insert(
+            Plte::new(&[PngPaletteEntry {
+                red: 1,
+                green: 2,
+                blue: 3,
+            }])
+            .into(),
+        )],
+    )
+    .unwrap();
+    let types = raw_chunks(&out)
+        .iter()
+        .map(|(ct, _)| *ct)
+        .collect::<Vec<_>>();
+    let index = |chunktype: &[u8; 4]| types.iter().position(|ct| ct == chunktype).unwrap();
+    assert_eq!(index(b"PLTE"), index(b"gAMA") + 1);
+    assert!(index(b"PLTE") < index(b"tRNS"));
+    assert!(index(b"PLTE") < index(b"bKGD"));
+    assert!(index(b"PLTE") < index(b"IDAT"));
+
+    let mut reader = PngReader::from_stream(Cursor::new(&out)).unwrap();
+    let chunks = reader.scan_all_chunks().unwrap();
+    assert!(png_container::ordering::check_chunk_order(&chunks).is_empty());
+}
+
+#[test]
+fn unsafe_chunks_after_critical_change() {
+    let mut doc = PngDocument::new(Ihdr::new(
+        2,
+        2,
+        8,
+        PngColourType::Greyscale,
+        PngInterlaceMethod::None,
+    ));
+    doc.set_image_data(vec![0x78, 0x9c, 1, 2, 3, 4]);
+    doc.set_gama(Gama::new(0.45455));
+    for chunktype in [*b"prIv", *b"prIV"] {
+        doc.insert(PngChunkData::Unknown {
+            chunktype,
+            data: vec![1],
+        });
+    }
+    let bytes = doc.write_to(Vec::new()).unwrap();
+
+    let types = |out: &[u8]| {
+        raw_chunks(out)
+            .iter()
+            .map(|(ct, _)| *ct)
+            .collect::<Vec<_>>()
+    };
+
+    // Changing ancillary chunks keeps everything else
+    let out = rewrite(&bytes, &[RewriteOp::Delete(ChunkSelector::Type(*b"gAMA"))]).unwrap();
+    assert_eq!(
+        types(&out),
+        [*b"IHDR", *b"IDAT", *b"prIv", *b"prIV", *b"IEND"]
+    );
+
+    // Changing critical chunks deletes the unrecognised unsafe-to-copy chunk
+    let ihdr = Ihdr::new(2, 2, 16, PngColourType::Greyscale, PngInterlaceMethod::None);
+    let out = rewrite(
+        &bytes,
+        &[RewriteOp::Replace(
+            ChunkSelector::Type(*b"IHDR"),
+            ihdr.into(),
+        )],
+    )
+    .unwrap();
+    assert_eq!(
+        types(&out),
+        [*b"IHDR", *b"gAMA", *b"IDAT", *b"prIv", *b"IEND"]
+    );
+}
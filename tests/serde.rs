@@ -0,0 +1,161 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Serializing chunks with the serde feature
+
+#![cfg(feature = "serde")]
+
+use serde_json::json;
+
+use png_container::chunks::*;
+use png_container::types::*;
+
+/// Serialize a chunk to JSON and back again
+fn round_trip(chunk: &PngChunkData) -> (serde_json::Value, PngChunkData) {
+    let value = serde_json::to_value(chunk).unwrap();
+    let chunk = serde_json::from_value(value.clone()).unwrap();
+    (value, chunk)
+}
+
+#[test]
+fn header() {
+    let ihdr = Ihdr::new(
+        640,
+        480,
+        8,
+        PngColourType::TrueColourAlpha,
+        PngInterlaceMethod::Adam7,
+    );
+    let (value, chunk) = round_trip(&PngChunkData::Ihdr(ihdr));
+    assert_eq!(value["Ihdr"]["width"], 640);
+    assert_eq!(value["Ihdr"]["colour_type"], "TrueColourAlpha");
+    assert_eq!(value["Ihdr"]["interlace_method"], "Adam7");
+    let PngChunkData::Ihdr(ihdr) = chunk else {
+        panic!("Not an IHDR chunk: {:?}", chunk);
+    };
+    assert_eq!(ihdr.height, 480);
+}
+
+#[test]
+fn decoded_values() {
+    let (value, chunk) = round_trip(&Gama::new(0.45455).into());
+    assert_eq!(
+        value["Gama"],
+        json!({"gamma": 45455, "gamma_value": 0.45455})
+    );
+    assert_eq!(chunk.gama_gamma(), Some(0.45455));
+
+    let (value, _) = round_trip(&Time::new(2025, 1, 2, 3, 4, 5).into());
+    assert_eq!(value["Time"]["time"], "2025-01-02T03:04:05+00:00");
+
+    let mut fctl = PngChunkData::Fctl(Box::new(Fctl {
+        sequence_number: 0,
+        width: 1,
+        height: 1,
+        x_offset: 0,
+        y_offset: 0,
+        delay_num: 1,
+        delay_den: 4,
+        dispose_op: ApngDisposalOperator::Background,
+        blend_op: ApngBlendOperator::Over,
+    }));
+    let (value, _) = round_trip(&fctl);
+    assert_eq!(value["Fctl"]["delay_value"], 0.25);
+    assert_eq!(value["Fctl"]["dispose_op"], "Background");
+    if let PngChunkData::Fctl(fctl) = &mut fctl {
+        fctl.delay_num = 3;
+    }
+    let (_, chunk) = round_trip(&fctl);
+    assert_eq!(
+        chunk.fctl_delay().unwrap().get::<uom::si::time::second>(),
+        0.75
+    );
+
+    // A denominator of 0 means 100, rather than an infinite delay
+    if let PngChunkData::Fctl(fctl) = &mut fctl {
+        fctl.delay_den = 0;
+    }
+    let (value, _) = round_trip(&fctl);
+    assert_eq!(value["Fctl"]["delay_value"], 0.03);
+}
+
+#[test]
+fn text() {
+    let (value, chunk) =
+        round_trip(&Ztxt::new("Comment", PngCompressionMethod::Zlib, "Hello, world").into());
+    assert_eq!(value["Ztxt"]["keyword"], "Comment");
+    assert_eq!(value["Ztxt"]["string"], "Hello, world");
+    assert!(value["Ztxt"]["compressed_string"].is_string());
+    assert_eq!(chunk.ztxt_string().as_deref(), Some("Hello, world"));
+
+    let (value, chunk) = round_trip(&Itxt::new("Title", None, "el", "Τίτλος", "Ωμέγα").into());
+    assert_eq!(value["Itxt"]["compression_method"], json!(null));
+    assert_eq!(value["Itxt"]["string"], "Ωμέγα");
+    assert_eq!(chunk.itxt_string().as_deref(), Some("Ωμέγα"));
+}
+
+#[test]
+fn binary_payloads() {
+    let (value, chunk) = round_trip(&PngChunkData::Idat(Box::new(Idat(vec![1, 2, 3, 4]))));
+    assert_eq!(value["Idat"], "AQIDBA==");
+    assert_eq!(
+        chunk.dat_data_iter().unwrap().copied().collect::<Vec<u8>>(),
+        [1, 2, 3, 4]
+    );
+
+    let (value, chunk) = round_trip(&PngChunkData::Unknown {
+        chunktype: *b"prIv",
+        data: vec![0xff],
+    });
+    assert_eq!(
+        value["Unknown"],
+        json!({"chunktype": "prIv", "data": "/w=="})
+    );
+    assert_eq!(chunk.chunktype(), *b"prIv");
+
+    let value = json!({"Unknown": {"chunktype": "toolong", "data": ""}});
+    assert!(serde_json::from_value::<PngChunkData>(value).is_err());
+}
+
+#[test]
+fn chunk_ref() {
+    let chunkref = PngChunkRef {
+        position: 33,
+        length: 4,
+        chunktype: *b"gAMA",
+    };
+    let value = serde_json::to_value(chunkref).unwrap();
+    assert_eq!(
+        value,
+        json!({"position": 33, "length": 4, "chunktype": "gAMA"})
+    );
+}
+
+#[test]
+fn types() {
+    assert_eq!(
+        serde_json::to_value(ColourPrimaries::Bt709).unwrap(),
+        "Bt709"
+    );
+    assert_eq!(
+        serde_json::to_value(TransferFunction::Reserved(42)).unwrap(),
+        json!({"Reserved": 42})
+    );
+    let colour_type: PngColourType = serde_json::from_value(json!("IndexedColour")).unwrap();
+    assert_eq!(colour_type, PngColourType::IndexedColour);
+}
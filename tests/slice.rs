@@ -0,0 +1,110 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Borrowing chunks from files in memory with PngSlice
+
+mod common;
+
+use std::io::{Cursor, ErrorKind};
+
+use png_container::chunks::*;
+use png_container::reader::PngReader;
+use png_container::slice::PngSlice;
+use png_container::types::*;
+
+use common::chunk;
+
+#[test]
+fn borrowed_image_data() {
+    let bytes = common::apng();
+    let png = PngSlice::new(&bytes).unwrap();
+    let chunks = png.chunks().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(chunks.len(), 10);
+
+    let idat = chunks[3].idat().unwrap();
+    assert!(std::ptr::eq(idat.0, chunks[3].data));
+    assert_eq!(idat.to_chunk().0, chunks[3].data);
+    assert!(chunks[3].fdat().is_none());
+
+    let fdat = chunks[6].fdat().unwrap();
+    assert_eq!(fdat.sequence_number, 2);
+    assert_eq!(fdat.frame_data, &chunks[6].data[4..]);
+    let fdat = fdat.to_chunk();
+    assert_eq!(fdat.sequence_number, 2);
+    assert_eq!(fdat.frame_data, &chunks[6].data[4..]);
+}
+
+#[test]
+fn borrowed_iccp() {
+    let profile = b"Not really an ICC profile".repeat(10);
+    let iccp = Iccp::new("Fake", PngCompressionMethod::Zlib, &profile);
+    let mut data = b"Fake\0\0".to_vec();
+    data.extend_from_slice(&iccp.compressed_profile);
+    let bytes = common::png(1, 1, 0, 8, &[chunk(b"iCCP", &data)]);
+
+    let png = PngSlice::new(&bytes).unwrap();
+    let chunk = png.chunks().nth(1).unwrap().unwrap();
+    let iccp_ref = chunk.iccp().unwrap();
+    assert_eq!(iccp_ref.name(), "Fake");
+    assert_eq!(iccp_ref.compressed_profile, &iccp.compressed_profile[..]);
+    assert_eq!(iccp_ref.profile().unwrap(), profile);
+
+    let e = iccp_ref.profile_with_limit(100).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::QuotaExceeded);
+
+    let owned = iccp_ref.to_chunk();
+    assert_eq!(owned.name, "Fake");
+    assert_eq!(owned.profile().unwrap(), profile);
+
+    // Parsing into an owned chunk gives the same thing
+    let PngChunkData::Iccp(parsed) = chunk.read_chunk(None, &Default::default()).unwrap() else {
+        panic!("Not an iCCP chunk");
+    };
+    assert_eq!(parsed.compressed_profile, owned.compressed_profile);
+}
+
+#[test]
+fn chunk_at() {
+    let bytes = common::apng();
+    let png = PngSlice::new(&bytes).unwrap();
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    for chunkref in reader.scan_all_chunks().unwrap() {
+        let chunk = png.chunk_at(&chunkref).unwrap();
+        assert_eq!(chunk.chunkref.chunktype, chunkref.chunktype);
+        assert_eq!(chunk.data.len(), chunkref.length as usize);
+        chunk.check_crc().unwrap();
+    }
+}
+
+#[test]
+fn chunk_at_out_of_range() {
+    let bytes = common::apng();
+    let png = PngSlice::new(&bytes).unwrap();
+    for position in [bytes.len() as u64 - 4, u64::MAX - 7, u64::MAX] {
+        let chunkref = PngChunkRef {
+            position,
+            length: 0,
+            chunktype: *b"IEND",
+        };
+        let e = png.chunk_at(&chunkref).unwrap_err();
+        assert!(matches!(
+            png_container::Error::from_io_error(&e),
+            Some(png_container::Error::TruncatedData { offset, .. }) if *offset == position
+        ));
+    }
+}
@@ -0,0 +1,130 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Reading from non-seekable streams with PngStreamReader
+
+mod common;
+
+use std::io::{Cursor, Read};
+
+use png_container::reader::PngReader;
+use png_container::streamreader::PngStreamReader;
+use png_container::types::*;
+
+/// A stream that can only be read forwards
+struct Pipe<R>(R);
+
+impl<R> Read for Pipe<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+#[test]
+fn chunks_match_reader() {
+    let bytes = common::apng();
+
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let chunkrefs = reader.scan_all_chunks().unwrap();
+
+    let mut stream_reader = PngStreamReader::from_stream(Pipe(&bytes[..])).unwrap();
+    for expected in &chunkrefs {
+        assert_eq!(stream_reader.position(), expected.position);
+        let (chunkref, chunk) = stream_reader.next_chunk().unwrap().unwrap();
+        assert_eq!(chunkref.position, expected.position);
+        assert_eq!(chunkref.length, expected.length);
+        assert_eq!(chunkref.chunktype, expected.chunktype);
+        assert_eq!(chunk.chunktype(), expected.chunktype);
+        assert_eq!(
+            format!("{:?}", chunk),
+            format!("{:?}", reader.read_chunk(expected).unwrap())
+        );
+    }
+    assert!(stream_reader.next_chunk().unwrap().is_none());
+
+    assert_eq!(stream_reader.filetype, PngFileType::Apng);
+    assert_eq!((stream_reader.width, stream_reader.height), (2, 2));
+    assert_eq!(stream_reader.bit_depth, 8);
+    assert_eq!(stream_reader.colour_type, PngColourType::Greyscale);
+}
+
+#[test]
+fn iterator() {
+    let bytes = common::apng();
+    let chunktypes = PngStreamReader::from_stream(Pipe(&bytes[..]))
+        .unwrap()
+        .map(|c| c.unwrap().0.chunktype)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        chunktypes,
+        [
+            *b"IHDR", *b"acTL", *b"fcTL", *b"IDAT", *b"IDAT", *b"fcTL", *b"fdAT", *b"fdAT",
+            *b"tEXt", *b"IEND"
+        ]
+    );
+}
+
+#[test]
+fn frames_match_reader() {
+    let bytes = common::apng();
+
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    reader.scan_header_chunks().unwrap();
+    reader.reset_next_chunk_position();
+    let frames = reader.apng_scan_frames().unwrap();
+    assert_eq!(frames.len(), 2);
+
+    let mut stream_reader = PngStreamReader::from_stream(Pipe(&bytes[..])).unwrap();
+    for frame in &frames {
+        let stream_frame = stream_reader.next_frame().unwrap().unwrap();
+        assert_eq!(
+            stream_frame.fctl.sequence_number,
+            frame.fctl.sequence_number
+        );
+        assert_eq!(stream_frame.dats.len(), frame.dats.len());
+        for (dat, chunkref) in stream_frame.dats.iter().zip(&frame.dats) {
+            assert_eq!(dat.chunktype(), chunkref.chunktype);
+            assert_eq!(
+                format!("{:?}", dat),
+                format!("{:?}", reader.read_chunk(chunkref).unwrap())
+            );
+        }
+    }
+    assert!(stream_reader.next_frame().unwrap().is_none());
+}
+
+#[test]
+fn out_of_order_sequence_number() {
+    let bytes = common::png(
+        1,
+        1,
+        0,
+        8,
+        &[
+            common::fctl(0, 1, 1),
+            common::chunk(b"IDAT", &common::zlib(&[0, 0])),
+            common::fctl(2, 1, 1),
+        ],
+    );
+    let mut stream_reader = PngStreamReader::from_stream(Pipe(&bytes[..])).unwrap();
+    let e = stream_reader.next_frame().unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,112 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Stripping ancillary chunks by policy
+
+use std::io::Cursor;
+
+use png_container::chunks::*;
+use png_container::document::PngDocument;
+use png_container::reader::PngReader;
+use png_container::strip::StripPolicy;
+use png_container::types::*;
+
+fn source() -> Vec<u8> {
+    let mut doc = PngDocument::new(Ihdr::new(
+        2,
+        2,
+        8,
+        PngColourType::TrueColour,
+        PngInterlaceMethod::None,
+    ));
+    doc.set_image_data(vec![0x78, 0x9c, 1, 2, 3, 4]);
+    doc.set_gama(Gama::new(0.45455));
+    doc.set_srgb(Srgb {
+        rendering_intent: PngRenderingIntent::Perceptual,
+    });
+    doc.set_trns(Trns::TrueColour {
+        red: 1,
+        green: 2,
+        blue: 3,
+    });
+    doc.set_phys(Phys {
+        x_pixels_per_unit: 2835,
+        y_pixels_per_unit: 2835,
+        unit: PngUnitType::Metre,
+    });
+    doc.add_text(Text::new("Author", "Someone").into());
+    doc.set_time(Time::new(2025, 1, 2, 3, 4, 5));
+    doc.insert(PngChunkData::Unknown {
+        chunktype: *b"prIv",
+        data: vec![1, 2, 3],
+    });
+
+    doc.write_to(Vec::new()).unwrap()
+}
+
+/// Strip a file, returning the chunk types that were kept and removed, and the bytes saved
+fn strip(policy: StripPolicy) -> (Vec<[u8; 4]>, Vec<[u8; 4]>, u64) {
+    let bytes = source();
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let mut out = Vec::new();
+    let report = reader.strip(policy, &mut out).unwrap();
+    assert_eq!(report.bytes_saved, (bytes.len() - out.len()) as u64);
+
+    let mut reader = PngReader::from_stream(Cursor::new(out)).unwrap();
+    let kept = reader
+        .scan_all_chunks()
+        .unwrap()
+        .iter()
+        .map(|c| c.chunktype)
+        .collect();
+    let removed = report.removed.iter().map(|c| c.chunktype).collect();
+    (kept, removed, report.bytes_saved)
+}
+
+#[test]
+fn all() {
+    let (kept, removed, bytes_saved) = strip(StripPolicy::All);
+    assert_eq!(kept, [*b"IHDR", *b"tRNS", *b"IDAT", *b"IEND"]);
+    assert_eq!(
+        removed,
+        [*b"gAMA", *b"sRGB", *b"pHYs", *b"tEXt", *b"tIME", *b"prIv"]
+    );
+    assert_eq!(bytes_saved, 16 + 13 + 21 + 26 + 19 + 15);
+}
+
+#[test]
+fn all_except_colour() {
+    let (kept, removed, _) = strip(StripPolicy::AllExceptColour);
+    assert_eq!(
+        kept,
+        [*b"IHDR", *b"gAMA", *b"sRGB", *b"tRNS", *b"IDAT", *b"IEND"]
+    );
+    assert_eq!(removed, [*b"pHYs", *b"tEXt", *b"tIME", *b"prIv"]);
+}
+
+#[test]
+fn metadata() {
+    let (kept, removed, _) = strip(StripPolicy::Metadata);
+    assert_eq!(
+        kept,
+        [
+            *b"IHDR", *b"gAMA", *b"sRGB", *b"tRNS", *b"pHYs", *b"IDAT", *b"prIv", *b"IEND"
+        ]
+    );
+    assert_eq!(removed, [*b"tEXt", *b"tIME"]);
+}
@@ -0,0 +1,55 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Chunk types that aren't recognised
+
+mod common;
+
+use std::io::Cursor;
+
+use png_container::chunks::*;
+use png_container::reader::PngReader;
+
+use common::chunk;
+
+#[test]
+fn unknown_chunks_round_trip() {
+    let private = chunk(b"prIv", &[1, 2, 3, 0, 0xff]);
+    let public = chunk(b"zzZZ", &[]);
+    let bytes = common::png(1, 1, 0, 8, &[private.clone(), public.clone()]);
+
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let chunks = reader.scan_all_chunks().unwrap();
+    assert_eq!(chunks.len(), 4);
+
+    for (chunkref, raw) in chunks[1..3].iter().zip([private, public]) {
+        let chunk = reader.read_chunk(chunkref).unwrap();
+        match &chunk {
+            PngChunkData::Unknown { chunktype, data } => {
+                assert_eq!(*chunktype, chunkref.chunktype);
+                assert_eq!(data[..], raw[8..raw.len() - 4]);
+            }
+            chunk => panic!("Read {:?}", chunk),
+        }
+
+        // Written back out unchanged, including the CRC
+        let mut out = Cursor::new(Vec::new());
+        chunk.to_stream(&mut out).unwrap();
+        assert_eq!(out.into_inner(), raw);
+    }
+}
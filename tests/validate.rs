@@ -0,0 +1,248 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Whole-file checks
+
+mod common;
+
+use std::io::Cursor;
+
+use png_container::reader::PngReader;
+use png_container::validate::{Code, Severity, is_valid_keyword, validate};
+
+use common::{chunk, fctl_at, fdat};
+
+/// Encode a PNG file from chunks, with an IHDR for a 4x4 image
+fn png(colour_type: u8, bit_depth: u8, chunks: &[Vec<u8>]) -> Vec<u8> {
+    common::png(4, 4, colour_type, bit_depth, chunks)
+}
+
+fn idat() -> Vec<u8> {
+    chunk(b"IDAT", &[0x78, 0x9c, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01])
+}
+
+fn actl(num_frames: u32) -> Vec<u8> {
+    let mut data = num_frames.to_be_bytes().to_vec();
+    data.extend_from_slice(&0_u32.to_be_bytes());
+    chunk(b"acTL", &data)
+}
+
+/// Codes and chunk types of the findings for a file
+fn codes(bytes: &[u8]) -> Vec<(Code, [u8; 4])> {
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    validate(&mut reader)
+        .unwrap()
+        .iter()
+        .map(|f| (f.code, f.chunktype.unwrap()))
+        .collect()
+}
+
+#[test]
+fn valid_file() {
+    let palette = chunk(b"PLTE", &[0, 0, 0, 255, 255, 255]);
+    let bytes = png(
+        3,
+        1,
+        &[
+            palette,
+            chunk(b"tRNS", &[0]),
+            chunk(b"hIST", &[0, 1, 0, 2]),
+            chunk(b"tEXt", b"Title\0A picture"),
+            idat(),
+        ],
+    );
+    assert_eq!(codes(&bytes), []);
+}
+
+#[test]
+fn bit_depth() {
+    assert_eq!(codes(&png(2, 4, &[idat()])), [(Code::BitDepth, *b"IHDR")]);
+    assert_eq!(codes(&png(0, 16, &[idat()])), []);
+}
+
+#[test]
+fn palette() {
+    assert_eq!(
+        codes(&png(3, 8, &[idat()])),
+        [(Code::MissingPalette, *b"IHDR")]
+    );
+
+    let three = chunk(b"PLTE", &[0; 9]);
+    assert_eq!(
+        codes(&png(3, 1, &[three.clone(), idat()])),
+        [(Code::PaletteSize, *b"PLTE")]
+    );
+    assert_eq!(codes(&png(3, 2, &[three.clone(), idat()])), []);
+    assert_eq!(
+        codes(&png(0, 8, &[three.clone(), idat()])),
+        [(Code::UnexpectedPalette, *b"PLTE")]
+    );
+
+    assert_eq!(
+        codes(&png(
+            3,
+            2,
+            &[
+                three,
+                chunk(b"tRNS", &[0; 4]),
+                chunk(b"hIST", &[0; 4]),
+                idat()
+            ]
+        )),
+        [(Code::TrnsLength, *b"tRNS"), (Code::HistLength, *b"hIST")]
+    );
+}
+
+#[test]
+fn sbit() {
+    assert_eq!(
+        codes(&png(2, 8, &[chunk(b"sBIT", &[8, 9, 8]), idat()])),
+        [(Code::SbitValue, *b"sBIT")]
+    );
+    assert_eq!(
+        codes(&png(0, 4, &[chunk(b"sBIT", &[0]), idat()])),
+        [(Code::SbitValue, *b"sBIT")]
+    );
+    assert_eq!(
+        codes(&png(
+            3,
+            1,
+            &[chunk(b"sBIT", &[5, 6, 5]), chunk(b"PLTE", &[0; 3]), idat()]
+        )),
+        []
+    );
+}
+
+#[test]
+fn keywords() {
+    assert!(is_valid_keyword("Title"));
+    assert!(is_valid_keyword("Creation Time"));
+    assert!(is_valid_keyword(&"k".repeat(79)));
+    assert!(!is_valid_keyword(""));
+    assert!(!is_valid_keyword(&"k".repeat(80)));
+    assert!(!is_valid_keyword(" Title"));
+    assert!(!is_valid_keyword("Title "));
+    assert!(!is_valid_keyword("Creation  Time"));
+    assert!(!is_valid_keyword("Tab\tbed"));
+
+    let bytes = png(0, 8, &[chunk(b"tEXt", b" Title\0A picture"), idat()]);
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let findings = validate(&mut reader).unwrap();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].code, Code::Keyword);
+    assert_eq!(findings[0].severity, Severity::Warning);
+    assert_eq!(findings[0].offset, Some(33));
+}
+
+#[test]
+fn animation() {
+    let valid = png(
+        6,
+        8,
+        &[
+            actl(2),
+            fctl_at(0, 4, 4, 0, 0),
+            idat(),
+            fctl_at(1, 2, 2, 2, 2),
+            fdat(2, &[0x78, 0x9c, 0x03, 0x00]),
+        ],
+    );
+    assert_eq!(codes(&valid), []);
+
+    let bytes = png(
+        6,
+        8,
+        &[
+            actl(3),
+            fctl_at(0, 2, 2, 0, 0),
+            idat(),
+            fctl_at(2, 4, 4, 1, 0),
+            fdat(3, &[0x78, 0x9c, 0x03, 0x00]),
+        ],
+    );
+    assert_eq!(
+        codes(&bytes),
+        [
+            (Code::FrameCount, *b"acTL"),
+            (Code::FrameBounds, *b"fcTL"),
+            (Code::FrameBounds, *b"fcTL"),
+            (Code::SequenceNumber, *b"fcTL"),
+        ]
+    );
+}
+
+#[test]
+fn frame_order() {
+    let fdat = |sequence_number| fdat(sequence_number, &[0x78, 0x9c, 0x03, 0x00]);
+
+    // Both fcTL chunks before IDAT, leaving the fdAT chunk without its own fcTL
+    let bytes = png(
+        6,
+        8,
+        &[
+            actl(2),
+            fctl_at(0, 4, 4, 0, 0),
+            fctl_at(1, 4, 4, 0, 0),
+            idat(),
+            fdat(2),
+        ],
+    );
+    assert_eq!(codes(&bytes), [(Code::FrameOrder, *b"fcTL")]);
+
+    // fcTL chunks after IDAT without any fdAT chunks before the next fcTL or IEND
+    let bytes = png(
+        6,
+        8,
+        &[
+            actl(4),
+            fctl_at(0, 4, 4, 0, 0),
+            idat(),
+            fctl_at(1, 4, 4, 0, 0),
+            fctl_at(2, 4, 4, 0, 0),
+            fdat(3),
+            fctl_at(4, 4, 4, 0, 0),
+        ],
+    );
+    let mut reader = PngReader::from_stream(Cursor::new(&bytes)).unwrap();
+    let findings = validate(&mut reader).unwrap();
+    assert_eq!(
+        findings
+            .iter()
+            .map(|f| (f.code, f.offset.unwrap()))
+            .collect::<Vec<_>>(),
+        [(Code::FrameOrder, 111), (Code::FrameOrder, 207)]
+    );
+}
+
+#[test]
+fn idot() {
+    // iDOT positions are relative to the start of the iDOT chunk, which is 12 + 4 + 12 long
+    let segment = |idat_position: u32| {
+        let mut data = 1_u32.to_be_bytes().to_vec();
+        for v in [0_u32, 4, idat_position] {
+            data.extend_from_slice(&v.to_be_bytes());
+        }
+        chunk(b"iDOT", &data)
+    };
+
+    assert_eq!(codes(&png(0, 8, &[segment(28), idat()])), []);
+    assert_eq!(
+        codes(&png(0, 8, &[segment(20), idat()])),
+        [(Code::IdotPosition, *b"iDOT")]
+    );
+}
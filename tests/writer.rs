@@ -0,0 +1,298 @@
+/*
+  png-container
+  Copyright (C) 2025 Ian Tester
+
+  This program is free software: you can redistribute it and/or modify
+  it under the terms of the GNU General Public License as published by
+  the Free Software Foundation, either version 3 of the License, or
+  (at your option) any later version.
+
+  This program is distributed in the hope that it will be useful,
+  but WITHOUT ANY WARRANTY; without even the implied warranty of
+  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+  GNU General Public License for more details.
+
+  You should have received a copy of the GNU General Public License
+  along with this program.  If not, see <http://www.gnu.org/licenses/>.
+*/
+
+//! Writing PNG files with PngWriter and CountingWriter
+
+use std::io::{Cursor, ErrorKind};
+
+use png_container::chunks::*;
+use png_container::countingwriter::CountingWriter;
+use png_container::reader::PngReader;
+use png_container::types::*;
+use png_container::validate::validate;
+use png_container::writer::PngWriter;
+
+fn indexed_ihdr() -> Ihdr {
+    Ihdr::new(
+        3,
+        2,
+        2,
+        PngColourType::IndexedColour,
+        PngInterlaceMethod::None,
+    )
+}
+
+fn chunk_types(reader: &mut PngReader<Cursor<Vec<u8>>>) -> Vec<[u8; 4]> {
+    reader
+        .scan_all_chunks()
+        .unwrap()
+        .iter()
+        .map(|c| c.chunktype)
+        .collect()
+}
+
+#[test]
+fn chunks_in_zones() {
+    let mut writer = PngWriter::new(Cursor::new(Vec::new()), indexed_ihdr()).unwrap();
+    writer.idat_size = 4;
+    writer
+        .add_chunk(Time::new(2025, 1, 2, 3, 4, 5).into())
+        .unwrap();
+    writer
+        .add_chunk(Trns::IndexedColour { values: vec![0] }.into())
+        .unwrap();
+    writer
+        .add_chunk(
+            Plte::new(&[
+                PngPaletteEntry {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                },
+                PngPaletteEntry {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                },
+            ])
+            .into(),
+        )
+        .unwrap();
+    writer.add_chunk(Gama::new(0.45455).into()).unwrap();
+    writer.add_chunk(PngChunkData::Iend).unwrap();
+    writer.write_image_data(&[1, 2, 3]).unwrap();
+    writer.write_image_data(&[4, 5, 6, 7, 8, 9]).unwrap();
+    writer.add_chunk(Text::new("Title", "Test").into()).unwrap();
+
+    let err = writer.add_chunk(Gama::new(1.0).into()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let stream = writer.finish().unwrap();
+    let mut reader = PngReader::from_stream(Cursor::new(stream.into_inner())).unwrap();
+    assert_eq!(
+        chunk_types(&mut reader),
+        [
+            *b"IHDR", *b"gAMA", *b"PLTE", *b"tIME", *b"tRNS", *b"IDAT", *b"IDAT", *b"IDAT",
+            *b"tEXt", *b"IEND"
+        ]
+    );
+    assert_eq!((reader.width, reader.height), (3, 2));
+
+    reader.reset_next_chunk_position();
+    let idat_lengths = reader
+        .scan_chunks_filtered(|ct| ct == *b"IDAT")
+        .unwrap()
+        .iter()
+        .map(|c| c.length)
+        .collect::<Vec<u32>>();
+    assert_eq!(idat_lengths, [4, 4, 1]);
+
+    assert!(reader.check_chunk_order().unwrap().is_empty());
+    assert!(validate(&mut reader).unwrap().is_empty());
+}
+
+#[test]
+fn chunk_refs() {
+    let mut writer = PngWriter::new(Cursor::new(Vec::new()), indexed_ihdr()).unwrap();
+    writer
+        .add_chunk(
+            Plte::new(&[PngPaletteEntry {
+                red: 1,
+                green: 2,
+                blue: 3,
+            }])
+            .into(),
+        )
+        .unwrap();
+    writer.write_image_data(&[0; 10]).unwrap();
+    let chunks = writer.chunks().to_vec();
+    let bytes = writer.finish().unwrap().into_inner();
+
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    let scanned = reader.scan_all_chunks().unwrap();
+    for (written, scanned) in chunks.iter().zip(&scanned) {
+        assert_eq!(
+            (written.position, written.length, written.chunktype),
+            (scanned.position, scanned.length, scanned.chunktype)
+        );
+    }
+}
+
+#[test]
+fn invalid_ihdr() {
+    let ihdr = Ihdr::new(1, 1, 4, PngColourType::TrueColour, PngInterlaceMethod::None);
+    let err = PngWriter::new(Cursor::new(Vec::new()), ihdr).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn no_image_data() {
+    let writer = PngWriter::new(Cursor::new(Vec::new()), indexed_ihdr()).unwrap();
+    assert!(writer.finish().is_err());
+}
+
+/// Chunks that break the ordering rules are rejected before anything is written
+#[test]
+fn order_checked_before_writing() {
+    let mut bytes = Vec::new();
+    let mut writer = PngWriter::new(&mut bytes, indexed_ihdr()).unwrap();
+    writer.add_chunk(Gama::new(0.45455).into()).unwrap();
+    let err = writer.add_chunk(Gama::new(0.5).into()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(writer.chunks().is_empty());
+    assert!(writer.get_ref().is_empty());
+
+    // The writer is still usable
+    writer
+        .add_chunk(
+            Plte::new(&[PngPaletteEntry {
+                red: 1,
+                green: 2,
+                blue: 3,
+            }])
+            .into(),
+        )
+        .unwrap();
+    writer.write_image_data(&[0; 10]).unwrap();
+    writer.add_chunk(Text::new("Title", "Test").into()).unwrap();
+    writer.finish().unwrap();
+
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    assert_eq!(
+        chunk_types(&mut reader),
+        [*b"IHDR", *b"gAMA", *b"PLTE", *b"IDAT", *b"tEXt", *b"IEND"]
+    );
+
+    // Without image data, finish() doesn't write anything
+    let mut bytes = Vec::new();
+    let mut writer = PngWriter::new(&mut bytes, indexed_ihdr()).unwrap();
+    writer.add_chunk(Text::new("Title", "Test").into()).unwrap();
+    let err = writer.finish().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(bytes.is_empty());
+}
+
+/// Only the first frame's fcTL goes before the image data
+#[test]
+fn apng_frames() {
+    let fctl = |sequence_number| {
+        PngChunkData::Fctl(Box::new(Fctl {
+            sequence_number,
+            width: 3,
+            height: 2,
+            x_offset: 0,
+            y_offset: 0,
+            delay_num: 1,
+            delay_den: 10,
+            dispose_op: ApngDisposalOperator::None,
+            blend_op: ApngBlendOperator::Source,
+        }))
+    };
+
+    let ihdr = Ihdr::new(3, 2, 8, PngColourType::Greyscale, PngInterlaceMethod::None);
+    let mut writer = PngWriter::new(Vec::new(), ihdr).unwrap();
+    writer
+        .add_chunk(PngChunkData::Actl(Actl {
+            num_frames: 2,
+            num_plays: 0,
+        }))
+        .unwrap();
+    writer.add_chunk(fctl(0)).unwrap();
+    writer.add_chunk(fctl(1)).unwrap();
+    writer
+        .add_chunk(PngChunkData::Fdat(Box::new(Fdat {
+            sequence_number: 2,
+            frame_data: vec![0x78, 0x9c, 0x03, 0x00],
+        })))
+        .unwrap();
+    writer
+        .write_image_data(&[0x78, 0x9c, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01])
+        .unwrap();
+    let bytes = writer.finish().unwrap();
+
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    assert_eq!(
+        chunk_types(&mut reader),
+        [
+            *b"IHDR", *b"acTL", *b"fcTL", *b"IDAT", *b"fcTL", *b"fdAT", *b"IEND"
+        ]
+    );
+    reader.reset_next_chunk_position();
+    let frames = reader.apng_scan_frames().unwrap();
+    assert_eq!(
+        frames
+            .iter()
+            .map(|f| (f.fctl.sequence_number, f.dats.len()))
+            .collect::<Vec<_>>(),
+        [(0, 1), (1, 1)]
+    );
+    assert!(validate(&mut reader).unwrap().is_empty());
+}
+
+#[test]
+fn non_seekable_stream() {
+    // Vec<u8> can be written to, but not seeked
+    let mut writer = PngWriter::new(Vec::new(), indexed_ihdr()).unwrap();
+    writer
+        .add_chunk(
+            Plte::new(&[PngPaletteEntry {
+                red: 1,
+                green: 2,
+                blue: 3,
+            }])
+            .into(),
+        )
+        .unwrap();
+    writer.write_image_data(&[0; 10]).unwrap();
+    let bytes = writer.finish().unwrap();
+
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    assert_eq!(
+        chunk_types(&mut reader),
+        [*b"IHDR", *b"PLTE", *b"IDAT", *b"IEND"]
+    );
+}
+
+#[test]
+fn counting_writer() {
+    let mut stream = CountingWriter::with_position(Vec::new(), 8);
+    let chunks = [
+        PngChunkData::Ihdr(indexed_ihdr()),
+        Text::new("Title", "Test").into(),
+        PngChunkData::Iend,
+    ]
+    .iter()
+    .map(|chunk| stream.write_chunk(chunk).unwrap())
+    .collect::<Vec<PngChunkRef>>();
+    assert_eq!(stream.position(), 8 + 25 + 22 + 12);
+
+    let mut bytes = vec![0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
+    bytes.extend(stream.into_inner());
+    let mut reader = PngReader::from_stream(Cursor::new(bytes)).unwrap();
+    let scanned = reader.scan_all_chunks().unwrap();
+    assert_eq!(
+        chunks
+            .iter()
+            .map(|c| (c.position, c.length, c.chunktype))
+            .collect::<Vec<_>>(),
+        scanned
+            .iter()
+            .map(|c| (c.position, c.length, c.chunktype))
+            .collect::<Vec<_>>()
+    );
+}